@@ -2,7 +2,9 @@ use crate::cells::helper::fluid_falling_helper;
 use crate::cells::salty_water::SaltyWater;
 use crate::cells::void::Void;
 use crate::cells::water::Water;
-use crate::cells::{CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use crate::cells::{
+    CellGrid, CellRegistry, CellTrait, CellType, Momentum, Prng, TemperatureContext,
+};
 use crate::cs;
 use crate::cs::PointType;
 
@@ -26,12 +28,12 @@ impl CellTrait for BaseWater {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         dim: &mut Prng,
         _: Option<&mut TemperatureContext>,
     ) {
-        if !fluid_falling_helper(self.den(), i, j, container, pal_container, cur, dim, 1) {
+        if !fluid_falling_helper(self.den(), i, j, container, pal_container, cur, dim) {
             let top = cs::xy_to_index(i, j + 1);
             let down = cs::xy_to_index(i, j - 1);
             let r = cs::xy_to_index(i + 1, j);
@@ -64,6 +66,12 @@ impl CellTrait for BaseWater {
     fn den(&self) -> i8 {
         2
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Liquid
+    }
+    fn electrical_conductivity(&self) -> f32 {
+        0.9
+    }
 
     fn shadow_rgba(&self) -> [u8; 4] {
         // Soft shadow for alkaline water as well.
@@ -72,6 +80,12 @@ impl CellTrait for BaseWater {
     fn proton_transfer(&self) -> CellType {
         SaltyWater::id()
     }
+    fn light_transmission(&self) -> f32 {
+        0.98
+    }
+    fn refractive_index(&self) -> f32 {
+        1.33
+    }
     fn name(&self) -> &str {
         "base water"
     }
@@ -0,0 +1,64 @@
+use crate::cs::PointType;
+
+use super::{CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+
+/// Battery — static power source for circuits (see `Simulation::step_electricity`).
+#[derive(Default)]
+pub struct Battery;
+
+impl Battery {
+    pub const fn new() -> Self {
+        Self
+    }
+
+    pub fn boxed() -> Box<Self> {
+        Box::new(Self::new())
+    }
+
+    pub fn id() -> CellType {
+        63
+    }
+}
+
+impl CellTrait for Battery {
+    fn update(
+        &self,
+        _i: PointType,
+        _j: PointType,
+        _cur: usize,
+        _container: &mut CellGrid,
+        _pal_container: &CellRegistry,
+        _prng: &mut Prng,
+        _temp_context: Option<&mut TemperatureContext>,
+    ) {
+        // Static; charge is spread by `Simulation::step_electricity`.
+    }
+
+    fn den(&self) -> i8 {
+        20
+    }
+
+    fn stat(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        "battery"
+    }
+
+    fn id(&self) -> CellType {
+        Self::id()
+    }
+
+    fn electrical_conductivity(&self) -> f32 {
+        1.0
+    }
+
+    fn electric_source(&self) -> bool {
+        true
+    }
+
+    fn display_color(&self) -> [u8; 3] {
+        [60, 180, 90]
+    }
+}
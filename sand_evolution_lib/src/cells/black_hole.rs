@@ -1,6 +1,6 @@
 use crate::cs::{self, PointType};
 
-use super::{void::Void, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use super::{void::Void, CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
 
 pub struct BlackHole;
 
@@ -24,7 +24,7 @@ impl CellTrait for BlackHole {
         i: PointType,
         j: PointType,
         _cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         _pal_container: &CellRegistry,
         _prng: &mut Prng,
         _: Option<&mut TemperatureContext>,
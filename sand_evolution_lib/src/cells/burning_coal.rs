@@ -15,17 +15,17 @@ impl BurningCoal {
     }
 }
 
-fn has_adjacent_air(i: PointType, j: PointType, container: &[CellType]) -> bool {
+fn has_adjacent_air(i: PointType, j: PointType, container: &CellGrid) -> bool {
     if i > 0 && container[cs::xy_to_index(i - 1, j)] == Void::id() {
         return true;
     }
-    if i + 1 < cs::SECTOR_SIZE.x && container[cs::xy_to_index(i + 1, j)] == Void::id() {
+    if i + 1 < cs::sector_size().x && container[cs::xy_to_index(i + 1, j)] == Void::id() {
         return true;
     }
     if j > 0 && container[cs::xy_to_index(i, j - 1)] == Void::id() {
         return true;
     }
-    if j + 1 < cs::SECTOR_SIZE.y && container[cs::xy_to_index(i, j + 1)] == Void::id() {
+    if j + 1 < cs::sector_size().y && container[cs::xy_to_index(i, j + 1)] == Void::id() {
         return true;
     }
     false
@@ -37,7 +37,7 @@ impl CellTrait for BurningCoal {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         mut temp_context: Option<&mut TemperatureContext>,
@@ -59,7 +59,7 @@ impl CellTrait for BurningCoal {
                 sum += temp_ctx.get_temp(i - 1, j);
                 n += 1.0;
             }
-            if i + 1 < cs::SECTOR_SIZE.x {
+            if i + 1 < cs::sector_size().x {
                 sum += temp_ctx.get_temp(i + 1, j);
                 n += 1.0;
             }
@@ -67,7 +67,7 @@ impl CellTrait for BurningCoal {
                 sum += temp_ctx.get_temp(i, j - 1);
                 n += 1.0;
             }
-            if j + 1 < cs::SECTOR_SIZE.y {
+            if j + 1 < cs::sector_size().y {
                 sum += temp_ctx.get_temp(i, j + 1);
                 n += 1.0;
             }
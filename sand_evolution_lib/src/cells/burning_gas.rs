@@ -1,8 +1,10 @@
-use crate::cs::{self, PointType};
+use crate::cs::PointType;
 
 use super::{
-    fire, helper::fluid_flying_helper, void::Void, CellRegistry, CellTrait, CellType, Prng,
-    TemperatureContext,
+    fire,
+    helper::{fluid_flying_helper, neighbour},
+    void::Void,
+    CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext,
 };
 
 pub struct BurningGas;
@@ -23,7 +25,7 @@ impl CellTrait for BurningGas {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         mut temp_context: Option<&mut TemperatureContext>,
@@ -36,10 +38,11 @@ impl CellTrait for BurningGas {
             temp_ctx.add_temp(i - 1, j, 3.0);
         }
 
-        let topl = cs::xy_to_index(i - 1, j + 1);
-        let topr = cs::xy_to_index(i + 1, j + 1);
+        let gravity = pal_container.gravity.rising(prng);
+        let topl = neighbour(gravity, i, j, -1, 1);
+        let topr = neighbour(gravity, i, j, 1, 1);
 
-        let top = cs::xy_to_index(i, j + 1);
+        let top = neighbour(gravity, i, j, 0, 1);
         let arr = [top, topl, topr];
         let cc = arr[(prng.next() % 3) as usize];
         let top_v = container[cc];
@@ -77,12 +80,21 @@ impl CellTrait for BurningGas {
     fn den(&self) -> i8 {
         -10
     }
+    fn thermal_conductivity(&self) -> f32 {
+        0.3
+    }
+    fn convection_factor(&self) -> f32 {
+        1.0
+    }
     fn casts_shadow(&self) -> bool {
         false
     }
     fn needs_temp(&self) -> bool {
         true
     }
+    fn wind_drag(&self) -> f32 {
+        1.0
+    }
     fn name(&self) -> &str {
         "burning gas"
     }
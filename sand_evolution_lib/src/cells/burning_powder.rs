@@ -1,6 +1,9 @@
 use super::{helper::try_spawn_smoke, TemperatureContext, *};
 use crate::cs::{self, PointType};
 
+/// Pressure added when a burning powder grain bursts.
+const BLAST_PRESSURE: f32 = 60.0;
+
 pub struct BurningPowder;
 impl BurningPowder {
     pub const fn new() -> Self {
@@ -21,13 +24,14 @@ impl CellTrait for BurningPowder {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         mut temp_context: Option<&mut TemperatureContext>,
     ) {
         if !sand_falling_helper(self.den(), i, j, container, pal_container, cur, prng) {
             if let Some(temp_ctx) = temp_context.as_deref_mut() {
+                let size = cs::sector_size();
                 temp_ctx.add_temp(i, j, 400.0);
                 
                 temp_ctx.add_temp(i, j + 1, 300.0);
@@ -38,26 +42,26 @@ impl CellTrait for BurningPowder {
                 if i > 0 && j > 0 {
                     temp_ctx.add_temp(i - 1, j - 1, 200.0);
                 }
-                if i > 0 && j + 1 < cs::SECTOR_SIZE.y {
+                if i > 0 && j + 1 < size.y {
                     temp_ctx.add_temp(i - 1, j + 1, 200.0);
                 }
-                if i + 1 < cs::SECTOR_SIZE.x && j > 0 {
+                if i + 1 < size.x && j > 0 {
                     temp_ctx.add_temp(i + 1, j - 1, 200.0);
                 }
-                if i + 1 < cs::SECTOR_SIZE.x && j + 1 < cs::SECTOR_SIZE.y {
+                if i + 1 < size.x && j + 1 < size.y {
                     temp_ctx.add_temp(i + 1, j + 1, 200.0);
                 }
                 
                 if i > 1 {
                     temp_ctx.add_temp(i - 2, j, 150.0);
                 }
-                if i + 2 < cs::SECTOR_SIZE.x {
+                if i + 2 < size.x {
                     temp_ctx.add_temp(i + 2, j, 150.0);
                 }
                 if j > 1 {
                     temp_ctx.add_temp(i, j - 2, 150.0);
                 }
-                if j + 2 < cs::SECTOR_SIZE.y {
+                if j + 2 < size.y {
                     temp_ctx.add_temp(i, j + 2, 150.0);
                 }
             }
@@ -86,6 +90,7 @@ impl CellTrait for BurningPowder {
 
             if prng.next() > 120 {
                 if let Some(temp_ctx) = temp_context.as_deref_mut() {
+                    let size = cs::sector_size();
                     temp_ctx.add_temp(i, j, 500.0);
                     temp_ctx.add_temp(i, j + 1, 500.0);
                     temp_ctx.add_temp(i, j - 1, 500.0);
@@ -94,27 +99,29 @@ impl CellTrait for BurningPowder {
                     if i > 0 && j > 0 {
                         temp_ctx.add_temp(i - 1, j - 1, 350.0);
                     }
-                    if i > 0 && j + 1 < cs::SECTOR_SIZE.y {
+                    if i > 0 && j + 1 < size.y {
                         temp_ctx.add_temp(i - 1, j + 1, 350.0);
                     }
-                    if i + 1 < cs::SECTOR_SIZE.x && j > 0 {
+                    if i + 1 < size.x && j > 0 {
                         temp_ctx.add_temp(i + 1, j - 1, 350.0);
                     }
-                    if i + 1 < cs::SECTOR_SIZE.x && j + 1 < cs::SECTOR_SIZE.y {
+                    if i + 1 < size.x && j + 1 < size.y {
                         temp_ctx.add_temp(i + 1, j + 1, 350.0);
                     }
                     if i > 1 {
                         temp_ctx.add_temp(i - 2, j, 250.0);
                     }
-                    if i + 2 < cs::SECTOR_SIZE.x {
+                    if i + 2 < size.x {
                         temp_ctx.add_temp(i + 2, j, 250.0);
                     }
                     if j > 1 {
                         temp_ctx.add_temp(i, j - 2, 250.0);
                     }
-                    if j + 2 < cs::SECTOR_SIZE.y {
+                    if j + 2 < size.y {
                         temp_ctx.add_temp(i, j + 2, 250.0);
                     }
+                    // The burst itself: throws loose cells outward, see `Simulation::step_pressure`.
+                    temp_ctx.add_pressure(i, j, BLAST_PRESSURE);
                 }
                 try_spawn_smoke(i, j, container, prng, 6);
                 container[cur] = Void::id();
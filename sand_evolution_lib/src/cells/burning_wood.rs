@@ -19,7 +19,7 @@ impl CellTrait for Cell {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         _pal_container: &CellRegistry,
         prng: &mut Prng,
         mut temp_context: Option<&mut TemperatureContext>,
@@ -34,7 +34,7 @@ impl CellTrait for Cell {
                 sum += temp_ctx.get_temp(i - 1, j);
                 n += 1.0;
             }
-            if i + 1 < cs::SECTOR_SIZE.x {
+            if i + 1 < cs::sector_size().x {
                 sum += temp_ctx.get_temp(i + 1, j);
                 n += 1.0;
             }
@@ -42,7 +42,7 @@ impl CellTrait for Cell {
                 sum += temp_ctx.get_temp(i, j - 1);
                 n += 1.0;
             }
-            if j + 1 < cs::SECTOR_SIZE.y {
+            if j + 1 < cs::sector_size().y {
                 sum += temp_ctx.get_temp(i, j + 1);
                 n += 1.0;
             }
@@ -72,14 +72,14 @@ impl CellTrait for Cell {
             let mut candidates: [usize; 3] = [0; 3];
             let mut n = 0usize;
 
-            if j + 1 < cs::SECTOR_SIZE.y {
+            if j + 1 < cs::sector_size().y {
                 candidates[n] = cs::xy_to_index(i, j + 1);
                 n += 1;
                 if i > 0 {
                     candidates[n] = cs::xy_to_index(i - 1, j + 1);
                     n += 1;
                 }
-                if i + 1 < cs::SECTOR_SIZE.x {
+                if i + 1 < cs::sector_size().x {
                     candidates[n] = cs::xy_to_index(i + 1, j + 1);
                     n += 1;
                 }
@@ -97,6 +97,18 @@ impl CellTrait for Cell {
                 }
             }
         }
+        if container.has_aux() {
+            // Same average life as the dice below, but every cell gets all of it.
+            const BURN_UPDATES: u16 = 1260;
+            let burnt = container.aux(cur) + 1;
+            if burnt < BURN_UPDATES {
+                container.set_aux(cur, burnt);
+                return;
+            }
+            try_spawn_smoke(i, j, container, prng, 1);
+            container[cur] = Coal::id();
+            return;
+        }
         if prng.next() > 25 {
             return;
         }
@@ -118,6 +130,9 @@ impl CellTrait for Cell {
     fn needs_temp(&self) -> bool {
         true
     }
+    fn blast_resistance(&self) -> f32 {
+        25.0
+    }
     fn name(&self) -> &str {
         "burning wood"
     }
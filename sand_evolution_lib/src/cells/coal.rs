@@ -2,8 +2,8 @@ use crate::cs::PointType;
 use crate::cs;
 
 use super::{
-    burning_coal::BurningCoal, burning_gas::BurningGas, helper::sand_falling_helper, CellRegistry,
-    CellTrait, CellType, Prng, TemperatureContext, void::Void,
+    burning_coal::BurningCoal, burning_gas::BurningGas, helper::sand_falling_helper, CellGrid,
+    CellRegistry, CellTrait, CellType, Momentum, Prng, TemperatureContext, void::Void,
 };
 
 pub struct Coal;
@@ -19,17 +19,17 @@ impl Coal {
     }
 }
 
-fn has_adjacent_air(i: PointType, j: PointType, container: &[CellType]) -> bool {
+fn has_adjacent_air(i: PointType, j: PointType, container: &CellGrid) -> bool {
     if i > 0 && container[cs::xy_to_index(i - 1, j)] == Void::id() {
         return true;
     }
-    if i + 1 < cs::SECTOR_SIZE.x && container[cs::xy_to_index(i + 1, j)] == Void::id() {
+    if i + 1 < cs::sector_size().x && container[cs::xy_to_index(i + 1, j)] == Void::id() {
         return true;
     }
     if j > 0 && container[cs::xy_to_index(i, j - 1)] == Void::id() {
         return true;
     }
-    if j + 1 < cs::SECTOR_SIZE.y && container[cs::xy_to_index(i, j + 1)] == Void::id() {
+    if j + 1 < cs::sector_size().y && container[cs::xy_to_index(i, j + 1)] == Void::id() {
         return true;
     }
     false
@@ -41,7 +41,7 @@ impl CellTrait for Coal {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         dim: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -61,6 +61,9 @@ impl CellTrait for Coal {
     fn den(&self) -> i8 {
         10
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Powder
+    }
     fn burnable(&self) -> u8 {
         BurningCoal::id()
     }
@@ -1,8 +1,8 @@
 use crate::cs::PointType;
 
-use super::{CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use super::{CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
 
-/// Copper — static solid with very high thermal conductivity.
+/// Copper — static solid with very high thermal and electrical conductivity.
 pub struct Copper;
 
 impl Copper {
@@ -26,12 +26,13 @@ impl CellTrait for Copper {
         _i: PointType,
         _j: PointType,
         _cur: usize,
-        _container: &mut [CellType],
+        _container: &mut CellGrid,
         _pal_container: &CellRegistry,
         _prng: &mut Prng,
         _temp_context: Option<&mut TemperatureContext>,
     ) {
-        // Static, no local behavior. Heat transport handled by global diffusion.
+        // Static, no local behavior. Heat transport handled by global diffusion
+        // (weighted by `thermal_conductivity`).
     }
 
     fn den(&self) -> i8 {
@@ -55,6 +56,11 @@ impl CellTrait for Copper {
         2.5
     }
 
+    /// Nearly lossless wire: a few hundred cells of copper still carry most of the charge.
+    fn electrical_conductivity(&self) -> f32 {
+        0.998
+    }
+
     /// Warm copper-like shadow tint (slightly reddish, not too bright).
     fn shadow_rgba(&self) -> [u8; 4] {
         [200, 150, 120, 255]
@@ -1,7 +1,10 @@
 use crate::cs::{self, PointType};
 
 use super::{
-    void::Void, water::Water, CellRegistry, CellTrait, CellType, Prng, TemperatureContext,
+    helper::{change_phase, neighbour},
+    void::Void,
+    water::Water,
+    CellGrid, CellRegistry, CellTrait, CellType, Gravity, Prng, TemperatureContext,
 };
 
 pub struct CrushedIce;
@@ -22,7 +25,7 @@ impl CellTrait for CrushedIce {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -32,21 +35,19 @@ impl CellTrait for CrushedIce {
 
             if prng.next() > 200 {
                 if temperature > 0.0 {
-                    container[cur] = Water::id();
-                    const MELT_COOLING: f32 = 3.0;
-                    temp_ctx.add_temp(i, j, -MELT_COOLING);
-                    temp_ctx.add_temp(i, j + 1, -MELT_COOLING);
-                    temp_ctx.add_temp(i, j - 1, -MELT_COOLING);
-                    temp_ctx.add_temp(i + 1, j, -MELT_COOLING);
-                    temp_ctx.add_temp(i - 1, j, -MELT_COOLING);
+                    change_phase(i, j, cur, Water::id(), container, pal_container, temp_ctx);
                     return;
                 }
             }
         }
 
         // Special logic for crushed ice: floats on water (density 0), but falls through Void
+        let gravity = pal_container.gravity;
+        if gravity == Gravity::Zero {
+            return;
+        }
         // Check cell below
-        let down = cs::xy_to_index(i, j - 1);
+        let down = neighbour(gravity, i, j, 0, -1);
         let down_v = container[down] as usize;
         let down_c = &pal_container.pal[down_v];
 
@@ -69,7 +70,7 @@ impl CellTrait for CrushedIce {
         for k in 0..2 {
             match selected_order[k] {
                 0 => {
-                    let dr = cs::xy_to_index(i + 1, j - 1);
+                    let dr = neighbour(gravity, i, j, 1, -1);
                     let dr_v = container[dr] as usize;
                     let dr_c = &pal_container.pal[dr_v];
                     if dr_v == Void::id() as usize || (dr_c.den() < self.den() && !dr_c.stat()) {
@@ -78,7 +79,7 @@ impl CellTrait for CrushedIce {
                     }
                 }
                 1 => {
-                    let dl = cs::xy_to_index(i - 1, j - 1);
+                    let dl = neighbour(gravity, i, j, -1, -1);
                     let dl_v = container[dl] as usize;
                     let dl_c = &pal_container.pal[dl_v];
                     if dl_v == Void::id() as usize || (dl_c.den() < self.den() && !dl_c.stat()) {
@@ -119,9 +120,15 @@ impl CellTrait for CrushedIce {
     fn heat_proof(&self) -> u8 {
         200
     }
+    fn heat_capacity(&self) -> f32 {
+        2.0
+    }
     fn needs_temp(&self) -> bool {
         true
     }
+    fn blast_resistance(&self) -> f32 {
+        10.0
+    }
     fn name(&self) -> &str {
         "crushed ice"
     }
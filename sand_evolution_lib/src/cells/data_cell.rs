@@ -0,0 +1,440 @@
+//! Materials defined in TOML instead of Rust.
+//!
+//! ```toml
+//! [[material]]
+//! name = "slime"
+//! movement = "liquid"          # powder | liquid | gas | static
+//! density = 3                  # compared with `den()` of neighbours, like built-in cells
+//! color = [90, 200, 60]
+//! thermal_conductivity = 0.4   # default 0.5; air is 0.3, copper 2.5
+//! convection = 0.5             # share of heat that rises, default 1.0 for gases, else 0
+//! heat_capacity = 3.0          # default 1.0 (stone); water is 4.0
+//! latent_heat = 40.0           # energy stored in this phase, see `CellTrait::latent_heat`
+//! electrical_conductivity = 0.9  # share of charge passed on, default 0 (insulator); copper 0.998
+//! light_transmission = 0.9     # share of a laser beam let through, default 1.0 for gases, else 0
+//! refractive_index = 1.4       # default 1.0; water is 1.33
+//! wind_drag = 0.5              # how much the wind carries it, default 1.0 for gases, else 0
+//! viscosity = 4                # liquids: spreads sideways on 1 update in 4, default 1 (water)
+//! above = { temperature = 120.0, into = "steam" }
+//! below = { temperature = -10.0, into = "ice", chance = 20 }
+//! burns_into = "fire"          # what fire / plasma / lasers turn it into
+//! heats_into = "smoke"
+//! heat_proof = 200
+//! acid_product = "gas"
+//! dissolves_into = "salty water"
+//!
+//! [[reaction]]                 # see `reaction::Reaction`
+//! a = "slime"
+//! b = "salt"
+//! a_into = "void"              # default: unchanged
+//! b_into = "salty water"
+//! chance = 40                  # out of 255 per check, default 32
+//! heat = -3.0
+//! pressure = 40.0              # blast pressure released, default 0
+//! max_temperature = 80.0       # optional window, also `min_temperature`
+//! ```
+//!
+//! Products can name built-in cells or other materials from the same file. Names are saved into
+//! map PNGs, so they must be Latin-1 text without line breaks.
+
+use std::collections::HashMap;
+
+use crate::cs::PointType;
+
+use super::{
+    helper::{change_phase, fluid_falling_helper, fluid_flying_helper, sand_falling_helper},
+    reaction::{self, Reaction},
+    void::Void,
+    CellGrid, CellRegistry, CellTrait, CellType, Momentum, Prng, TemperatureContext,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Movement {
+    Powder,
+    Liquid,
+    Gas,
+    Static,
+}
+
+#[derive(serde::Deserialize)]
+struct MaterialsToml {
+    #[serde(default)]
+    material: Vec<MaterialToml>,
+    #[serde(default)]
+    reaction: Vec<ReactionToml>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReactionToml {
+    a: String,
+    b: String,
+    a_into: Option<String>,
+    b_into: Option<String>,
+    #[serde(default = "default_phase_chance")]
+    chance: u8,
+    #[serde(default)]
+    heat: f32,
+    #[serde(default)]
+    pressure: f32,
+    min_temperature: Option<f32>,
+    max_temperature: Option<f32>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialToml {
+    name: String,
+    movement: Movement,
+    density: i8,
+    color: [u8; 3],
+    #[serde(default = "default_thermal_conductivity")]
+    thermal_conductivity: f32,
+    convection: Option<f32>,
+    #[serde(default = "default_heat_capacity")]
+    heat_capacity: f32,
+    #[serde(default)]
+    latent_heat: f32,
+    #[serde(default)]
+    electrical_conductivity: f32,
+    light_transmission: Option<f32>,
+    #[serde(default = "default_refractive_index")]
+    refractive_index: f32,
+    wind_drag: Option<f32>,
+    #[serde(default = "default_viscosity")]
+    viscosity: u8,
+    above: Option<PhaseChangeToml>,
+    below: Option<PhaseChangeToml>,
+    burns_into: Option<String>,
+    heats_into: Option<String>,
+    #[serde(default = "default_heat_proof")]
+    heat_proof: u8,
+    ignition_temperature: Option<f32>,
+    acid_product: Option<String>,
+    dissolves_into: Option<String>,
+    casts_shadow: Option<bool>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PhaseChangeToml {
+    temperature: f32,
+    into: String,
+    /// Per-update chance out of 255 once past the temperature.
+    #[serde(default = "default_phase_chance")]
+    chance: u8,
+}
+
+fn default_thermal_conductivity() -> f32 {
+    0.5
+}
+
+fn default_heat_capacity() -> f32 {
+    1.0
+}
+
+fn default_refractive_index() -> f32 {
+    1.0
+}
+
+fn default_viscosity() -> u8 {
+    1
+}
+
+fn default_heat_proof() -> u8 {
+    1
+}
+
+fn default_phase_chance() -> u8 {
+    32
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PhaseChange {
+    pub temperature: f32,
+    pub into: CellType,
+    pub chance: u8,
+}
+
+pub struct DataCell {
+    id: CellType,
+    name: String,
+    pub movement: Movement,
+    pub density: i8,
+    pub color: [u8; 3],
+    pub thermal_conductivity: f32,
+    pub convection: f32,
+    pub heat_capacity: f32,
+    pub latent_heat: f32,
+    pub electrical_conductivity: f32,
+    pub light_transmission: f32,
+    pub refractive_index: f32,
+    pub wind_drag: f32,
+    pub viscosity: u8,
+    /// Turns into another cell when hotter than `temperature`.
+    pub above: Option<PhaseChange>,
+    /// Turns into another cell when colder than `temperature`.
+    pub below: Option<PhaseChange>,
+    pub burns_into: CellType,
+    pub heats_into: CellType,
+    pub heat_proof: u8,
+    pub ignition_temperature: Option<f32>,
+    pub acid_product: CellType,
+    pub dissolves_into: CellType,
+    pub casts_shadow: bool,
+}
+
+impl CellTrait for DataCell {
+    fn update(
+        &self,
+        i: PointType,
+        j: PointType,
+        cur: usize,
+        container: &mut CellGrid,
+        pal_container: &CellRegistry,
+        prng: &mut Prng,
+        temp_context: Option<&mut TemperatureContext>,
+    ) {
+        if let Some(temp_ctx) = temp_context {
+            let temperature = temp_ctx.get_temp(i, j);
+            if let Some(change) = self.above {
+                if temperature > change.temperature && prng.next() < change.chance {
+                    change_phase(i, j, cur, change.into, container, pal_container, temp_ctx);
+                    return;
+                }
+            }
+            if let Some(change) = self.below {
+                if temperature < change.temperature && prng.next() < change.chance {
+                    change_phase(i, j, cur, change.into, container, pal_container, temp_ctx);
+                    return;
+                }
+            }
+        }
+
+        match self.movement {
+            Movement::Powder => {
+                sand_falling_helper(self.density, i, j, container, pal_container, cur, prng);
+            }
+            Movement::Liquid => {
+                fluid_falling_helper(self.density, i, j, container, pal_container, cur, prng);
+            }
+            Movement::Gas => {
+                fluid_flying_helper(self.density, i, j, container, pal_container, cur, prng);
+            }
+            Movement::Static => {}
+        }
+    }
+    fn den(&self) -> i8 {
+        self.density
+    }
+    fn stat(&self) -> bool {
+        self.movement == Movement::Static
+    }
+    fn momentum(&self) -> Momentum {
+        match self.movement {
+            Movement::Powder => Momentum::Powder,
+            Movement::Liquid => Momentum::Liquid,
+            Movement::Gas | Movement::Static => Momentum::None,
+        }
+    }
+    fn casts_shadow(&self) -> bool {
+        self.casts_shadow
+    }
+    fn burnable(&self) -> CellType {
+        self.burns_into
+    }
+    fn proton_transfer(&self) -> CellType {
+        self.acid_product
+    }
+    fn dissolve(&self) -> CellType {
+        self.dissolves_into
+    }
+    fn heatable(&self) -> CellType {
+        self.heats_into
+    }
+    fn heat_proof(&self) -> u8 {
+        self.heat_proof
+    }
+    fn ignition_temperature(&self) -> Option<f32> {
+        self.ignition_temperature
+    }
+    fn thermal_conductivity(&self) -> f32 {
+        self.thermal_conductivity
+    }
+    fn convection_factor(&self) -> f32 {
+        self.convection
+    }
+    fn heat_capacity(&self) -> f32 {
+        self.heat_capacity
+    }
+    fn latent_heat(&self) -> f32 {
+        self.latent_heat
+    }
+    fn electrical_conductivity(&self) -> f32 {
+        self.electrical_conductivity
+    }
+    fn light_transmission(&self) -> f32 {
+        self.light_transmission
+    }
+    fn refractive_index(&self) -> f32 {
+        self.refractive_index
+    }
+    fn wind_drag(&self) -> f32 {
+        self.wind_drag
+    }
+    fn viscosity(&self) -> u8 {
+        self.viscosity
+    }
+    fn needs_temp(&self) -> bool {
+        self.above.is_some() || self.below.is_some()
+    }
+    fn is_data_cell(&self) -> bool {
+        true
+    }
+    fn display_color(&self) -> [u8; 3] {
+        self.color
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn id(&self) -> CellType {
+        self.id
+    }
+}
+
+/// Parses a materials file and registers every material and reaction into `registry`.
+///
+/// A material whose name was registered by an earlier file replaces that cell in its slot (so
+/// reloading a file updates it in place), and names of built-in cells are an error; new names
+/// take free palette slots. Returns the material ids in file order.
+pub fn register_materials(
+    registry: &mut CellRegistry,
+    toml_text: &str,
+) -> Result<Vec<CellType>, String> {
+    let parsed: MaterialsToml = toml::from_str(toml_text).map_err(|e| e.to_string())?;
+    let void_name = registry.pal[Void::id() as usize].name().to_owned();
+
+    // Assign ids first so materials can refer to each other.
+    let mut ids: HashMap<&str, CellType> = HashMap::new();
+    let mut free = (1..=255u8).filter(|&id| registry.pal[id as usize].id() == Void::id());
+    for m in &parsed.material {
+        // Names end up in the id=name table of map PNGs, a Latin-1 tEXt chunk with one line
+        // per cell.
+        let text = m.name.chars().all(|c| c <= '\u{ff}' && !c.is_control());
+        if m.name.is_empty() || m.name == void_name || !text {
+            return Err(format!("invalid material name {:?}", m.name));
+        }
+        if ids.contains_key(m.name.as_str()) {
+            return Err(format!("material {:?} is defined twice", m.name));
+        }
+        let id = match registry.dict.get(&m.name) {
+            Some(&id) if registry.pal[id as usize].is_data_cell() => id,
+            Some(_) => return Err(format!("material {:?} is a built-in cell", m.name)),
+            None => free
+                .next()
+                .ok_or_else(|| format!("no free cell slot for {:?}", m.name))?,
+        };
+        ids.insert(&m.name, id);
+    }
+
+    let resolve_name = |name: &str| -> Result<CellType, String> {
+        if name == void_name {
+            return Ok(Void::id());
+        }
+        ids.get(name)
+            .or_else(|| registry.dict.get(name))
+            .copied()
+            .ok_or_else(|| format!("unknown cell {name:?}"))
+    };
+    let resolve = |name: &Option<String>| -> Result<CellType, String> {
+        name.as_deref().map_or(Ok(Void::id()), resolve_name)
+    };
+    let phase = |change: &Option<PhaseChangeToml>| -> Result<Option<PhaseChange>, String> {
+        change
+            .as_ref()
+            .map(|c| {
+                Ok(PhaseChange {
+                    temperature: c.temperature,
+                    into: resolve(&Some(c.into.clone()))?,
+                    chance: c.chance,
+                })
+            })
+            .transpose()
+    };
+
+    let mut cells = Vec::with_capacity(parsed.material.len());
+    for m in &parsed.material {
+        cells.push(DataCell {
+            id: ids[m.name.as_str()],
+            name: m.name.clone(),
+            movement: m.movement,
+            density: m.density,
+            color: m.color,
+            thermal_conductivity: m.thermal_conductivity,
+            convection: m.convection.unwrap_or(match m.movement {
+                Movement::Gas => 1.0,
+                _ => 0.0,
+            }),
+            heat_capacity: m.heat_capacity,
+            latent_heat: m.latent_heat,
+            electrical_conductivity: m.electrical_conductivity.clamp(0.0, 1.0),
+            light_transmission: m
+                .light_transmission
+                .unwrap_or(match m.movement {
+                    Movement::Gas => 1.0,
+                    _ => 0.0,
+                })
+                .clamp(0.0, 1.0),
+            refractive_index: m.refractive_index.max(1.0),
+            wind_drag: m
+                .wind_drag
+                .unwrap_or(match m.movement {
+                    Movement::Gas => 1.0,
+                    _ => 0.0,
+                })
+                .clamp(0.0, 1.0),
+            viscosity: m.viscosity.max(1),
+            above: phase(&m.above)?,
+            below: phase(&m.below)?,
+            burns_into: resolve(&m.burns_into)?,
+            heats_into: resolve(&m.heats_into)?,
+            heat_proof: m.heat_proof,
+            ignition_temperature: m.ignition_temperature,
+            acid_product: resolve(&m.acid_product)?,
+            dissolves_into: resolve(&m.dissolves_into)?,
+            casts_shadow: m
+                .casts_shadow
+                .unwrap_or(!matches!(m.movement, Movement::Gas)),
+        });
+    }
+
+    let mut reactions = Vec::with_capacity(parsed.reaction.len());
+    for r in &parsed.reaction {
+        let (a, b) = (resolve_name(&r.a)?, resolve_name(&r.b)?);
+        let a_into = r.a_into.as_deref().map_or(Ok(a), resolve_name)?;
+        let b_into = r.b_into.as_deref().map_or(Ok(b), resolve_name)?;
+        let reaction = Reaction::new(r.chance, a_into, b_into)
+            .with_heat(r.heat)
+            .with_pressure(r.pressure)
+            .with_temperature_window(
+                r.min_temperature.unwrap_or(f32::NEG_INFINITY),
+                r.max_temperature.unwrap_or(f32::INFINITY),
+            );
+        reactions.push((a, b, reaction));
+    }
+
+    // Nothing is registered until the whole file resolved.
+    let mut registered = Vec::with_capacity(cells.len());
+    for cell in cells {
+        let id = cell.id;
+        reaction::add_property_reactions(&mut registry.reactions, &cell);
+        registry.dict.insert(cell.name.clone(), id);
+        registry.pal[id as usize] = Box::new(cell);
+        registered.push(id);
+    }
+    for (a, b, reaction) in reactions {
+        registry.reactions.add(a, b, reaction);
+    }
+    Ok(registered)
+}
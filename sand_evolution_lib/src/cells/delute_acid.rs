@@ -3,7 +3,9 @@ use crate::cells::helper::fluid_falling_helper;
 use crate::cells::steam::Steam;
 use crate::cells::void::Void;
 use crate::cells::water::Water;
-use crate::cells::{CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use crate::cells::{
+    CellGrid, CellRegistry, CellTrait, CellType, Momentum, Prng, TemperatureContext,
+};
 use crate::cs;
 use crate::cs::PointType;
 
@@ -27,7 +29,7 @@ impl CellTrait for DeluteAcid {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         dim: &mut Prng,
         mut temp_context: Option<&mut TemperatureContext>,
@@ -37,23 +39,20 @@ impl CellTrait for DeluteAcid {
 
             if temperature >= 100.0 {
                 if dim.next() < 100 {
+                    // Either way the water part boils off.
+                    let boiled = pal_container.pal[Steam::id() as usize].latent_heat();
+                    temp_ctx.add_heat(i, j, self.latent_heat() - boiled);
                     if dim.next() < 128 {
                         container[cur] = Steam::id();
                     } else {
                         container[cur] = Acid::id();
                     }
-                    const EVAP_COOLING: f32 = 45.0;
-                    temp_ctx.add_temp(i, j, -EVAP_COOLING);
-                    temp_ctx.add_temp(i, j + 1, -EVAP_COOLING * 0.5);
-                    temp_ctx.add_temp(i, j - 1, -EVAP_COOLING * 0.5);
-                    temp_ctx.add_temp(i + 1, j, -EVAP_COOLING * 0.5);
-                    temp_ctx.add_temp(i - 1, j, -EVAP_COOLING * 0.5);
                     return;
                 }
             }
         }
 
-        if !fluid_falling_helper(self.den(), i, j, container, pal_container, cur, dim, 1) {
+        if !fluid_falling_helper(self.den(), i, j, container, pal_container, cur, dim) {
             let top = cs::xy_to_index(i, j + 1);
             let down = cs::xy_to_index(i, j - 1);
             let r = cs::xy_to_index(i + 1, j);
@@ -110,7 +109,16 @@ impl CellTrait for DeluteAcid {
     fn den(&self) -> i8 {
         2
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Liquid
+    }
 
+    fn heat_capacity(&self) -> f32 {
+        4.0
+    }
+    fn latent_heat(&self) -> f32 {
+        40.0
+    }
     fn needs_temp(&self) -> bool {
         true
     }
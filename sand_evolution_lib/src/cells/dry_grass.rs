@@ -1,7 +1,7 @@
 use crate::cs::{self, PointType};
 
 use super::{
-    burning_wood, grass::Grass, water::Water, CellRegistry, CellTrait, CellType, Prng,
+    burning_wood, grass::Grass, water::Water, CellGrid, CellRegistry, CellTrait, CellType, Prng,
     TemperatureContext,
 };
 
@@ -23,7 +23,7 @@ impl CellTrait for DryGrass {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         _pal_container: &CellRegistry,
         prng: &mut Prng,
         _: Option<&mut TemperatureContext>,
@@ -99,6 +99,9 @@ impl CellTrait for DryGrass {
     fn id(&self) -> CellType {
         71
     }
+    fn blast_resistance(&self) -> f32 {
+        8.0
+    }
     fn name(&self) -> &str {
         "dry grass"
     }
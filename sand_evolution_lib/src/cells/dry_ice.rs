@@ -24,7 +24,7 @@ impl CellTrait for DryIce {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         _: Option<&mut TemperatureContext>,
@@ -1,6 +1,6 @@
 use crate::cs::{self, PointType};
 
-use super::{void::Void, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use super::{void::Void, CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
 
 pub struct Electricity;
 impl Electricity {
@@ -20,8 +20,8 @@ impl CellTrait for Electricity {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
-        _pal_container: &CellRegistry,
+        container: &mut CellGrid,
+        pal_container: &CellRegistry,
         prng: &mut Prng,
         _: Option<&mut TemperatureContext>,
     ) {
@@ -37,6 +37,12 @@ impl CellTrait for Electricity {
 
             if rand_v == Void::id() {
                 container[cc] = Electricity::id();
+            } else {
+                // Sparks light gas, powder and other burnables they touch.
+                let burnt = pal_container.pal[rand_v as usize].burnable();
+                if burnt != Void::id() {
+                    container[cc] = burnt;
+                }
             }
         } else {
             container[cur] = Void::id();
@@ -49,6 +55,9 @@ impl CellTrait for Electricity {
     fn casts_shadow(&self) -> bool {
         false
     }
+    fn light_transmission(&self) -> f32 {
+        1.0
+    }
     fn name(&self) -> &str {
         "electricity"
     }
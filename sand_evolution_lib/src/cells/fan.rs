@@ -0,0 +1,83 @@
+use crate::cs::{self, PointType};
+
+use super::{CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+
+/// Wind added per update in front of a fan cell; a row of fans adds up.
+const FAN_PUSH: f32 = 0.15;
+/// How far in front of the fan the gust starts, so it lands in open air.
+const FAN_REACH: i32 = 4;
+
+/// Static cell that blows the wind field (see `Simulation::step_wind`) in one direction.
+pub struct Fan {
+    facing: usize,
+}
+
+impl Fan {
+    /// Blowing direction per facing, counter-clockwise from "right"; up is +y.
+    pub const FACINGS: [(i8, i8); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    const NAMES: [&'static str; 4] = ["fan right", "fan up", "fan left", "fan down"];
+
+    pub const fn new(facing: usize) -> Self {
+        Self { facing }
+    }
+
+    pub fn boxed(facing: usize) -> Box<Self> {
+        Box::new(Self::new(facing))
+    }
+
+    pub fn id(facing: usize) -> CellType {
+        97 + facing as CellType
+    }
+}
+
+impl CellTrait for Fan {
+    fn update(
+        &self,
+        i: PointType,
+        j: PointType,
+        _cur: usize,
+        _container: &mut CellGrid,
+        _pal_container: &CellRegistry,
+        _prng: &mut Prng,
+        temp_context: Option<&mut TemperatureContext>,
+    ) {
+        let Some(temp_ctx) = temp_context else { return };
+        let (dx, dy) = Self::FACINGS[self.facing];
+        let size = cs::sector_size();
+        let x = (i as i32 + dx as i32 * FAN_REACH).clamp(0, size.x as i32 - 1);
+        let y = (j as i32 + dy as i32 * FAN_REACH).clamp(0, size.y as i32 - 1);
+        temp_ctx.add_wind(
+            x as PointType,
+            y as PointType,
+            [dx as f32 * FAN_PUSH, dy as f32 * FAN_PUSH],
+        );
+    }
+
+    fn den(&self) -> i8 {
+        20
+    }
+
+    fn keeps_awake(&self) -> bool {
+        true
+    }
+
+    fn stat(&self) -> bool {
+        true
+    }
+
+    fn needs_temp(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        Self::NAMES[self.facing]
+    }
+
+    fn id(&self) -> CellType {
+        Self::id(self.facing)
+    }
+
+    fn display_color(&self) -> [u8; 3] {
+        [110, 130, 140]
+    }
+}
@@ -1,4 +1,7 @@
-use super::{helper::try_spawn_smoke, TemperatureContext, *};
+use super::{
+    helper::{neighbour, try_spawn_smoke},
+    TemperatureContext, *,
+};
 use crate::cs::{self, PointType};
 
 pub const fn new() -> Cell {
@@ -17,8 +20,8 @@ impl CellTrait for Cell {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
-        _pal_container: &CellRegistry,
+        container: &mut CellGrid,
+        pal_container: &CellRegistry,
         prng: &mut Prng,
         mut temp_context: Option<&mut TemperatureContext>,
     ) {
@@ -32,7 +35,7 @@ impl CellTrait for Cell {
                 sum += temp_ctx.get_temp(i - 1, j);
                 n += 1.0;
             }
-            if i + 1 < cs::SECTOR_SIZE.x {
+            if i + 1 < cs::sector_size().x {
                 sum += temp_ctx.get_temp(i + 1, j);
                 n += 1.0;
             }
@@ -40,7 +43,7 @@ impl CellTrait for Cell {
                 sum += temp_ctx.get_temp(i, j - 1);
                 n += 1.0;
             }
-            if j + 1 < cs::SECTOR_SIZE.y {
+            if j + 1 < cs::sector_size().y {
                 sum += temp_ctx.get_temp(i, j + 1);
                 n += 1.0;
             }
@@ -105,14 +108,17 @@ impl CellTrait for Cell {
         ];
         let (_nx, _ny, _cc) = arr[(prng.next() % 4) as usize];
 
-        let top_v = container[top];
+        // Flames rise against gravity.
+        let gravity = pal_container.gravity.rising(prng);
+        let up = neighbour(gravity, i, j, 0, 1);
+        let up_v = container[up];
 
-        if top_v == Void::id() {
-            container.swap(cur, top);
+        if up_v == Void::id() {
+            container.swap(cur, up);
             return;
         }
 
-        let topl = cs::xy_to_index(i - 1, j + 1);
+        let topl = neighbour(gravity, i, j, -1, 1);
         let topl_v = container[topl];
 
         if topl_v == Void::id() {
@@ -120,7 +126,7 @@ impl CellTrait for Cell {
             return;
         }
 
-        let topr = cs::xy_to_index(i + 1, j + 1);
+        let topr = neighbour(gravity, i, j, 1, 1);
         let topr_v = container[topr];
 
         if topr_v == Void::id() {
@@ -135,6 +141,12 @@ impl CellTrait for Cell {
     fn den(&self) -> i8 {
         -1
     }
+    fn thermal_conductivity(&self) -> f32 {
+        0.3
+    }
+    fn convection_factor(&self) -> f32 {
+        1.0
+    }
     fn casts_shadow(&self) -> bool {
         false
     }
@@ -142,6 +154,9 @@ impl CellTrait for Cell {
     fn needs_temp(&self) -> bool {
         true
     }
+    fn wind_drag(&self) -> f32 {
+        1.0
+    }
     fn name(&self) -> &str {
         "fire"
     }
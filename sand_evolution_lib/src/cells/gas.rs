@@ -1,4 +1,7 @@
-use super::{helper::fluid_flying_helper, TemperatureContext, *};
+use super::{
+    helper::{change_phase, fluid_flying_helper},
+    TemperatureContext, *,
+};
 use crate::cs::PointType;
 
 pub struct Gas;
@@ -19,12 +22,12 @@ impl CellTrait for Gas {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
     ) {
-        if let Some(temp_ctx) = temp_context.as_deref() {
+        if let Some(temp_ctx) = temp_context {
             let temperature = temp_ctx.get_temp(i, j);
 
             if temperature >= 150.0 {
@@ -38,7 +41,7 @@ impl CellTrait for Gas {
 
             if temperature < -50.0 && prng.next() < 10 {
                 use super::liquid_gas::LiquidGas;
-                container[cur] = LiquidGas::id();
+                change_phase(i, j, cur, LiquidGas::id(), container, pal_container, temp_ctx);
                 return;
             }
         }
@@ -49,6 +52,12 @@ impl CellTrait for Gas {
     fn den(&self) -> i8 {
         -1
     }
+    fn thermal_conductivity(&self) -> f32 {
+        0.3
+    }
+    fn convection_factor(&self) -> f32 {
+        1.0
+    }
     fn casts_shadow(&self) -> bool {
         false
     }
@@ -58,9 +67,18 @@ impl CellTrait for Gas {
     fn heatable(&self) -> CellType {
         Void::id()
     }
+    fn latent_heat(&self) -> f32 {
+        12.0
+    }
     fn needs_temp(&self) -> bool {
         true
     }
+    fn light_transmission(&self) -> f32 {
+        0.995
+    }
+    fn wind_drag(&self) -> f32 {
+        1.0
+    }
     fn name(&self) -> &str {
         "gas"
     }
@@ -2,8 +2,8 @@ use crate::cells::dry_grass::DryGrass;
 use crate::cs::{self, PointType};
 
 use super::{
-    base_water::BaseWater, burning_wood, sand::Base, void::Void, water::Water, CellRegistry,
-    CellTrait, CellType, Prng, TemperatureContext,
+    burning_wood, void::Void, water::Water, CellGrid, CellRegistry, CellTrait, CellType, Prng,
+    TemperatureContext,
 };
 
 pub struct Grass;
@@ -23,65 +23,13 @@ impl CellTrait for Grass {
         &self,
         i: PointType,
         j: PointType,
-        cur: usize,
-        container: &mut [CellType],
+        _cur: usize,
+        container: &mut CellGrid,
         _pal_container: &CellRegistry,
         prng: &mut Prng,
         _: Option<&mut TemperatureContext>,
     ) {
-        // Check for drying on contact with alkali
-        if prng.next() > 180 {
-            let top = cs::xy_to_index(i, j + 1);
-            let down = cs::xy_to_index(i, j - 1);
-            let r = cs::xy_to_index(i + 1, j);
-            let l = cs::xy_to_index(i - 1, j);
-
-            let top_v = container[top];
-            let down_v = container[down];
-            let r_v = container[r];
-            let l_v = container[l];
-
-            // Check neighboring cells for alkali or alkaline water
-            if top_v == Base::id()
-                || top_v == BaseWater::id()
-                || down_v == Base::id()
-                || down_v == BaseWater::id()
-                || r_v == Base::id()
-                || r_v == BaseWater::id()
-                || l_v == Base::id()
-                || l_v == BaseWater::id()
-            {
-                // Grass dries and turns into dry grass
-                container[cur] = DryGrass::id();
-                return;
-            }
-        }
-
-        // Slow mixing with dry grass
-        if prng.next() > 240 {
-            let top = cs::xy_to_index(i, j + 1);
-            let down = cs::xy_to_index(i, j - 1);
-            let r = cs::xy_to_index(i + 1, j);
-            let l = cs::xy_to_index(i - 1, j);
-
-            let top_v = container[top];
-            let down_v = container[down];
-            let r_v = container[r];
-            let l_v = container[l];
-
-            // If there's dry grass nearby, green grass slowly turns into dry
-            if top_v == DryGrass::id()
-                || down_v == DryGrass::id()
-                || r_v == DryGrass::id()
-                || l_v == DryGrass::id()
-            {
-                // With very low probability, green grass dries
-                if prng.next() > 250 {
-                    container[cur] = DryGrass::id();
-                    return;
-                }
-            }
-        }
+        // Drying next to alkali / dry grass is in `reaction::setup_reactions`.
 
         // Grass grows on neighboring empty cells if there's water near the grass
         // Check rarely for slow growth
@@ -137,6 +85,9 @@ impl CellTrait for Grass {
     fn ignition_temperature(&self) -> Option<f32> {
         Some(400.0)
     }
+    fn blast_resistance(&self) -> f32 {
+        8.0
+    }
     fn name(&self) -> &str {
         "grass"
     }
@@ -1,7 +1,8 @@
 use crate::cs::PointType;
 
 use super::{
-    helper::sand_falling_helper, CellRegistry, CellTrait, CellType, Prng, TemperatureContext,
+    helper::sand_falling_helper, CellGrid, CellRegistry, CellTrait, CellType, Momentum, Prng,
+    TemperatureContext,
 };
 
 pub struct Gravel;
@@ -26,7 +27,7 @@ impl CellTrait for Gravel {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         _: Option<&mut TemperatureContext>,
@@ -37,6 +38,9 @@ impl CellTrait for Gravel {
     fn den(&self) -> i8 {
         10
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Powder
+    }
 
     fn name(&self) -> &str {
         "gravel"
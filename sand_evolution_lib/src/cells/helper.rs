@@ -1,20 +1,35 @@
 use crate::cs;
 
-use super::{smoke::Smoke, void::Void, CellRegistry, CellType, PointType, Prng};
+use super::{
+    smoke::Smoke, void::Void, CellGrid, CellRegistry, CellType, FieldView, Gravity, Momentum,
+    PointType, Prng, TemperatureContext,
+};
+
+/// Index of the cell at a (side, up) offset from (i, j), with "up" against `gravity`.
+#[inline(always)]
+pub fn neighbour(gravity: Gravity, i: PointType, j: PointType, side: i32, up: i32) -> usize {
+    let (dx, dy) = gravity.offset(side, up);
+    cs::xy_to_index((i as i32 + dx) as PointType, (j as i32 + dy) as PointType)
+}
 
 pub fn sand_falling_helper(
     my_den: i8,
     i: u16,
     j: u16,
-    container: &mut [u8],
+    container: &mut CellGrid,
     pal_container: &CellRegistry,
     cur: usize,
     rpng: &mut Prng,
 ) -> bool {
     const ORDER: [[usize; 2]; 2] = [[0, 1], [1, 0]];
+    let gravity = pal_container.gravity;
+    if gravity == Gravity::Zero {
+        return false;
+    }
+    let at = |side, up| neighbour(gravity, i, j, side, up);
     let selected_order = ORDER[(rpng.next() % 2) as usize];
 
-    let down = cs::xy_to_index(i, j - 1);
+    let down = at(0, -1);
     let down_v = container[down] as usize;
     let down_c = &pal_container.pal[down_v];
     if down_c.den() < my_den && !down_c.stat() {
@@ -25,7 +40,7 @@ pub fn sand_falling_helper(
     for k in 0..2 {
         match selected_order[k] {
             0 => {
-                let dr = cs::xy_to_index(i + 1, j - 1);
+                let dr = at(1, -1);
                 let dr_v = container[dr] as usize;
                 let dr_c = &pal_container.pal[dr_v];
                 if dr_c.den() < my_den && !dr_c.stat() {
@@ -34,7 +49,7 @@ pub fn sand_falling_helper(
                 }
             }
             1 => {
-                let dl = cs::xy_to_index(i - 1, j - 1);
+                let dl = at(-1, -1);
                 let dl_v = container[dl] as usize;
                 let dl_c = &pal_container.pal[dl_v];
                 if dl_c.den() < my_den && !dl_c.stat() {
@@ -53,15 +68,20 @@ pub fn snow_falling_helper(
     my_den: i8,
     i: u16,
     j: u16,
-    container: &mut [u8],
+    container: &mut CellGrid,
     pal_container: &CellRegistry,
     cur: usize,
     rpng: &mut Prng,
 ) -> bool {
     const ORDER: [[usize; 2]; 2] = [[0, 1], [1, 0]];
+    let gravity = pal_container.gravity;
+    if gravity == Gravity::Zero {
+        return false;
+    }
+    let at = |side, up| neighbour(gravity, i, j, side, up);
     let selected_order = ORDER[(rpng.next() % 2) as usize];
 
-    let down = cs::xy_to_index(i, j - 1);
+    let down = at(0, -1);
     let down_v = container[down] as usize;
     let down_c = &pal_container.pal[down_v];
     if down_c.den() < my_den && !down_c.stat() {
@@ -72,7 +92,7 @@ pub fn snow_falling_helper(
     for k in 0..2 {
         match selected_order[k] {
             0 => {
-                let dr = cs::xy_to_index(i + 1, j - 1);
+                let dr = at(1, -1);
                 let dr_v = container[dr] as usize;
                 let dr_c = &pal_container.pal[dr_v];
                 if dr_c.den() < my_den && !dr_c.stat() {
@@ -81,7 +101,7 @@ pub fn snow_falling_helper(
                 }
             }
             1 => {
-                let dl = cs::xy_to_index(i - 1, j - 1);
+                let dl = at(-1, -1);
                 let dl_v = container[dl] as usize;
                 let dl_c = &pal_container.pal[dl_v];
                 if dl_c.den() < my_den && !dl_c.stat() {
@@ -96,21 +116,30 @@ pub fn snow_falling_helper(
     false
 }
 
+/// Falls, slides down diagonally, then spreads sideways on one update in `viscosity()`,
+/// trying both sides in random order. On those updates a liquid that can do none of that
+/// may also level out through the liquid below it (see `level_liquid`).
 #[inline(always)]
 pub fn fluid_falling_helper(
     my_den: i8,
     i: u16,
     j: u16,
-    container: &mut [u8],
+    container: &mut CellGrid,
     pal_container: &CellRegistry,
     cur: usize,
     rpng: &mut Prng,
-    thickness: u8,
 ) -> bool {
     const ORDER: [[usize; 2]; 2] = [[0, 1], [1, 0]];
-    let selected_order = [0, 1]; //ORDER[(rpng.next() % 2) as usize];
+    let gravity = pal_container.gravity;
+    if gravity == Gravity::Zero {
+        return false;
+    }
+    let at = |side, up| neighbour(gravity, i, j, side, up);
+    let selected_order = ORDER[(rpng.next() < 128) as usize];
+    let me = &pal_container.pal[container[cur] as usize];
+    let viscosity = me.viscosity().max(1);
 
-    let down = cs::xy_to_index(i, j - 1);
+    let down = at(0, -1);
     let down_v = container[down] as usize;
     let down_c = &pal_container.pal[down_v];
     if down_c.den() < my_den && !down_c.stat() {
@@ -121,7 +150,7 @@ pub fn fluid_falling_helper(
     for k in 0..2 {
         match selected_order[k] {
             0 => {
-                let dr = cs::xy_to_index(i + 1, j - 1);
+                let dr = at(1, -1);
                 let dr_v = container[dr] as usize;
                 let dr_c = &pal_container.pal[dr_v];
                 if dr_c.den() < my_den && !dr_c.stat() {
@@ -130,7 +159,7 @@ pub fn fluid_falling_helper(
                 }
             }
             1 => {
-                let dl = cs::xy_to_index(i - 1, j - 1);
+                let dl = at(-1, -1);
                 let dl_v = container[dl] as usize;
                 let dl_c = &pal_container.pal[dl_v];
                 if dl_c.den() < my_den && !dl_c.stat() {
@@ -142,11 +171,11 @@ pub fn fluid_falling_helper(
         }
     }
 
-    if thickness == 1 || rpng.next() > (255 - 255 / thickness) {
+    if viscosity == 1 || rpng.next() > (255 - 255 / viscosity) {
         for k in 0..2 {
             match selected_order[k] {
                 0 => {
-                    let dr = cs::xy_to_index(i + 1, j);
+                    let dr = at(1, 0);
                     let dr_v = container[dr] as usize;
                     let dr_c = &pal_container.pal[dr_v];
                     if dr_c.den() < my_den && !dr_c.stat() {
@@ -155,7 +184,7 @@ pub fn fluid_falling_helper(
                     }
                 }
                 1 => {
-                    let dl = cs::xy_to_index(i - 1, j);
+                    let dl = at(-1, 0);
                     let dl_v = container[dl] as usize;
                     let dl_c = &pal_container.pal[dl_v];
                     if dl_c.den() < my_den && !dl_c.stat() {
@@ -166,28 +195,121 @@ pub fn fluid_falling_helper(
                 _ => (),
             }
         }
+
+        if pal_container.liquid_levelling && me.momentum() == Momentum::Liquid {
+            return level_liquid(i, j, cur, container, pal_container, rpng);
+        }
     }
 
     false
 }
 
+/// Hydrostatic levelling for the top cell of a liquid column: follows the body of the same
+/// liquid down, along one side (then the other) and back up, and moves the cell onto the
+/// first surface it finds that is lower than where it started. That is how a U-tube evens
+/// out, which plain sideways spreading can't do through a narrow bend. Bodies that are
+/// still falling are left alone.
+pub fn level_liquid(
+    i: PointType,
+    j: PointType,
+    cur: usize,
+    container: &mut CellGrid,
+    pal_container: &CellRegistry,
+    rpng: &mut Prng,
+) -> bool {
+    let me = container[cur];
+    let gravity = pal_container.gravity;
+    // Only empty cells: pushing liquid through steam or smoke would collapse rising bubbles,
+    // which ordinary falling already handles.
+    let open = |container: &CellGrid, idx: usize| container[idx] == Void::id();
+    if container[neighbour(gravity, i, j, 0, -1)] != me
+        || !open(container, neighbour(gravity, i, j, 0, 1))
+    {
+        return false;
+    }
+
+    let first = if rpng.next() < 128 { 1 } else { -1 };
+    for side in [first, -first] {
+        if let Some(above) = find_lower_surface(i, j, side, container, me, gravity, &open) {
+            container.swap(cur, above);
+            return true;
+        }
+    }
+    false
+}
+
+/// The walk for `level_liquid` along one `side`: index of the free cell above the first
+/// surface of liquid `me` it reaches at least two cells below the start.
+fn find_lower_surface(
+    i: PointType,
+    j: PointType,
+    side: i32,
+    container: &CellGrid,
+    me: CellType,
+    gravity: Gravity,
+    open: &impl Fn(&CellGrid, usize) -> bool,
+) -> Option<usize> {
+    // Cells the walk may cross, enough for a vessel a few hundred cells across.
+    const MAX_STEPS: usize = 384;
+
+    let size = cs::sector_size();
+    let (width, height) = (size.x as i32, size.y as i32);
+    let at = |x: i32, y: i32, up| neighbour(gravity, x as PointType, y as PointType, 0, up);
+    let (mut x, mut y) = (i as i32, j as i32);
+    let mut prev = (x, y);
+    let mut level = 0;
+    let mut descending = true;
+    for _ in 0..MAX_STEPS {
+        // Prefer down, then along `side`, then up, never straight back.
+        let next = [(0, -1), (side, 0), (0, 1)].into_iter().find_map(|(s, u)| {
+            let (dx, dy) = gravity.offset(s, u);
+            let (nx, ny) = (x + dx, y + dy);
+            let inside =
+                nx >= 1 && ny >= 1 && nx < width - 1 && ny < height - 1 && cs::within_reach(nx, ny);
+            let liquid = inside
+                && (nx, ny) != prev
+                && container[cs::xy_to_index(nx as PointType, ny as PointType)] == me;
+            liquid.then_some((nx, ny, u))
+        });
+        let (nx, ny, u) = next?;
+        if descending && u != -1 {
+            // Reached the bottom of the first column: it has to rest on something.
+            if open(container, at(x, y, -1)) {
+                return None;
+            }
+            descending = false;
+        }
+        prev = (x, y);
+        (x, y) = (nx, ny);
+        level += u;
+        // The cell lands one above the surface it finds, so that surface must be two lower.
+        let above = at(x, y, 1);
+        if level < -1 && open(container, above) {
+            return Some(above);
+        }
+    }
+    None
+}
+
 #[inline(always)]
 pub fn fluid_flying_helper(
     my_den: i8,
     i: u16,
     j: u16,
-    container: &mut [u8],
+    container: &mut CellGrid,
     pal_container: &CellRegistry,
     cur: usize,
     dim: &mut Prng,
 ) -> bool {
     const ORDER: [[usize; 2]; 2] = [[0, 1], [1, 0]];
+    let gravity = pal_container.gravity.rising(dim);
+    let at = |side, up| neighbour(gravity, i, j, side, up);
     let selected_order = ORDER[(dim.next() % 2) as usize];
 
     for k in 0..2 {
         match selected_order[k] {
             0 => {
-                let dr = cs::xy_to_index(i + 1, j + 1);
+                let dr = at(1, 1);
                 let dr_v = container[dr] as usize;
                 let dr_c = &pal_container.pal[dr_v];
                 if dr_c.den() > my_den && !dr_c.stat() {
@@ -196,7 +318,7 @@ pub fn fluid_flying_helper(
                 }
             }
             1 => {
-                let dl = cs::xy_to_index(i - 1, j + 1);
+                let dl = at(-1, 1);
                 let dl_v = container[dl] as usize;
                 let dl_c = &pal_container.pal[dl_v];
                 if dl_c.den() > my_den && !dl_c.stat() {
@@ -211,7 +333,7 @@ pub fn fluid_flying_helper(
     for k in 0..2 {
         match selected_order[k] {
             0 => {
-                let dr = cs::xy_to_index(i + 1, j);
+                let dr = at(1, 0);
                 let dr_v = container[dr] as usize;
                 let dr_c = &pal_container.pal[dr_v];
                 if dr_c.den() > my_den && !dr_c.stat() {
@@ -220,7 +342,7 @@ pub fn fluid_flying_helper(
                 }
             }
             1 => {
-                let dl = cs::xy_to_index(i - 1, j);
+                let dl = at(-1, 0);
                 let dl_v = container[dl] as usize;
                 let dl_c = &pal_container.pal[dl_v];
                 if dl_c.den() > my_den && !dl_c.stat() {
@@ -235,17 +357,187 @@ pub fn fluid_flying_helper(
     false
 }
 
+/// Drifts a cell with a `wind_drag()` along the air flow at (i, j) through empty space, a
+/// fractional speed moving it one cell with that chance. Returns where it ended up, so the
+/// cell's own update can still run there.
+pub fn wind_helper(
+    i: PointType,
+    j: PointType,
+    container: &mut CellGrid,
+    wind: [f32; 2],
+    drag: f32,
+    prng: &mut Prng,
+) -> (PointType, PointType) {
+    // Cells per update along each axis, however strong the wind.
+    const MAX_DRIFT: f32 = 3.0;
+
+    let mut steps = [0i32; 2];
+    for (k, step) in steps.iter_mut().enumerate() {
+        let v = (wind[k] * drag).clamp(-MAX_DRIFT, MAX_DRIFT);
+        let whole = v.abs().floor();
+        let extra = ((prng.next() as f32) < (v.abs() - whole) * 255.0) as i32;
+        *step = (whole as i32 + extra) * v.signum() as i32;
+    }
+
+    let size = cs::sector_size();
+    let (width, height) = (size.x as i32, size.y as i32);
+    let (mut x, mut y) = (i as i32, j as i32);
+    let mut cur = cs::xy_to_index(i, j);
+    while steps != [0, 0] {
+        let k = (steps[1].abs() > steps[0].abs()) as usize;
+        let d = steps[k].signum();
+        let (nx, ny) = if k == 0 { (x + d, y) } else { (x, y + d) };
+        if nx < 1 || ny < 1 || nx >= width - 2 || ny >= height - 2 {
+            break;
+        }
+        let target = cs::xy_to_index(nx as PointType, ny as PointType);
+        if container[target] != Void::id() {
+            break;
+        }
+        container.swap(cur, target);
+        (x, y, cur) = (nx, ny, target);
+        steps[k] -= d;
+    }
+    (x as PointType, y as PointType)
+}
+
+/// Ballistic movement for cells with a `momentum()` while the velocity buffer is on.
+///
+/// Free cells gain speed along gravity every update and travel along their velocity, possibly
+/// several cells at once, swapping through anything lighter and loose. A landing stops the fall
+/// (liquids turn it into a sideways splash) and ground contact slows sideways travel; in zero-g
+/// cells just coast until they hit something. Velocities move together with the cells.
+///
+/// Returns false when the cell is at rest or blocked, so the caller can fall back to the
+/// usual one-cell helpers. A cell stacked on a falling one counts as moving.
+pub fn momentum_helper(
+    i: u16,
+    j: u16,
+    container: &mut CellGrid,
+    velocities: &mut FieldView<[f32; 2]>,
+    pal_container: &CellRegistry,
+    cur: usize,
+    rpng: &mut Prng,
+) -> bool {
+    const GRAVITY: f32 = 0.35;
+    const MAX_SPEED: f32 = 8.0;
+    // Share of the landing speed a liquid keeps as sideways splash.
+    const SPLASH: f32 = 0.6;
+    // Share of sideways speed kept per update while touching the ground.
+    const GROUND_FRICTION: f32 = 0.6;
+
+    let me = &pal_container.pal[container[cur] as usize];
+    let (my_den, kind) = (me.den(), me.momentum());
+    // Everything below works in the (side, up) frame of the current gravity.
+    let gravity = pal_container.gravity;
+    let weight = if gravity == Gravity::Zero { 0.0 } else { GRAVITY };
+    let to_grid = |x: i32, y: i32| {
+        let (dx, dy) = gravity.offset(x, y);
+        (i as i32 + dx, j as i32 + dy)
+    };
+    let size = cs::sector_size();
+    let passable = |container: &CellGrid, x: i32, y: i32| {
+        let (x, y) = to_grid(x, y);
+        if x < 0 || y < 0 || x >= size.x as i32 || y >= size.y as i32 {
+            return false;
+        }
+        let c = &pal_container.pal[container[cs::xy_to_index(x as u16, y as u16)] as usize];
+        c.den() < my_den && !c.stat()
+    };
+
+    let mut v = gravity.to_local(velocities[cur]);
+    let falling = weight > 0.0 && passable(container, 0, -1);
+    // Resting on a cell that is itself falling: keep pace with it instead of landing.
+    let below = neighbour(gravity, i, j, 0, -1);
+    let below_vy = gravity.to_local(velocities[below])[1];
+    let riding = weight > 0.0
+        && !falling
+        && below_vy < 0.0
+        && pal_container.pal[container[below] as usize].momentum() != Momentum::None;
+    if falling {
+        v[1] = (v[1] - weight).max(-MAX_SPEED);
+    } else if riding {
+        v[1] = v[1].max(below_vy);
+    } else if weight > 0.0 {
+        if v[1] < 0.0 && kind == Momentum::Liquid {
+            let splash = -v[1] * SPLASH;
+            v[0] += if rpng.next() < 128 { splash } else { -splash };
+        } else {
+            v[0] *= GROUND_FRICTION;
+        }
+        v[1] = v[1].max(0.0);
+    }
+    v[0] = v[0].clamp(-MAX_SPEED, MAX_SPEED);
+    v[1] = v[1].clamp(-MAX_SPEED, MAX_SPEED);
+
+    let speed = v[0].abs().max(v[1].abs());
+    if speed < 1.0 && !falling {
+        velocities[cur] = [0.0; 2];
+        return false;
+    }
+
+    // Walk the straight line to the target one cell at a time; slow falls still drop one cell.
+    let steps = speed.ceil().max(1.0) as i32;
+    let (mut x, mut y, mut at, mut prev) = (0, 0, cur, cur);
+    for k in 1..=steps {
+        let t = k as f32 / steps as f32;
+        let nx = (v[0] * t).round() as i32;
+        let ny = if speed < 1.0 {
+            -1
+        } else {
+            (v[1] * t).round() as i32
+        };
+        if (nx, ny) == (x, y) {
+            continue;
+        }
+        if !passable(container, nx, ny) {
+            // Stop the blocked direction; a downward hit is left for the landing above.
+            if nx != x && !passable(container, nx, y) {
+                v[0] = 0.0;
+            }
+            if (ny > y || weight == 0.0) && ny != y && !passable(container, x, ny) {
+                v[1] = 0.0;
+            }
+            break;
+        }
+        let (gx, gy) = to_grid(nx, ny);
+        let next = cs::xy_to_index(gx as u16, gy as u16);
+        container.swap(at, next);
+        velocities.swap(at, next);
+        (x, y, at, prev) = (nx, ny, next, at);
+    }
+    // The update loop visits one parity class per tick, so a cell moved by an even offset on
+    // both axes could be updated again this tick. Stop one step short instead.
+    if at != cur && x & 1 == 0 && y & 1 == 0 {
+        container.swap(at, prev);
+        velocities.swap(at, prev);
+        at = prev;
+    }
+
+    if at == cur {
+        if riding {
+            // Wait for the cell below to make room rather than spreading mid-air.
+            velocities[cur] = gravity.to_grid(v);
+            return true;
+        }
+        velocities[cur] = [0.0; 2];
+        return false;
+    }
+    velocities[at] = gravity.to_grid(v);
+    true
+}
+
 pub fn try_spawn_smoke(
     i: PointType,
     j: PointType,
-    container: &mut [CellType],
+    container: &mut CellGrid,
     prng: &mut Prng,
     target_count: usize,
 ) -> usize {
     let mut spawned = 0;
     let mut candidates = Vec::new();
 
-    if j + 1 < cs::SECTOR_SIZE.y {
+    if j + 1 < cs::sector_size().y {
         let top = cs::xy_to_index(i, j + 1);
         if container[top] == Void::id() {
             candidates.push(top);
@@ -257,7 +549,7 @@ pub fn try_spawn_smoke(
             candidates.push(bot);
         }
     }
-    if i + 1 < cs::SECTOR_SIZE.x {
+    if i + 1 < cs::sector_size().x {
         let right = cs::xy_to_index(i + 1, j);
         if container[right] == Void::id() {
             candidates.push(right);
@@ -279,3 +571,82 @@ pub fn try_spawn_smoke(
 
     spawned
 }
+
+/// Turns `cur` into `into` and moves the latent heat difference between the two
+/// (`CellTrait::latent_heat`) in or out of the temperature field, so melting and boiling
+/// cool their surroundings and freezing and condensing warm them by the same amount.
+#[inline]
+pub fn change_phase(
+    i: PointType,
+    j: PointType,
+    cur: usize,
+    into: CellType,
+    container: &mut CellGrid,
+    pal_container: &CellRegistry,
+    temp_ctx: &mut TemperatureContext,
+) {
+    let from = &pal_container.pal[container[cur] as usize];
+    let absorbed = pal_container.pal[into as usize].latent_heat() - from.latent_heat();
+    container[cur] = into;
+    if absorbed != 0.0 {
+        temp_ctx.add_heat(i, j, -absorbed);
+    }
+}
+
+/// Checks `pal_container.reactions` against one random 4-neighbour of the cell and applies
+/// the reaction if it fires. Returns true if that turned the cell into something else (skip
+/// its regular update then); a reaction that only changed the neighbour returns false.
+#[inline]
+pub fn try_react(
+    i: PointType,
+    j: PointType,
+    cur: usize,
+    container: &mut CellGrid,
+    pal_container: &CellRegistry,
+    prng: &mut Prng,
+    temp_context: Option<&mut TemperatureContext>,
+) -> bool {
+    let cur_v = container[cur];
+    if !pal_container.reactions.is_reactive(cur_v) {
+        return false;
+    }
+
+    let other = match prng.next() % 4 {
+        0 => cs::xy_to_index(i, j + 1),
+        1 => cs::xy_to_index(i, j - 1),
+        2 => cs::xy_to_index(i + 1, j),
+        _ => cs::xy_to_index(i - 1, j),
+    };
+    let Some(reaction) = pal_container.reactions.get(cur_v, container[other]) else {
+        return false;
+    };
+    if reaction.chance < 255 && prng.next() >= reaction.chance {
+        return false;
+    }
+
+    match temp_context {
+        Some(temp_ctx) => {
+            let temperature = temp_ctx.get_temp(i, j);
+            if temperature < reaction.min_temperature || temperature > reaction.max_temperature {
+                return false;
+            }
+            if reaction.heat != 0.0 {
+                temp_ctx.add_temp(i, j, reaction.heat);
+            }
+            if reaction.pressure > 0.0 {
+                temp_ctx.add_pressure(i, j, reaction.pressure);
+            }
+        }
+        // Without the temperature field only unconditional reactions can run.
+        None if reaction.min_temperature > f32::NEG_INFINITY
+            || reaction.max_temperature < f32::INFINITY =>
+        {
+            return false;
+        }
+        None => {}
+    }
+
+    container[cur] = reaction.into;
+    container[other] = reaction.other_into;
+    reaction.into != cur_v
+}
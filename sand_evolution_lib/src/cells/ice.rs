@@ -1,6 +1,9 @@
 use crate::cs::PointType;
 
-use super::{water::Water, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use super::{
+    helper::change_phase, water::Water, CellGrid, CellRegistry, CellTrait, CellType, Prng,
+    TemperatureContext,
+};
 
 pub struct Ice;
 impl Ice {
@@ -20,8 +23,8 @@ impl CellTrait for Ice {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
-        _pal_container: &CellRegistry,
+        container: &mut CellGrid,
+        pal_container: &CellRegistry,
         prng: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
     ) {
@@ -32,13 +35,7 @@ impl CellTrait for Ice {
                 let chance_f = ((temperature / 20.0) * 26.0).clamp(0.0, 255.0);
                 let chance = chance_f as u8;
                 if prng.next() < chance {
-                    container[cur] = Water::id();
-                    const MELT_COOLING: f32 = 5.0;
-                    temp_ctx.add_temp(i, j, -MELT_COOLING);
-                    temp_ctx.add_temp(i, j + 1, -MELT_COOLING);
-                    temp_ctx.add_temp(i, j - 1, -MELT_COOLING);
-                    temp_ctx.add_temp(i + 1, j, -MELT_COOLING);
-                    temp_ctx.add_temp(i - 1, j, -MELT_COOLING);
+                    change_phase(i, j, cur, Water::id(), container, pal_container, temp_ctx);
                     return;
                 }
             }
@@ -60,9 +57,24 @@ impl CellTrait for Ice {
     fn heat_proof(&self) -> u8 {
         240
     }
+    fn heat_capacity(&self) -> f32 {
+        2.0
+    }
     fn needs_temp(&self) -> bool {
         true
     }
+    fn blast_resistance(&self) -> f32 {
+        30.0
+    }
+    fn rubble(&self) -> CellType {
+        super::crushed_ice::CrushedIce::id()
+    }
+    fn light_transmission(&self) -> f32 {
+        0.96
+    }
+    fn refractive_index(&self) -> f32 {
+        1.31
+    }
     fn name(&self) -> &str {
         "ice"
     }
@@ -1,7 +1,28 @@
+//! Laser beams.
+//!
+//! Every `light_emitter()` cell casts a ray across the grid each tick (`cast_beams`). The ray
+//! crosses cells with a `light_transmission()`, losing that share of its power as heat in each,
+//! bends by Snell's law where the `refractive_index()` changes, bounces off mirrors and dumps
+//! what is left into the first opaque cell it hits. Empty cells along the way are filled with
+//! `Laser` so the beam shows up; every `Laser` cell is cleared again before the next cast.
+
 use crate::cs::{self, PointType};
 
-use super::{void::Void, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use super::{void::Void, CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+
+/// Temperature added per tick to a cell that absorbs a full-power beam.
+const BEAM_HEAT: f32 = 10.0;
+/// Beams weaker than this are too faint to carry on.
+const MIN_POWER: f32 = 0.05;
+/// Cells a single beam may cross, bounces included.
+const MAX_STEPS: usize = 4096;
+/// Mirror bounces (and total internal reflections) before a beam is dropped, so a closed
+/// mirror box can't trap it forever.
+const MAX_BOUNCES: usize = 64;
 
+/// Visible segment of a beam. `cast_beams` clears them all each tick before recasting, so one
+/// painted by hand or pushed aside by a falling grain lasts a single tick.
+#[derive(Default)]
 pub struct Laser;
 impl Laser {
     pub const fn new() -> Self {
@@ -17,45 +38,14 @@ impl Laser {
 impl CellTrait for Laser {
     fn update(
         &self,
-        i: PointType,
-        j: PointType,
-        cur: usize,
-        container: &mut [CellType],
+        _i: PointType,
+        _j: PointType,
+        _cur: usize,
+        _container: &mut CellGrid,
         _pal_container: &CellRegistry,
-        prng: &mut Prng,
-        _: Option<&mut TemperatureContext>,
+        _prng: &mut Prng,
+        _temp_context: Option<&mut TemperatureContext>,
     ) {
-        let top = cs::xy_to_index(i, j + 1);
-        let down = cs::xy_to_index(i, j - 1);
-        let r = cs::xy_to_index(i + 1, j);
-        let l = cs::xy_to_index(i - 1, j);
-
-        let arr = [top, down, l, r];
-        let cc = arr[(prng.next() % 4) as usize];
-
-        if prng.next() > 50 {
-            let cc_v = container[cc] as usize;
-            let cc_c = &_pal_container.pal[cc_v];
-            let cc_b = cc_c.burnable();
-
-            if cc_b != Void::id() {
-                container[cc] = cc_b;
-                return;
-            }
-
-            let cc_h = cc_c.heatable();
-
-            if cc_h != Void::id() && prng.next() > cc_c.heat_proof() {
-                container[cc] = cc_h;
-                return;
-            }
-        }
-
-        if prng.next() < 200 {
-            return;
-        } else {
-            container[cur] = Void::id();
-        }
     }
 
     fn den(&self) -> i8 {
@@ -64,6 +54,9 @@ impl CellTrait for Laser {
     fn casts_shadow(&self) -> bool {
         false
     }
+    fn light_transmission(&self) -> f32 {
+        1.0
+    }
     fn name(&self) -> &str {
         "laser"
     }
@@ -74,3 +67,289 @@ impl CellTrait for Laser {
         [255, 26, 0]
     }
 }
+
+/// Static cell that fires a beam every tick in one of eight directions.
+pub struct LaserEmitter {
+    facing: usize,
+}
+
+impl LaserEmitter {
+    /// Beam direction per facing, counter-clockwise from "right"; up is +y.
+    pub const FACINGS: [(i8, i8); 8] = [
+        (1, 0),
+        (1, 1),
+        (0, 1),
+        (-1, 1),
+        (-1, 0),
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+    ];
+    const NAMES: [&'static str; 8] = [
+        "laser emitter right",
+        "laser emitter up-right",
+        "laser emitter up",
+        "laser emitter up-left",
+        "laser emitter left",
+        "laser emitter down-left",
+        "laser emitter down",
+        "laser emitter down-right",
+    ];
+
+    pub const fn new(facing: usize) -> Self {
+        Self { facing }
+    }
+
+    pub fn boxed(facing: usize) -> Box<Self> {
+        Box::new(Self::new(facing))
+    }
+
+    pub fn id(facing: usize) -> CellType {
+        88 + facing as CellType
+    }
+}
+
+impl CellTrait for LaserEmitter {
+    fn update(
+        &self,
+        _i: PointType,
+        _j: PointType,
+        _cur: usize,
+        _container: &mut CellGrid,
+        _pal_container: &CellRegistry,
+        _prng: &mut Prng,
+        _temp_context: Option<&mut TemperatureContext>,
+    ) {
+        // Static; the beam is cast by `cast_beams`.
+    }
+
+    fn den(&self) -> i8 {
+        20
+    }
+
+    fn stat(&self) -> bool {
+        true
+    }
+
+    fn light_emitter(&self) -> Option<(i8, i8)> {
+        Some(Self::FACINGS[self.facing])
+    }
+
+    fn name(&self) -> &str {
+        Self::NAMES[self.facing]
+    }
+
+    fn id(&self) -> CellType {
+        Self::id(self.facing)
+    }
+
+    fn display_color(&self) -> [u8; 3] {
+        [200, 60, 60]
+    }
+}
+
+/// Optical properties per cell id, looked up once per cast instead of per crossed cell.
+struct Optics {
+    transmission: [f32; 256],
+    refractive_index: [f32; 256],
+    reflects: [bool; 256],
+}
+
+/// Casts one beam from every emitter on the grid.
+///
+/// `light` receives the beam power crossing each cell, read back by `Simulation::get_light`.
+pub fn cast_beams(
+    container: &mut CellGrid,
+    pal_container: &CellRegistry,
+    light: &mut Vec<f32>,
+    temp_ctx: &mut TemperatureContext,
+) {
+    let mut emitter = [None; 256];
+    let mut optics = Optics {
+        transmission: [0.0; 256],
+        refractive_index: [1.0; 256],
+        reflects: [false; 256],
+    };
+    for (id, cell) in pal_container.pal.iter().enumerate() {
+        emitter[id] = cell.light_emitter();
+        optics.transmission[id] = cell.light_transmission();
+        optics.refractive_index[id] = cell.refractive_index();
+        optics.reflects[id] = cell.reflects_light();
+    }
+
+    let width = cs::sector_size().x as usize;
+    let mut emitters = Vec::new();
+    for idx in 0..container.len() {
+        let id = container[idx];
+        if id == Laser::id() {
+            container[idx] = Void::id();
+        } else if let Some(dir) = emitter[id as usize] {
+            emitters.push((idx, dir));
+        }
+    }
+    if emitters.is_empty() {
+        light.clear();
+        return;
+    }
+    if light.len() == container.len() {
+        light.fill(0.0);
+    } else {
+        *light = vec![0.0; container.len()];
+    }
+
+    for (idx, (dx, dy)) in emitters {
+        let start = [(idx % width) as i32, (idx / width) as i32];
+        let len = ((dx as f32).powi(2) + (dy as f32).powi(2)).sqrt();
+        let dir = [dx as f32 / len, dy as f32 / len];
+        trace(start, dir, container, &optics, light, temp_ctx);
+    }
+}
+
+/// Time along `dir` until the ray at `pos` leaves cell `cell` on one axis.
+fn exit_time(pos: f32, cell: i32, dir: f32) -> f32 {
+    if dir > 0.0 {
+        ((cell + 1) as f32 - pos) / dir
+    } else if dir < 0.0 {
+        (cell as f32 - pos) / dir
+    } else {
+        f32::INFINITY
+    }
+}
+
+/// Walks one beam cell by cell, crossing one cell face per step.
+fn trace(
+    start: [i32; 2],
+    mut dir: [f32; 2],
+    container: &mut CellGrid,
+    optics: &Optics,
+    light: &mut [f32],
+    temp_ctx: &mut TemperatureContext,
+) {
+    let size = cs::sector_size();
+    let (width, height) = (size.x as i32, size.y as i32);
+    let mut cell = start;
+    let mut pos = [start[0] as f32 + 0.5, start[1] as f32 + 0.5];
+    let mut power = 1.0f32;
+    let mut index = 1.0f32;
+    let mut bounces = 0;
+
+    for _ in 0..MAX_STEPS {
+        let tx = exit_time(pos[0], cell[0], dir[0]);
+        let ty = exit_time(pos[1], cell[1], dir[1]);
+        let axis = if tx <= ty { 0 } else { 1 };
+        let t = tx.min(ty).max(0.0);
+        pos = [pos[0] + dir[0] * t, pos[1] + dir[1] * t];
+        let mut next = cell;
+        next[axis] += if dir[axis] > 0.0 { 1 } else { -1 };
+        if next[0] < 1 || next[1] < 1 || next[0] >= width - 1 || next[1] >= height - 1 {
+            return;
+        }
+        let (nx, ny) = (next[0] as PointType, next[1] as PointType);
+        let next_idx = cs::xy_to_index(nx, ny);
+        let id = container[next_idx] as usize;
+
+        if optics.reflects[id] {
+            bounces += 1;
+            if bounces > MAX_BOUNCES {
+                return;
+            }
+            let tangent = mirror_tangent(container, &optics.reflects, nx, ny);
+            dir = reflect(dir, axis, tangent);
+            continue;
+        }
+        let transmission = optics.transmission[id];
+        if transmission <= 0.0 {
+            temp_ctx.add_temp(nx, ny, power * BEAM_HEAT);
+            return;
+        }
+        let next_index = optics.refractive_index[id];
+        if next_index != index {
+            match refract(dir, axis, index / next_index) {
+                Some(bent) => {
+                    dir = bent;
+                    index = next_index;
+                }
+                None => {
+                    // Total internal reflection: stay inside and bounce off the face.
+                    bounces += 1;
+                    if bounces > MAX_BOUNCES {
+                        return;
+                    }
+                    dir[axis] = -dir[axis];
+                    continue;
+                }
+            }
+        }
+
+        cell = next;
+        let absorbed = power * (1.0 - transmission);
+        if absorbed > 0.0 {
+            temp_ctx.add_temp(nx, ny, absorbed * BEAM_HEAT);
+            power -= absorbed;
+        }
+        light[next_idx] = light[next_idx].max(power);
+        if id == Void::id() as usize {
+            container[next_idx] = Laser::id();
+        }
+        if power < MIN_POWER {
+            return;
+        }
+    }
+}
+
+/// Direction of the mirror surface through (x, y), from the line its mirror neighbours form.
+/// `None` for a lone mirror cell or a blob with no clear direction.
+fn mirror_tangent(
+    container: &CellGrid,
+    reflects: &[bool; 256],
+    x: PointType,
+    y: PointType,
+) -> Option<[f32; 2]> {
+    let (mut xx, mut yy, mut xy) = (0.0f32, 0.0f32, 0.0f32);
+    for dy in -1i32..=1 {
+        for dx in -1i32..=1 {
+            let (nx, ny) = ((x as i32 + dx) as PointType, (y as i32 + dy) as PointType);
+            if (dx, dy) == (0, 0) || !reflects[container[cs::xy_to_index(nx, ny)] as usize] {
+                continue;
+            }
+            xx += (dx * dx) as f32;
+            yy += (dy * dy) as f32;
+            xy += (dx * dy) as f32;
+        }
+    }
+    if (xx - yy).abs() < 1e-3 && xy.abs() < 1e-3 {
+        return None;
+    }
+    let angle = 0.5 * (2.0 * xy).atan2(xx - yy);
+    Some([angle.cos(), angle.sin()])
+}
+
+/// Mirrors `dir` across the surface `tangent`. Falls back to flipping the component along the
+/// crossed face `axis` when there is no tangent, or when the bounce would still head into the
+/// mirror (hitting the end of a mirror line).
+fn reflect(dir: [f32; 2], axis: usize, tangent: Option<[f32; 2]>) -> [f32; 2] {
+    let mut flipped = dir;
+    flipped[axis] = -flipped[axis];
+    let Some(t) = tangent else { return flipped };
+    let along = dir[0] * t[0] + dir[1] * t[1];
+    let bounced = [2.0 * along * t[0] - dir[0], 2.0 * along * t[1] - dir[1]];
+    if bounced[axis] * dir[axis] > 0.0 {
+        flipped
+    } else {
+        bounced
+    }
+}
+
+/// Bends `dir` crossing a face normal to `axis`, with `ratio` = n(from) / n(to).
+/// `None` on total internal reflection.
+fn refract(dir: [f32; 2], axis: usize, ratio: f32) -> Option<[f32; 2]> {
+    let side = 1 - axis;
+    let tangential = dir[side] * ratio;
+    if tangential.abs() > 1.0 {
+        return None;
+    }
+    let mut bent = [0.0; 2];
+    bent[side] = tangential;
+    bent[axis] = dir[axis].signum() * (1.0 - tangential * tangential).sqrt();
+    Some(bent)
+}
@@ -1,4 +1,7 @@
-use super::{helper::fluid_falling_helper, TemperatureContext, *};
+use super::{
+    helper::{change_phase, fluid_falling_helper},
+    TemperatureContext, *,
+};
 use crate::cs::PointType;
 
 pub struct LiquidGas;
@@ -20,7 +23,7 @@ impl CellTrait for LiquidGas {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -30,22 +33,21 @@ impl CellTrait for LiquidGas {
 
             if temperature > -5.0 && prng.next() < 30 {
                 use super::gas::Gas;
-                container[cur] = Gas::id();
-                temp_ctx.add_temp(i, j + 1, -3.0);
-                temp_ctx.add_temp(i, j - 1, -3.0);
-                temp_ctx.add_temp(i + 1, j, -3.0);
-                temp_ctx.add_temp(i - 1, j, -3.0);
+                change_phase(i, j, cur, Gas::id(), container, pal_container, temp_ctx);
                 return;
             }
         }
 
         // Liquid gas behaves like a liquid
-        fluid_falling_helper(self.den(), i, j, container, pal_container, cur, prng, 1);
+        fluid_falling_helper(self.den(), i, j, container, pal_container, cur, prng);
     }
 
     fn den(&self) -> i8 {
         1
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Liquid
+    }
 
     fn burnable(&self) -> CellType {
         Void::id()
@@ -0,0 +1,234 @@
+//! Switches, gates and sensors for in-grid circuits.
+//!
+//! Every kind has an off and an on cell, so the state is just the cell id and survives saves.
+//! An "on" gate or sensor is an `electric_source()`, so `Simulation::step_electricity` carries
+//! its signal down the wires; gates read their inputs back through
+//! `TemperatureContext::get_charge` on their next update.
+//!
+//! Gates read the cell to their left and output to the right (AND reads above and below
+//! instead). Sensors output on all sides. A switch is a plain conductor while on.
+
+use crate::cs::PointType;
+
+use super::{
+    base_water::BaseWater, burning_coal::BurningCoal, burning_gas::BurningGas,
+    burning_powder::BurningPowder, burning_wood, fire, salty_water::SaltyWater, water::Water,
+    CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext,
+};
+
+/// Input charge that counts as a high signal.
+const SIGNAL_THRESHOLD: f32 = 0.1;
+/// Per-update chance out of 255 that a delay cell follows its input, so a chain of them
+/// passes the signal on slowly.
+const DELAY_CHANCE: u8 = 48;
+/// Temperature at which a heat sensor switches on.
+const HEAT_SENSOR_TEMPERATURE: f32 = 60.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Logic {
+    /// Conducts while on; flipped by scripts or the brush.
+    Switch,
+    /// Passes a signal from left to right only.
+    Diode,
+    /// On while its input is off.
+    Not,
+    /// On while both the cell above and the cell below carry a signal.
+    And,
+    /// Follows its input after a random delay.
+    Delay,
+    /// On while touching water.
+    WaterSensor,
+    /// On while touching fire or anything burning.
+    FireSensor,
+    /// On at or above `HEAT_SENSOR_TEMPERATURE`.
+    HeatSensor,
+}
+
+impl Logic {
+    pub const ALL: [Logic; 8] = [
+        Logic::Switch,
+        Logic::Diode,
+        Logic::Not,
+        Logic::And,
+        Logic::Delay,
+        Logic::WaterSensor,
+        Logic::FireSensor,
+        Logic::HeatSensor,
+    ];
+
+    pub fn id(self, on: bool) -> CellType {
+        let off = match self {
+            Logic::Switch => 64,
+            Logic::Diode => 66,
+            Logic::Not => 68,
+            Logic::And => 72,
+            Logic::Delay => 74,
+            Logic::WaterSensor => 76,
+            Logic::FireSensor => 78,
+            Logic::HeatSensor => 81,
+        };
+        off + on as CellType
+    }
+
+    fn name(self, on: bool) -> &'static str {
+        match (self, on) {
+            (Logic::Switch, false) => "switch",
+            (Logic::Switch, true) => "switch on",
+            (Logic::Diode, false) => "diode",
+            (Logic::Diode, true) => "diode on",
+            (Logic::Not, false) => "not gate",
+            (Logic::Not, true) => "not gate on",
+            (Logic::And, false) => "and gate",
+            (Logic::And, true) => "and gate on",
+            (Logic::Delay, false) => "delay",
+            (Logic::Delay, true) => "delay on",
+            (Logic::WaterSensor, false) => "water sensor",
+            (Logic::WaterSensor, true) => "water sensor on",
+            (Logic::FireSensor, false) => "fire sensor",
+            (Logic::FireSensor, true) => "fire sensor on",
+            (Logic::HeatSensor, false) => "heat sensor",
+            (Logic::HeatSensor, true) => "heat sensor on",
+        }
+    }
+}
+
+pub struct LogicCell {
+    kind: Logic,
+    on: bool,
+}
+
+impl LogicCell {
+    pub const fn new(kind: Logic, on: bool) -> Self {
+        Self { kind, on }
+    }
+
+    pub fn boxed(kind: Logic, on: bool) -> Box<Self> {
+        Box::new(Self::new(kind, on))
+    }
+}
+
+fn touches(container: &CellGrid, i: PointType, j: PointType, ids: &[CellType]) -> bool {
+    [(i - 1, j), (i + 1, j), (i, j - 1), (i, j + 1)]
+        .iter()
+        .any(|&(x, y)| ids.contains(&container[crate::cs::xy_to_index(x, y)]))
+}
+
+impl CellTrait for LogicCell {
+    fn update(
+        &self,
+        i: PointType,
+        j: PointType,
+        cur: usize,
+        container: &mut CellGrid,
+        _pal_container: &CellRegistry,
+        prng: &mut Prng,
+        temp_context: Option<&mut TemperatureContext>,
+    ) {
+        let Some(temp_ctx) = temp_context else { return };
+        let signal = |x: PointType, y: PointType| temp_ctx.get_charge(x, y) >= SIGNAL_THRESHOLD;
+
+        let on = match self.kind {
+            Logic::Switch => return,
+            Logic::Diode => signal(i - 1, j),
+            Logic::Not => !signal(i - 1, j),
+            Logic::And => signal(i, j + 1) && signal(i, j - 1),
+            Logic::Delay => {
+                let input = signal(i - 1, j);
+                if input != self.on && prng.next() >= DELAY_CHANCE {
+                    return;
+                }
+                input
+            }
+            Logic::WaterSensor => touches(
+                container,
+                i,
+                j,
+                &[
+                    Water::id(),
+                    BaseWater::id(),
+                    SaltyWater::with_level(1),
+                    SaltyWater::with_level(2),
+                    SaltyWater::with_level(3),
+                    SaltyWater::with_level(4),
+                ],
+            ),
+            Logic::FireSensor => touches(
+                container,
+                i,
+                j,
+                &[
+                    fire::id(),
+                    burning_wood::id(),
+                    BurningCoal::id(),
+                    BurningGas::id(),
+                    BurningPowder::id(),
+                ],
+            ),
+            Logic::HeatSensor => temp_ctx.get_temp(i, j) >= HEAT_SENSOR_TEMPERATURE,
+        };
+        if on != self.on {
+            container[cur] = self.kind.id(on);
+        }
+    }
+
+    fn den(&self) -> i8 {
+        20
+    }
+
+    fn stat(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        self.kind.name(self.on)
+    }
+
+    fn id(&self) -> CellType {
+        self.kind.id(self.on)
+    }
+
+    fn electrical_conductivity(&self) -> f32 {
+        if self.kind == Logic::Switch && self.on {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    fn electric_source(&self) -> bool {
+        self.on && self.kind != Logic::Switch
+    }
+
+    fn electric_output(&self) -> Option<(i8, i8)> {
+        match self.kind {
+            Logic::Switch | Logic::WaterSensor | Logic::FireSensor | Logic::HeatSensor => None,
+            Logic::Diode | Logic::Not | Logic::And | Logic::Delay => Some((1, 0)),
+        }
+    }
+
+    fn electric_terminal(&self) -> bool {
+        true
+    }
+
+    fn needs_temp(&self) -> bool {
+        self.kind != Logic::Switch
+    }
+
+    fn display_color(&self) -> [u8; 3] {
+        let base: [u8; 3] = match self.kind {
+            Logic::Switch => [150, 150, 160],
+            Logic::Diode => [120, 90, 160],
+            Logic::Not => [170, 80, 80],
+            Logic::And => [80, 120, 170],
+            Logic::Delay => [150, 130, 70],
+            Logic::WaterSensor => [60, 110, 170],
+            Logic::FireSensor => [180, 100, 40],
+            Logic::HeatSensor => [170, 70, 110],
+        };
+        if self.on {
+            base.map(|c| c.saturating_add(70))
+        } else {
+            base
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::cs::PointType;
+
+use super::{CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+
+/// Mirror — static cell that bounces laser beams (see `laser::cast_beams`). The surface
+/// follows the line of neighbouring mirror cells, so a diagonal row turns a beam by 90°.
+#[derive(Default)]
+pub struct Mirror;
+
+impl Mirror {
+    pub const fn new() -> Self {
+        Self
+    }
+
+    pub fn boxed() -> Box<Self> {
+        Box::new(Self::new())
+    }
+
+    pub fn id() -> CellType {
+        96
+    }
+}
+
+impl CellTrait for Mirror {
+    fn update(
+        &self,
+        _i: PointType,
+        _j: PointType,
+        _cur: usize,
+        _container: &mut CellGrid,
+        _pal_container: &CellRegistry,
+        _prng: &mut Prng,
+        _temp_context: Option<&mut TemperatureContext>,
+    ) {
+    }
+
+    fn den(&self) -> i8 {
+        20
+    }
+
+    fn stat(&self) -> bool {
+        true
+    }
+
+    fn blast_resistance(&self) -> f32 {
+        20.0
+    }
+
+    fn reflects_light(&self) -> bool {
+        true
+    }
+
+    fn name(&self) -> &str {
+        "mirror"
+    }
+
+    fn id(&self) -> CellType {
+        Self::id()
+    }
+
+    fn display_color(&self) -> [u8; 3] {
+        [210, 225, 235]
+    }
+}
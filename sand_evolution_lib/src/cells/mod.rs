@@ -1,5 +1,6 @@
 pub mod acid;
 mod base_water;
+pub mod battery;
 pub mod black_hole;
 pub mod burning_coal;
 pub mod burning_gas;
@@ -7,19 +8,24 @@ pub mod burning_powder;
 pub mod burning_wood;
 pub mod coal;
 pub mod crushed_ice;
+pub mod data_cell;
 pub mod powder;
+pub mod reaction;
 mod delute_acid;
 pub mod earth;
+pub mod fan;
 pub mod electricity;
 pub mod fire;
 pub mod gas;
 pub mod copper;
 mod grass;
 pub mod gravel;
-mod helper;
+pub(crate) mod helper;
 pub mod ice;
 pub mod liquid_gas;
-mod salty_water;
+pub mod logic;
+pub mod mirror;
+pub mod salty_water;
 pub mod salt;
 pub mod sand;
 pub mod smoke;
@@ -30,25 +36,32 @@ pub mod void;
 pub mod water;
 pub mod wood;
 
-mod laser;
+pub mod laser;
 pub mod molten_base;
 pub mod molten_salt;
 
 mod dry_grass;
 mod plasma;
 
+use crate::cells::battery::Battery;
 use crate::cells::dry_grass::DryGrass;
 use crate::cells::electricity::Electricity;
+use crate::cells::fan::Fan;
 use crate::cells::grass::Grass;
-use crate::cells::laser::Laser;
+use crate::cells::laser::{Laser, LaserEmitter};
+use crate::cells::logic::{Logic, LogicCell};
+use crate::cells::mirror::Mirror;
 use crate::cells::molten_base::MoltenBase;
 use crate::cells::molten_salt::MoltenSalt;
 use crate::cells::plasma::Plasma;
-use crate::cs::{self, PointType};
+use crate::cs::PointType;
+use crate::simulation::{MIN_HEAT_CAPACITY, PRESSURE_CELL_SIZE, TEMP_MAX, TEMP_MIN, WIND_CELL_SIZE};
 use base_water::BaseWater;
 use delute_acid::DeluteAcid;
+use reaction::ReactionTable;
 use salty_water::SaltyWater;
 use std::collections::HashMap;
+use std::marker::PhantomData;
 
 use self::{
     acid::Acid,
@@ -77,6 +90,168 @@ use self::{
 };
 pub type CellType = u8;
 
+/// One entry of the aux buffer (see `Simulation::set_aux_buffer`): a value a cell keeps
+/// between updates, such as a fuse timer, tagged with the cell type that wrote it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CellAux {
+    pub owner: CellType,
+    pub value: u16,
+}
+
+/// A per-cell buffer reached one element at a time through a raw pointer, never as a whole
+/// slice. The workers of the parallel cell pass (see `Simulation::set_threads`) each hold one
+/// over the same buffer and write near their own chunk; a `&mut [T]` apiece would alias.
+pub struct FieldView<'a, T> {
+    ptr: *mut T,
+    len: usize,
+    buffer: PhantomData<&'a mut [T]>,
+}
+
+impl<'a, T> FieldView<'a, T> {
+    pub fn new(buffer: &'a mut [T]) -> Self {
+        Self {
+            ptr: buffer.as_mut_ptr(),
+            len: buffer.len(),
+            buffer: PhantomData,
+        }
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` must point to `len` elements that stay valid for `'a`, and while this view reads or
+    /// writes an element nothing else may write it.
+    pub unsafe fn from_raw(ptr: *mut T, len: usize) -> Self {
+        Self {
+            ptr,
+            len,
+            buffer: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        // SAFETY: in bounds; see `from_raw` for who else may touch the element.
+        (idx < self.len).then(|| unsafe { &*self.ptr.add(idx) })
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        // SAFETY: as in `get`.
+        (idx < self.len).then(|| unsafe { &mut *self.ptr.add(idx) })
+    }
+
+    #[inline]
+    pub fn swap(&mut self, a: usize, b: usize) {
+        assert!(a < self.len && b < self.len, "swap of {a} and {b} out of {}", self.len);
+        // SAFETY: both in bounds; `ptr::swap` allows `a == b`.
+        unsafe { std::ptr::swap(self.ptr.add(a), self.ptr.add(b)) }
+    }
+}
+
+impl<T> std::ops::Index<usize> for FieldView<'_, T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, idx: usize) -> &T {
+        let len = self.len;
+        self.get(idx)
+            .unwrap_or_else(|| panic!("index {idx} out of a field of {len}"))
+    }
+}
+
+impl<T> std::ops::IndexMut<usize> for FieldView<'_, T> {
+    #[inline]
+    fn index_mut(&mut self, idx: usize) -> &mut T {
+        let len = self.len;
+        self.get_mut(idx)
+            .unwrap_or_else(|| panic!("index {idx} out of a field of {len}"))
+    }
+}
+
+/// The grid as `CellTrait::update` sees it: the cells, indexed like a `[CellType]`, plus the
+/// aux buffer, which is empty while it is off. `swap` moves aux values along with their cells.
+/// Cells that change type some other way keep the slot, but the value only reads back for the
+/// type that wrote it, so a new cell starts from 0 unless it turns back into that type.
+pub struct CellGrid<'a> {
+    cells: FieldView<'a, CellType>,
+    aux: FieldView<'a, CellAux>,
+}
+
+impl<'a> CellGrid<'a> {
+    /// `aux` is either empty or as long as `cells`.
+    pub fn new(cells: &'a mut [CellType], aux: &'a mut [CellAux]) -> Self {
+        Self::from_views(FieldView::new(cells), FieldView::new(aux))
+    }
+
+    pub fn from_views(cells: FieldView<'a, CellType>, aux: FieldView<'a, CellAux>) -> Self {
+        debug_assert!(aux.is_empty() || aux.len() == cells.len());
+        Self { cells, aux }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Swaps two cells together with their aux values.
+    #[inline]
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.cells.swap(a, b);
+        if !self.aux.is_empty() {
+            self.aux.swap(a, b);
+        }
+    }
+
+    pub fn has_aux(&self) -> bool {
+        !self.aux.is_empty()
+    }
+
+    /// Aux value of the cell at `idx`, 0 if the buffer is off or another cell type wrote it.
+    #[inline]
+    pub fn aux(&self, idx: usize) -> u16 {
+        match self.aux.get(idx) {
+            Some(aux) if aux.owner == self.cells[idx] => aux.value,
+            _ => 0,
+        }
+    }
+
+    /// Stores `value` for the cell at `idx`; ignored while the buffer is off.
+    #[inline]
+    pub fn set_aux(&mut self, idx: usize, value: u16) {
+        let owner = self.cells[idx];
+        if let Some(aux) = self.aux.get_mut(idx) {
+            *aux = CellAux { owner, value };
+        }
+    }
+}
+
+impl std::ops::Index<usize> for CellGrid<'_> {
+    type Output = CellType;
+
+    #[inline]
+    fn index(&self, idx: usize) -> &CellType {
+        &self.cells[idx]
+    }
+}
+
+impl std::ops::IndexMut<usize> for CellGrid<'_> {
+    #[inline]
+    fn index_mut(&mut self, idx: usize) -> &mut CellType {
+        &mut self.cells[idx]
+    }
+}
+
 pub struct Prng {
     state: u64,
     carb: i32,
@@ -96,6 +271,21 @@ impl Prng {
         Self { state: seed, carb: 100 }
     }
 
+    /// Fixed-seed generator for reproducible runs (the same seed gives the same sequence).
+    pub fn from_seed(seed: u64) -> Self {
+        let seed = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+        Self { state: seed, carb: 100 }
+    }
+
+    /// Exact generator state, for save files (`from_state` restores it).
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn from_state(state: u64, carb: i32) -> Self {
+        Self { state, carb }
+    }
+
     pub fn gen(&mut self) {
         // Kept for API compatibility; just advance the internal state a bit.
         // (Formerly this refilled the pool via getrandom.)
@@ -108,6 +298,11 @@ impl Prng {
         (self.state >> 56) as u8
     }
 
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = splitmix64(self.state);
+        self.state
+    }
+
     pub fn add_carb(&mut self) {
         self.carb += 1;
     }
@@ -135,6 +330,15 @@ pub struct CellRegistry {
     pub pal: Vec<Box<dyn CellTrait>>,
 
     pub dict: HashMap<String, u8>,
+
+    pub reactions: ReactionTable,
+
+    /// Which way loose cells fall; set through `Simulation::set_gravity`.
+    pub gravity: Gravity,
+
+    /// Lets liquid surfaces even out through the liquid below (see `helper::level_liquid`),
+    /// so communicating vessels settle at one height.
+    pub liquid_levelling: bool,
 }
 
 impl CellRegistry {
@@ -142,35 +346,299 @@ impl CellRegistry {
         let mut me = Self {
             pal: Vec::new(),
             dict: HashMap::new(),
+            reactions: ReactionTable::new(),
+            gravity: Gravity::Down,
+            liquid_levelling: true,
         };
         setup_palette(&mut me);
+        for cell in &me.pal {
+            reaction::add_property_reactions(&mut me.reactions, cell.as_ref());
+        }
+        reaction::setup_reactions(&mut me.reactions);
         me
     }
 }
 
-/// Temperature access for cell updates.
+/// How a loose cell is carried by its velocity (see `helper::momentum_helper`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Momentum {
+    /// Moves one cell per update through its own helpers only.
+    None,
+    /// Falls and flies ballistically, loses sideways speed on the ground.
+    Powder,
+    /// Like `Powder`, but turns the speed of a landing into a sideways splash.
+    Liquid,
+}
+
+/// Direction of gravity for the whole world. Falling helpers work in a frame turned so that
+/// "down" points along it, and gases rise the opposite way.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Gravity {
+    #[default]
+    Down,
+    Up,
+    Left,
+    Right,
+    /// Nothing falls; gases and flames drift in random directions.
+    Zero,
+}
+
+impl Gravity {
+    pub const ALL: [Gravity; 5] = [
+        Gravity::Down,
+        Gravity::Up,
+        Gravity::Left,
+        Gravity::Right,
+        Gravity::Zero,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Gravity::Down => "down",
+            Gravity::Up => "up",
+            Gravity::Left => "left",
+            Gravity::Right => "right",
+            Gravity::Zero => "zero",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|g| g.name() == name)
+    }
+
+    /// Unit pull in grid axes (up is +y); (0, 0) in zero-g.
+    pub fn vector(self) -> (i8, i8) {
+        match self {
+            Gravity::Down => (0, -1),
+            Gravity::Up => (0, 1),
+            Gravity::Left => (-1, 0),
+            Gravity::Right => (1, 0),
+            Gravity::Zero => (0, 0),
+        }
+    }
+
+    /// Grid offset of a local (side, up) offset, where local up points against the pull.
+    /// Zero-g keeps the usual orientation.
+    #[inline]
+    pub fn offset(self, side: i32, up: i32) -> (i32, i32) {
+        match self {
+            Gravity::Down | Gravity::Zero => (side, up),
+            Gravity::Up => (-side, -up),
+            Gravity::Left => (up, -side),
+            Gravity::Right => (-up, side),
+        }
+    }
+
+    /// Grid-axis vector turned into the local (side, up) frame; inverse of `to_grid`.
+    pub fn to_local(self, [x, y]: [f32; 2]) -> [f32; 2] {
+        match self {
+            Gravity::Down | Gravity::Zero => [x, y],
+            Gravity::Up => [-x, -y],
+            Gravity::Left => [-y, x],
+            Gravity::Right => [y, -x],
+        }
+    }
+
+    /// Local (side, up) vector turned into grid axes, like `offset`.
+    pub fn to_grid(self, [side, up]: [f32; 2]) -> [f32; 2] {
+        match self {
+            Gravity::Down | Gravity::Zero => [side, up],
+            Gravity::Up => [-side, -up],
+            Gravity::Left => [up, -side],
+            Gravity::Right => [-up, side],
+        }
+    }
+
+    /// Frame for cells that rise against gravity: in zero-g every call picks a random
+    /// direction, so gases and flames spread out instead of all going one way.
+    #[inline]
+    pub fn rising(self, prng: &mut Prng) -> Self {
+        match self {
+            Gravity::Zero => Self::ALL[(prng.next() % 4) as usize],
+            g => g,
+        }
+    }
+}
+
+/// Raw pointers to the grid and the per-cell fields, taken by `Simulation::cell_fields` in one
+/// go at the start of the cell pass. Cell updates reach the simulation only through the views
+/// made from these, so the workers of the parallel pass can share them.
+#[derive(Clone, Copy)]
+pub(crate) struct CellFields {
+    pub grid: *mut CellType,
+    pub grid_width: usize,
+    pub grid_height: usize,
+    pub aux: *mut CellAux,
+    pub aux_len: usize,
+    pub velocities: *mut [f32; 2],
+    pub velocities_len: usize,
+    pub temps: *mut f32,
+    pub temps_len: usize,
+    pub temp_width: usize,
+    /// Grid cells per temperature cell along each axis.
+    pub temp_scale: usize,
+    pub global_temperature: f32,
+    /// `CellTrait::heat_capacity` per cell type, at least `MIN_HEAT_CAPACITY`.
+    pub heat_capacity: [f32; 256],
+    pub charge: *mut f32,
+    pub charge_len: usize,
+    pub wind: *mut [f32; 2],
+    pub wind_len: usize,
+    pub wind_width: usize,
+    pub pressures: *mut f32,
+    pub pressures_len: usize,
+    pub pressure_width: usize,
+}
+
+impl CellFields {
+    /// # Safety
+    ///
+    /// The buffers must stay valid for `'a` and not be reached through `Simulation` meanwhile.
+    /// Views made from the same `CellFields` on other threads must never touch the same
+    /// elements at the same time.
+    pub unsafe fn grid<'a>(&self) -> CellGrid<'a> {
+        CellGrid::from_views(
+            FieldView::from_raw(self.grid, self.grid_width * self.grid_height),
+            FieldView::from_raw(self.aux, self.aux_len),
+        )
+    }
+
+    /// # Safety
+    ///
+    /// As for `grid`.
+    pub unsafe fn velocities<'a>(&self) -> FieldView<'a, [f32; 2]> {
+        FieldView::from_raw(self.velocities, self.velocities_len)
+    }
+
+    /// # Safety
+    ///
+    /// As for `grid`.
+    pub unsafe fn temperature_context<'a>(&self) -> TemperatureContext<'a> {
+        TemperatureContext {
+            fields: *self,
+            pressure_added: false,
+            pass: PhantomData,
+        }
+    }
+}
+
+/// Temperature, charge, wind and pressure access for cell updates.
 ///
 /// Important: this is used in the *hot path* (per-cell, per-tick), so it must avoid heap
-/// allocations and dynamic dispatch. We store a raw pointer to `State` and provide small
-/// inline methods.
-pub struct TemperatureContext {
-    state_ptr: *mut crate::State,
+/// allocations and dynamic dispatch. It works on the raw `CellFields` of the pass, the same
+/// arithmetic as the `Simulation` methods it mirrors, one element at a time.
+pub struct TemperatureContext<'a> {
+    fields: CellFields,
+    /// For `Simulation::finish_cell_pass`, so `step_pressure` wakes up.
+    pressure_added: bool,
+    pass: PhantomData<&'a mut ()>,
 }
 
-impl TemperatureContext {
+impl<'a> TemperatureContext<'a> {
+    #[inline]
+    fn temps(&self) -> FieldView<'a, f32> {
+        // SAFETY: see `CellFields::temperature_context`; the same holds for the other fields.
+        unsafe { FieldView::from_raw(self.fields.temps, self.fields.temps_len) }
+    }
+
     #[inline]
-    pub fn new(state_ptr: *mut crate::State) -> Self {
-        Self { state_ptr }
+    fn temp_index(&self, x: PointType, y: PointType) -> usize {
+        let scale = self.fields.temp_scale;
+        y as usize / scale * self.fields.temp_width + x as usize / scale
     }
 
+    /// See `Simulation::get_temperature`.
     #[inline]
     pub fn get_temp(&self, x: PointType, y: PointType) -> f32 {
-        unsafe { (*self.state_ptr).get_temperature(x, y) }
+        match self.temps().get(self.temp_index(x, y)) {
+            Some(t) => (t + self.fields.global_temperature).clamp(TEMP_MIN, TEMP_MAX),
+            None => 0.0,
+        }
     }
 
+    /// See `Simulation::add_temperature`.
     #[inline]
     pub fn add_temp(&mut self, x: PointType, y: PointType, delta: f32) {
-        unsafe { (*self.state_ptr).add_temperature(x, y, delta) }
+        let global = self.fields.global_temperature;
+        if let Some(t) = self.temps().get_mut(self.temp_index(x, y)) {
+            *t += delta;
+            *t = (*t + global).clamp(TEMP_MIN, TEMP_MAX) - global;
+        }
+    }
+
+    /// See `Simulation::add_heat`.
+    #[inline]
+    pub fn add_heat(&mut self, x: PointType, y: PointType, energy: f32) {
+        let fields = &self.fields;
+        let scale = fields.temp_scale;
+        let (x0, y0) = (x as usize / scale * scale, y as usize / scale * scale);
+        // SAFETY: as in `temps`.
+        let cells = fields.grid_width * fields.grid_height;
+        let grid = unsafe { FieldView::from_raw(fields.grid, cells) };
+        let mut capacity = 0.0;
+        for y in y0..(y0 + scale).min(fields.grid_height) {
+            for x in x0..(x0 + scale).min(fields.grid_width) {
+                capacity += fields.heat_capacity[grid[y * fields.grid_width + x] as usize];
+            }
+        }
+        self.add_temp(x, y, energy / capacity.max(MIN_HEAT_CAPACITY));
+    }
+
+    /// Electric charge of a cell, see `Simulation::step_electricity`.
+    #[inline]
+    pub fn get_charge(&self, x: PointType, y: PointType) -> f32 {
+        let fields = &self.fields;
+        // SAFETY: as in `temps`.
+        let charge = unsafe { FieldView::from_raw(fields.charge, fields.charge_len) };
+        let idx = y as usize * fields.grid_width + x as usize;
+        charge.get(idx).copied().unwrap_or(0.0)
+    }
+
+    #[inline]
+    fn wind(&self) -> FieldView<'a, [f32; 2]> {
+        // SAFETY: as in `temps`.
+        unsafe { FieldView::from_raw(self.fields.wind, self.fields.wind_len) }
+    }
+
+    #[inline]
+    fn wind_index(&self, x: PointType, y: PointType) -> usize {
+        let scale = WIND_CELL_SIZE as usize;
+        y as usize / scale * self.fields.wind_width + x as usize / scale
+    }
+
+    /// See `Simulation::get_wind`.
+    #[inline]
+    pub fn get_wind(&self, x: PointType, y: PointType) -> [f32; 2] {
+        self.wind().get(self.wind_index(x, y)).copied().unwrap_or([0.0; 2])
+    }
+
+    /// Fans: see `Simulation::add_wind`.
+    #[inline]
+    pub fn add_wind(&mut self, x: PointType, y: PointType, wind: [f32; 2]) {
+        if let Some(w) = self.wind().get_mut(self.wind_index(x, y)) {
+            *w = [w[0] + wind[0], w[1] + wind[1]];
+        }
+    }
+
+    /// Explosions: see `Simulation::add_pressure`.
+    #[inline]
+    pub fn add_pressure(&mut self, x: PointType, y: PointType, amount: f32) {
+        let fields = &self.fields;
+        let scale = PRESSURE_CELL_SIZE as usize;
+        if x as usize / scale >= fields.pressure_width {
+            return;
+        }
+        let idx = y as usize / scale * fields.pressure_width + x as usize / scale;
+        // SAFETY: as in `temps`.
+        let mut pressures = unsafe { FieldView::from_raw(fields.pressures, fields.pressures_len) };
+        if let Some(p) = pressures.get_mut(idx) {
+            *p = (*p + amount).max(0.0);
+            self.pressure_added = true;
+        }
+    }
+
+    pub(crate) fn pressure_added(&self) -> bool {
+        self.pressure_added
     }
 }
 
@@ -180,7 +648,7 @@ pub trait CellTrait {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -229,15 +697,97 @@ pub trait CellTrait {
     fn ignition_temperature(&self) -> Option<f32> {
         None
     }
+    /// How readily heat flows through this cell (see `Simulation::diffuse_temperature`).
+    /// Stone is 0.4, copper 2.5, air (void) 0.3.
     fn thermal_conductivity(&self) -> f32 {
-        0.0
+        0.5
     }
+    /// How much of this cell's heat rises each diffusion pass (1.0 for air and other gases).
     fn convection_factor(&self) -> f32 {
         0.0
     }
+    /// Energy to warm this cell by one degree, relative to stone. Water is 4.0.
+    fn heat_capacity(&self) -> f32 {
+        1.0
+    }
+    /// Energy stored in this phase, relative to the coldest phase of the same substance
+    /// (ice 0, water 40, steam 440). `helper::change_phase` takes the difference out of the
+    /// temperature field when a cell melts or boils and gives it back when it freezes or condenses.
+    fn latent_heat(&self) -> f32 {
+        0.0
+    }
+    /// Blast pressure that breaks a static cell into `rubble()`. Loose cells are thrown
+    /// instead of broken, so this only matters for static ones; infinite means unbreakable.
+    fn blast_resistance(&self) -> f32 {
+        f32::INFINITY
+    }
+    fn rubble(&self) -> CellType {
+        Void::id()
+    }
+    /// Share of electric charge this cell passes on to the next one (see
+    /// `Simulation::step_electricity`); the rest is lost as heat. 0 is an insulator,
+    /// copper is 0.998 and salty water 0.95.
+    fn electrical_conductivity(&self) -> f32 {
+        0.0
+    }
+    /// Power source: holds full charge and feeds every conductor connected to it.
+    fn electric_source(&self) -> bool {
+        false
+    }
+    /// For sources: feed only the neighbour at this (x, y) offset instead of all four,
+    /// so gates don't power their own inputs.
+    fn electric_output(&self) -> Option<(i8, i8)> {
+        None
+    }
+    /// Whether wires connect to this cell; a conductor ending next to a terminal is not a
+    /// loose tip and doesn't spark. Logic cells are terminals even while off.
+    fn electric_terminal(&self) -> bool {
+        self.electric_source() || self.electrical_conductivity() > 0.0
+    }
+    /// Share of a laser beam's power that crosses this cell (see `laser::cast_beams`); the rest
+    /// heats it. 0 is opaque: the beam stops here and all of its power turns into heat.
+    fn light_transmission(&self) -> f32 {
+        0.0
+    }
+    /// Bends beams crossing into or out of this cell by Snell's law; air is 1.0, water 1.33.
+    fn refractive_index(&self) -> f32 {
+        1.0
+    }
+    /// Mirrors bounce beams off instead of absorbing them.
+    fn reflects_light(&self) -> bool {
+        false
+    }
+    /// Laser emitters: cast a beam in this (x, y) direction every tick.
+    fn light_emitter(&self) -> Option<(i8, i8)> {
+        None
+    }
+    /// How strongly the wind field carries this cell (see `helper::wind_helper`); 1.0 drifts
+    /// with the air, 0 ignores it.
+    fn wind_drag(&self) -> f32 {
+        0.0
+    }
+    /// How thick a liquid is for `helper::fluid_falling_helper`: it spreads sideways on one
+    /// update in `viscosity()` and levels out that much slower. 1 is water.
+    fn viscosity(&self) -> u8 {
+        1
+    }
+    /// How this cell moves while the velocity buffer is on (see `helper::momentum_helper`).
+    fn momentum(&self) -> Momentum {
+        Momentum::None
+    }
     fn needs_temp(&self) -> bool {
         false
     }
+    /// Whether a chunk holding this cell must keep updating even though its cells stay the
+    /// same (see `chunk_sleep`), e.g. because the cell drives a field instead of the grid.
+    fn keeps_awake(&self) -> bool {
+        false
+    }
+    /// Whether the cell comes from a materials file (see `data_cell`), so a later file may
+    /// replace it in its slot.
+    fn is_data_cell(&self) -> bool {
+        false
+    }
     fn display_color(&self) -> [u8; 3] {
         [200, 200, 200]
     }
@@ -280,14 +830,29 @@ pub fn setup_palette(cell_registry: &mut CellRegistry) {
     cell_registry.pal[60] = Electricity::boxed();
     cell_registry.pal[61] = Plasma::boxed();
     cell_registry.pal[62] = Laser::boxed();
+    cell_registry.pal[63] = Battery::boxed();
+    for kind in Logic::ALL {
+        for on in [false, true] {
+            cell_registry.pal[kind.id(on) as usize] = LogicCell::boxed(kind, on);
+        }
+    }
     cell_registry.pal[70] = Grass::boxed();
     cell_registry.pal[71] = DryGrass::boxed();
     cell_registry.pal[80] = BlackHole::boxed();
     cell_registry.pal[83] = DeluteAcid::boxed();
-    cell_registry.pal[84] = SaltyWater::boxed();
+    for level in 1..=SaltyWater::SATURATED {
+        cell_registry.pal[SaltyWater::with_level(level) as usize] = SaltyWater::boxed(level);
+    }
     cell_registry.pal[85] = BaseWater::boxed();
     cell_registry.pal[86] = MoltenSalt::boxed();
     cell_registry.pal[87] = MoltenBase::boxed();
+    for facing in 0..LaserEmitter::FACINGS.len() {
+        cell_registry.pal[LaserEmitter::id(facing) as usize] = LaserEmitter::boxed(facing);
+    }
+    cell_registry.pal[96] = Mirror::boxed();
+    for facing in 0..Fan::FACINGS.len() {
+        cell_registry.pal[Fan::id(facing) as usize] = Fan::boxed(facing);
+    }
     cell_registry.pal[255] = stone::Stone::boxed();
 
     let mut index = 0;
@@ -1,9 +1,11 @@
 use crate::cells::base_water::BaseWater;
-use crate::cells::helper::fluid_falling_helper;
+use crate::cells::helper::{change_phase, fluid_falling_helper};
 use crate::cells::sand::Base;
 use crate::cells::salt::Salt;
 use crate::cells::void::Void;
-use crate::cells::{CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use crate::cells::{
+    CellGrid, CellRegistry, CellTrait, CellType, Momentum, Prng, TemperatureContext,
+};
 use crate::cs;
 use crate::cs::PointType;
 
@@ -27,7 +29,7 @@ impl CellTrait for MoltenBase {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         mut temp_context: Option<&mut TemperatureContext>,
@@ -35,21 +37,19 @@ impl CellTrait for MoltenBase {
         if let Some(ref mut temp_ctx) = temp_context {
             let temperature = temp_ctx.get_temp(i, j);
 
+            // Crystallizing gives back the latent heat taken when melting.
             const TARGET_TEMP: f32 = 300.0;
             let deficit = TARGET_TEMP - temperature;
             if deficit > 0.0 {
-                let heat = deficit * 0.35;
-                temp_ctx.add_temp(i, j, heat);
-
                 let crystal_chance = ((deficit / TARGET_TEMP) * 35.0).clamp(0.0, 255.0) as u8;
                 if prng.next() < crystal_chance {
-                    container[cur] = Base::id();
+                    change_phase(i, j, cur, Base::id(), container, pal_container, temp_ctx);
                     return;
                 }
             }
         }
 
-        if !fluid_falling_helper(self.den(), i, j, container, pal_container, cur, prng, 2) {
+        if !fluid_falling_helper(self.den(), i, j, container, pal_container, cur, prng) {
             let top = cs::xy_to_index(i, j + 1);
             let down = cs::xy_to_index(i, j - 1);
             let r = cs::xy_to_index(i + 1, j);
@@ -84,6 +84,9 @@ impl CellTrait for MoltenBase {
     fn den(&self) -> i8 {
         4
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Liquid
+    }
 
     fn proton_transfer(&self) -> CellType {
         Salt::id()
@@ -109,9 +112,15 @@ impl CellTrait for MoltenBase {
         Void::id()
     }
 
+    fn latent_heat(&self) -> f32 {
+        60.0
+    }
     fn needs_temp(&self) -> bool {
         true
     }
+    fn viscosity(&self) -> u8 {
+        2
+    }
     fn name(&self) -> &str {
         "molten base"
     }
@@ -1,8 +1,10 @@
-use crate::cells::helper::fluid_falling_helper;
+use crate::cells::helper::{change_phase, fluid_falling_helper};
 use crate::cells::salt::Salt;
 use crate::cells::salty_water::SaltyWater;
 use crate::cells::void::Void;
-use crate::cells::{CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use crate::cells::{
+    CellGrid, CellRegistry, CellTrait, CellType, Momentum, Prng, TemperatureContext,
+};
 use crate::cs::PointType;
 
 pub struct MoltenSalt;
@@ -25,7 +27,7 @@ impl CellTrait for MoltenSalt {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -33,26 +35,30 @@ impl CellTrait for MoltenSalt {
         if let Some(temp_ctx) = temp_context {
             let temperature = temp_ctx.get_temp(i, j);
 
+            // Crystallizing gives back the latent heat taken when melting.
             const TARGET_TEMP: f32 = 400.0;
             let deficit = TARGET_TEMP - temperature;
             if deficit > 0.0 {
-                let heat = deficit * 0.4;
-                temp_ctx.add_temp(i, j, heat);
-
                 let crystal_chance = ((deficit / TARGET_TEMP) * 30.0).clamp(0.0, 255.0) as u8;
                 if prng.next() < crystal_chance {
-                    container[cur] = Salt::id();
+                    change_phase(i, j, cur, Salt::id(), container, pal_container, temp_ctx);
                     return;
                 }
             }
         }
 
-        fluid_falling_helper(self.den(), i, j, container, pal_container, cur, prng, 2);
+        fluid_falling_helper(self.den(), i, j, container, pal_container, cur, prng);
     }
 
     fn den(&self) -> i8 {
         4
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Liquid
+    }
+    fn electrical_conductivity(&self) -> f32 {
+        0.9
+    }
 
     fn dissolve(&self) -> CellType {
         SaltyWater::id()
@@ -74,9 +80,15 @@ impl CellTrait for MoltenSalt {
         Void::id()
     }
 
+    fn latent_heat(&self) -> f32 {
+        60.0
+    }
     fn needs_temp(&self) -> bool {
         true
     }
+    fn viscosity(&self) -> u8 {
+        2
+    }
     fn name(&self) -> &str {
         "molten salt"
     }
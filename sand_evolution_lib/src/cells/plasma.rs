@@ -1,7 +1,7 @@
 use crate::cells::helper::fluid_falling_helper;
 use crate::cs::{self, PointType};
 
-use super::{void::Void, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use super::{void::Void, CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
 
 pub struct Plasma;
 impl Plasma {
@@ -21,7 +21,7 @@ impl CellTrait for Plasma {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         _pal_container: &CellRegistry,
         prng: &mut Prng,
         _: Option<&mut TemperatureContext>,
@@ -53,7 +53,7 @@ impl CellTrait for Plasma {
         }
 
         if prng.next() > 200
-            && fluid_falling_helper(self.den(), i, j, container, _pal_container, cur, prng, 10)
+            && fluid_falling_helper(self.den(), i, j, container, _pal_container, cur, prng)
         {
             return;
         }
@@ -81,6 +81,9 @@ impl CellTrait for Plasma {
     fn casts_shadow(&self) -> bool {
         false
     }
+    fn viscosity(&self) -> u8 {
+        10
+    }
     fn name(&self) -> &str {
         "plasma"
     }
@@ -1,8 +1,8 @@
 use crate::cs::{self, PointType};
 
 use super::{
-    burning_powder::BurningPowder, burning_gas::BurningGas, helper::sand_falling_helper, CellRegistry,
-    CellTrait, CellType, Prng, TemperatureContext,
+    burning_gas::BurningGas, burning_powder::BurningPowder, helper::sand_falling_helper,
+    CellGrid, CellRegistry, CellTrait, CellType, Momentum, Prng, TemperatureContext,
 };
 
 pub struct Powder;
@@ -24,7 +24,7 @@ impl CellTrait for Powder {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         dim: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -51,6 +51,9 @@ impl CellTrait for Powder {
     fn den(&self) -> i8 {
         10
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Powder
+    }
     fn burnable(&self) -> u8 {
         BurningPowder::id()
     }
@@ -0,0 +1,192 @@
+//! Pairwise reactions between neighbouring cells.
+//!
+//! Every tick a reactive cell picks one random neighbour and looks the pair up here
+//! (see `helper::try_react`). Reactions can also be declared in a materials TOML file
+//! (see `data_cell`).
+
+use ahash::AHashMap;
+
+use super::{
+    acid::Acid, base_water::BaseWater, delute_acid::DeluteAcid, dry_grass::DryGrass, grass::Grass,
+    ice::Ice, salt::Salt, salty_water::SaltyWater, sand::Base, void::Void, water::Water, CellTrait,
+    CellType,
+};
+
+/// Chance of the acid and water reactions derived from cell properties. Both cells of a pair
+/// check it, falling or not, so it is well below the 205 the liquids used to roll themselves.
+const CHANNEL_CHANCE: u8 = 64;
+
+/// Degrees a reacting acid cell releases, what it used to spread over itself and its four
+/// neighbours.
+const ACID_HEAT: f32 = 140.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Reaction {
+    /// Chance out of 255 per check that the pair reacts.
+    pub chance: u8,
+    /// What the checking cell becomes.
+    pub into: CellType,
+    /// What the neighbour becomes.
+    pub other_into: CellType,
+    /// Degrees added at the checking cell when the reaction happens (negative absorbs heat).
+    pub heat: f32,
+    /// Blast pressure released at the checking cell (see `Simulation::add_pressure`).
+    pub pressure: f32,
+    /// The reaction only happens while the checking cell's temperature is in this range.
+    pub min_temperature: f32,
+    pub max_temperature: f32,
+}
+
+impl Reaction {
+    pub fn new(chance: u8, into: CellType, other_into: CellType) -> Self {
+        Self {
+            chance,
+            into,
+            other_into,
+            heat: 0.0,
+            pressure: 0.0,
+            min_temperature: f32::NEG_INFINITY,
+            max_temperature: f32::INFINITY,
+        }
+    }
+
+    pub fn with_heat(mut self, heat: f32) -> Self {
+        self.heat = heat;
+        self
+    }
+
+    pub fn with_pressure(mut self, pressure: f32) -> Self {
+        self.pressure = pressure;
+        self
+    }
+
+    pub fn with_temperature_window(mut self, min: f32, max: f32) -> Self {
+        self.min_temperature = min;
+        self.max_temperature = max;
+        self
+    }
+
+    fn mirrored(self) -> Self {
+        Self {
+            into: self.other_into,
+            other_into: self.into,
+            ..self
+        }
+    }
+}
+
+pub struct ReactionTable {
+    reactive: [bool; 256],
+    pairs: AHashMap<(CellType, CellType), Reaction>,
+}
+
+impl Default for ReactionTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReactionTable {
+    pub fn new() -> Self {
+        Self {
+            reactive: [false; 256],
+            pairs: AHashMap::new(),
+        }
+    }
+
+    /// Registers `a` next to `b` reacting into `reaction.into` / `reaction.other_into`.
+    /// The pair is checked from both sides, so it also applies when `b` is the cell being updated.
+    /// Replaces an earlier reaction for the same pair.
+    pub fn add(&mut self, a: CellType, b: CellType, reaction: Reaction) {
+        self.reactive[a as usize] = true;
+        self.reactive[b as usize] = true;
+        self.pairs.insert((a, b), reaction);
+        self.pairs.insert((b, a), reaction.mirrored());
+    }
+
+    #[inline]
+    pub fn is_reactive(&self, cell: CellType) -> bool {
+        self.reactive[cell as usize]
+    }
+
+    #[inline]
+    pub fn get(&self, cell: CellType, neighbour: CellType) -> Option<&Reaction> {
+        self.pairs.get(&(cell, neighbour))
+    }
+
+    /// All registered reactions, once per pair (`a <= b`), sorted by ids.
+    pub fn list(&self) -> Vec<(CellType, CellType, Reaction)> {
+        let mut out: Vec<_> = self
+            .pairs
+            .iter()
+            .filter(|((a, b), _)| a <= b)
+            .map(|(&(a, b), &r)| (a, b, r))
+            .collect();
+        out.sort_by_key(|&(a, b, _)| (a, b));
+        out
+    }
+}
+
+/// Registers what acid and water do to `cell` through its `proton_transfer`, `heatable` and
+/// `dissolve` properties. Runs for every palette cell, materials loaded later included, before
+/// the explicit pairs of `setup_reactions` and materials files, which take precedence.
+pub fn add_property_reactions(table: &mut ReactionTable, cell: &dyn CellTrait) {
+    let id = cell.id();
+    if id == Void::id() {
+        return;
+    }
+
+    // Water takes the place of what it dissolves.
+    if cell.dissolve() != Void::id() {
+        table.add(
+            Water::id(),
+            id,
+            Reaction::new(CHANNEL_CHANCE, cell.dissolve(), Void::id()),
+        );
+    }
+
+    // Acid gives its protons away and is diluted; otherwise it only heats the cell through.
+    let acid = if cell.proton_transfer() != Void::id() {
+        Reaction::new(CHANNEL_CHANCE, DeluteAcid::id(), cell.proton_transfer())
+    } else if cell.heatable() != Void::id() {
+        Reaction::new(CHANNEL_CHANCE, Acid::id(), cell.heatable())
+    } else {
+        return;
+    };
+    table.add(Acid::id(), id, acid.with_heat(ACID_HEAT));
+}
+
+pub fn setup_reactions(table: &mut ReactionTable) {
+    // Alkali dries grass out.
+    table.add(Grass::id(), Base::id(), Reaction::new(255, DryGrass::id(), Base::id()));
+    table.add(
+        Grass::id(),
+        BaseWater::id(),
+        Reaction::new(255, DryGrass::id(), BaseWater::id()),
+    );
+    // Dry patches spread very slowly.
+    table.add(
+        Grass::id(),
+        DryGrass::id(),
+        Reaction::new(1, DryGrass::id(), DryGrass::id()),
+    );
+    // Salt melts ice below freezing, taking heat from the surroundings.
+    table.add(
+        Salt::id(),
+        Ice::id(),
+        Reaction::new(12, Void::id(), SaltyWater::id())
+            .with_heat(-2.0)
+            .with_temperature_window(-21.0, 0.0),
+    );
+    // Acid neutralised by salt leaves brine; diluting it gives off heat too.
+    table.add(
+        Acid::id(),
+        Salt::id(),
+        Reaction::new(CHANNEL_CHANCE, DeluteAcid::id(), SaltyWater::id()).with_heat(ACID_HEAT),
+    );
+    table.add(
+        Acid::id(),
+        Water::id(),
+        Reaction::new(CHANNEL_CHANCE, DeluteAcid::id(), DeluteAcid::id()).with_heat(ACID_HEAT),
+    );
+}
@@ -3,7 +3,8 @@ use crate::cells::salty_water::SaltyWater;
 use crate::cs::PointType;
 
 use super::{
-    helper::sand_falling_helper, CellRegistry, CellTrait, CellType, Prng, TemperatureContext,
+    helper::{change_phase, sand_falling_helper},
+    CellGrid, CellRegistry, CellTrait, CellType, Momentum, Prng, TemperatureContext,
 };
 
 pub struct Salt;
@@ -24,7 +25,7 @@ impl CellTrait for Salt {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -36,8 +37,7 @@ impl CellTrait for Salt {
                 let over = (temperature - MELT_POINT) / 200.0;
                 let chance = (over * 40.0).clamp(0.0, 255.0) as u8;
                 if prng.next() < chance {
-                    container[cur] = MoltenSalt::id();
-                    temp_ctx.add_temp(i, j, -(temperature - MELT_POINT) * 0.5);
+                    change_phase(i, j, cur, MoltenSalt::id(), container, pal_container, temp_ctx);
                     return;
                 }
             }
@@ -47,6 +47,9 @@ impl CellTrait for Salt {
     fn den(&self) -> i8 {
         10
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Powder
+    }
     fn dissolve(&self) -> CellType {
         SaltyWater::id()
     }
@@ -1,21 +1,53 @@
+//! Salt solutions.
+//!
+//! The concentration is part of the cell id: one id per `level` of dissolved salt, from 1
+//! ("salty water") up to `SaltyWater::SATURATED`. Water counts as level 0. Every grain a
+//! solution dissolves raises its level by one, neighbours far enough apart even out one level
+//! at a time, and stronger brine is denser, so it sinks under fresh water. Boiling hands the
+//! salt on to a neighbouring solution with room for it; a cell that can't pass it on
+//! crystallises back into `Salt`.
+
 use crate::cells::helper::fluid_falling_helper;
 use crate::cells::salt::Salt;
 use crate::cells::steam::Steam;
-use crate::cells::{CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
-use crate::cs::PointType;
+use crate::cells::void::Void;
+use crate::cells::water::Water;
+use crate::cells::{
+    CellGrid, CellRegistry, CellTrait, CellType, Momentum, Prng, TemperatureContext,
+};
+use crate::cs::{self, PointType};
 
-pub struct SaltyWater;
+pub struct SaltyWater {
+    level: u8,
+}
 
 impl SaltyWater {
-    pub const fn new() -> Self {
-        Self
+    /// Highest concentration; a saturated solution dissolves no more salt.
+    pub const SATURATED: u8 = 4;
+    const NAMES: [&'static str; 4] = ["salty water", "brine", "strong brine", "saturated brine"];
+
+    pub const fn new(level: u8) -> Self {
+        Self { level }
     }
-    pub fn boxed() -> Box<Self> {
-        Box::new(Self::new())
+    pub fn boxed(level: u8) -> Box<Self> {
+        Box::new(Self::new(level))
     }
+    /// The weakest solution, what water and a grain of salt make.
     pub fn id() -> CellType {
         84
     }
+    /// Id of the solution at `level`; level 0 is plain water.
+    pub fn with_level(level: u8) -> CellType {
+        match level {
+            0 => Water::id(),
+            1 => Self::id(),
+            _ => 99 + level.min(Self::SATURATED) as CellType,
+        }
+    }
+    /// Concentration of `id`: `Some(0)` for water, `None` for anything that isn't a solution.
+    pub fn level_of(id: CellType) -> Option<u8> {
+        (0..=Self::SATURATED).find(|&level| Self::with_level(level) == id)
+    }
 }
 
 impl CellTrait for SaltyWater {
@@ -24,54 +56,102 @@ impl CellTrait for SaltyWater {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         dim: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
     ) {
+        let arr = [
+            cs::xy_to_index(i, j + 1),
+            cs::xy_to_index(i, j - 1),
+            cs::xy_to_index(i - 1, j),
+            cs::xy_to_index(i + 1, j),
+        ];
+
         if let Some(temp_ctx) = temp_context {
             let temperature = temp_ctx.get_temp(i, j);
 
             if temperature >= 105.0 {
                 if dim.next() < 100 {
-                    if dim.next() < 128 {
-                        container[cur] = Steam::id();
-                    } else {
-                        container[cur] = Salt::id();
+                    // The water boils off either way; salt left behind keeps no latent heat.
+                    let boiled = pal_container.pal[Steam::id() as usize].latent_heat();
+                    temp_ctx.add_heat(i, j, self.latent_heat() - boiled);
+                    let cc = arr[(dim.next() % 4) as usize];
+                    match Self::level_of(container[cc]) {
+                        Some(level) if level + self.level <= Self::SATURATED => {
+                            container[cc] = Self::with_level(level + self.level);
+                            container[cur] = Steam::id();
+                        }
+                        _ => container[cur] = Salt::id(),
                     }
-                    const EVAP_COOLING: f32 = 50.0;
-                    temp_ctx.add_temp(i, j, -EVAP_COOLING);
-                    temp_ctx.add_temp(i, j + 1, -EVAP_COOLING * 0.5);
-                    temp_ctx.add_temp(i, j - 1, -EVAP_COOLING * 0.5);
-                    temp_ctx.add_temp(i + 1, j, -EVAP_COOLING * 0.5);
-                    temp_ctx.add_temp(i - 1, j, -EVAP_COOLING * 0.5);
                     return;
                 }
             }
         }
 
-        fluid_falling_helper(self.den(), i, j, container, pal_container, cur, dim, 1);
+        if fluid_falling_helper(self.den(), i, j, container, pal_container, cur, dim) {
+            return;
+        }
+
+        let cc = arr[(dim.next() % 4) as usize];
+        if dim.next() > 50 {
+            let cc_v = container[cc];
+            if self.level < Self::SATURATED
+                && pal_container.pal[cc_v as usize].dissolve() == Self::id()
+            {
+                container[cc] = Void::id();
+                container[cur] = Self::with_level(self.level + 1);
+                return;
+            }
+            // Diffusion: a step of one level would just swap the two, so only wider gaps mix.
+            if let Some(level) = Self::level_of(cc_v) {
+                if level + 2 <= self.level {
+                    container[cc] = Self::with_level(level + 1);
+                    container[cur] = Self::with_level(self.level - 1);
+                }
+            }
+        }
     }
 
     fn den(&self) -> i8 {
-        2
+        2 + (self.level as i8 - 1) / 2
+    }
+    fn momentum(&self) -> Momentum {
+        Momentum::Liquid
+    }
+    fn electrical_conductivity(&self) -> f32 {
+        0.95
     }
 
     fn shadow_rgba(&self) -> [u8; 4] {
         [205, 220, 255, 115]
     }
 
+    fn heat_capacity(&self) -> f32 {
+        4.0
+    }
+    fn latent_heat(&self) -> f32 {
+        40.0
+    }
     fn needs_temp(&self) -> bool {
         true
     }
+    fn light_transmission(&self) -> f32 {
+        0.98 - 0.01 * (self.level - 1) as f32
+    }
+    fn refractive_index(&self) -> f32 {
+        1.33 + 0.01 * self.level as f32
+    }
     fn name(&self) -> &str {
-        "salty water"
+        Self::NAMES[self.level as usize - 1]
     }
 
     fn id(&self) -> CellType {
-        84
+        Self::with_level(self.level)
     }
     fn display_color(&self) -> [u8; 3] {
-        [128, 128, 255]
+        // Cloudier as it gets stronger.
+        let shade = 30 * (self.level - 1);
+        [128 + shade, 128 + shade, 255 - shade / 2]
     }
 }
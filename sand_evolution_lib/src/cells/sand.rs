@@ -4,8 +4,9 @@ use crate::cells::salt::Salt;
 use crate::cs::PointType;
 
 use super::{
-    gas::Gas, helper::sand_falling_helper, CellRegistry, CellTrait, CellType, Prng,
-    TemperatureContext,
+    gas::Gas,
+    helper::{change_phase, sand_falling_helper}, CellGrid, CellRegistry, CellTrait, CellType,
+    Momentum, Prng, TemperatureContext,
 };
 
 pub struct Sand;
@@ -26,7 +27,7 @@ impl CellTrait for Sand {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         _: Option<&mut TemperatureContext>,
@@ -36,6 +37,9 @@ impl CellTrait for Sand {
     fn den(&self) -> i8 {
         10
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Powder
+    }
     fn thermal_conductivity(&self) -> f32 {
         0.5
     }
@@ -71,7 +75,7 @@ impl CellTrait for Base {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -83,8 +87,7 @@ impl CellTrait for Base {
                 let over = (temperature - MELT_POINT) / 150.0;
                 let chance = (over * 50.0).clamp(0.0, 255.0) as u8;
                 if prng.next() < chance {
-                    container[cur] = MoltenBase::id();
-                    temp_ctx.add_temp(i, j, -(temperature - MELT_POINT) * 0.5);
+                    change_phase(i, j, cur, MoltenBase::id(), container, pal_container, temp_ctx);
                     return;
                 }
             }
@@ -94,6 +97,9 @@ impl CellTrait for Base {
     fn den(&self) -> i8 {
         10
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Powder
+    }
     fn proton_transfer(&self) -> CellType {
         Salt::id()
     }
@@ -20,7 +20,7 @@ impl CellTrait for Smoke {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         _temp_context: Option<&mut TemperatureContext>,
@@ -52,10 +52,22 @@ impl CellTrait for Smoke {
         -1
     }
 
+    fn thermal_conductivity(&self) -> f32 {
+        0.3
+    }
+    fn convection_factor(&self) -> f32 {
+        1.0
+    }
     fn casts_shadow(&self) -> bool {
         false
     }
 
+    fn light_transmission(&self) -> f32 {
+        0.85
+    }
+    fn wind_drag(&self) -> f32 {
+        1.0
+    }
     fn name(&self) -> &str {
         "smoke"
     }
@@ -1,7 +1,8 @@
 use crate::cs::{self, PointType};
 
 use super::{
-    helper::fluid_falling_helper, water::Water, CellRegistry, CellTrait, CellType, Prng,
+    helper::{change_phase, fluid_falling_helper},
+    water::Water, CellGrid, CellRegistry, CellTrait, CellType, Prng,
     TemperatureContext,
 };
 
@@ -23,7 +24,7 @@ impl CellTrait for Snow {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -32,19 +33,13 @@ impl CellTrait for Snow {
             let temperature = temp_ctx.get_temp(i, j);
 
             if temperature > 0.0 {
-                container[cur] = Water::id();
-                const MELT_COOLING: f32 = 2.0;
-                temp_ctx.add_temp(i, j, -MELT_COOLING);
-                temp_ctx.add_temp(i, j + 1, -MELT_COOLING);
-                temp_ctx.add_temp(i, j - 1, -MELT_COOLING);
-                temp_ctx.add_temp(i + 1, j, -MELT_COOLING);
-                temp_ctx.add_temp(i - 1, j, -MELT_COOLING);
+                change_phase(i, j, cur, Water::id(), container, pal_container, temp_ctx);
                 return;
             }
         }
 
         if prng.next() > 128
-            && fluid_falling_helper(self.den(), i, j, container, pal_container, cur, prng, 10)
+            && fluid_falling_helper(self.den(), i, j, container, pal_container, cur, prng)
         {
             return;
         }
@@ -74,9 +69,21 @@ impl CellTrait for Snow {
     fn heatable(&self) -> CellType {
         Water::id()
     }
+    fn heat_capacity(&self) -> f32 {
+        2.0
+    }
     fn needs_temp(&self) -> bool {
         true
     }
+    fn blast_resistance(&self) -> f32 {
+        5.0
+    }
+    fn wind_drag(&self) -> f32 {
+        0.6
+    }
+    fn viscosity(&self) -> u8 {
+        10
+    }
     fn name(&self) -> &str {
         "snow"
     }
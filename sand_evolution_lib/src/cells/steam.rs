@@ -1,4 +1,7 @@
-use super::{helper::fluid_flying_helper, TemperatureContext, *};
+use super::{
+    helper::{change_phase, fluid_flying_helper},
+    TemperatureContext, *,
+};
 use crate::cs::PointType;
 
 pub struct Steam;
@@ -20,7 +23,7 @@ impl CellTrait for Steam {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         prng: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -30,7 +33,7 @@ impl CellTrait for Steam {
 
             if temperature < 0.0 && prng.next() < 10 {
                 use super::water::Water;
-                container[cur] = Water::id();
+                change_phase(i, j, cur, Water::id(), container, pal_container, temp_ctx);
                 return;
             }
         }
@@ -41,13 +44,31 @@ impl CellTrait for Steam {
     fn den(&self) -> i8 {
         -1
     }
+    fn thermal_conductivity(&self) -> f32 {
+        0.3
+    }
+    fn convection_factor(&self) -> f32 {
+        1.0
+    }
     fn casts_shadow(&self) -> bool {
         false
     }
 
+    fn heat_capacity(&self) -> f32 {
+        2.0
+    }
+    fn latent_heat(&self) -> f32 {
+        440.0
+    }
     fn needs_temp(&self) -> bool {
         true
     }
+    fn light_transmission(&self) -> f32 {
+        0.97
+    }
+    fn wind_drag(&self) -> f32 {
+        1.0
+    }
     fn name(&self) -> &str {
         "steam"
     }
@@ -1,6 +1,6 @@
 use crate::cs::PointType;
 
-use super::{CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use super::{CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
 
 pub struct Stone;
 impl Stone {
@@ -20,7 +20,7 @@ impl CellTrait for Stone {
         _: PointType,
         _: PointType,
         _: usize,
-        _: &mut [CellType],
+        _: &mut CellGrid,
         _: &CellRegistry,
         _: &mut Prng,
         _: Option<&mut TemperatureContext>,
@@ -31,6 +31,13 @@ impl CellTrait for Stone {
         true
     }
 
+    /// Cracks into gravel.
+    fn blast_resistance(&self) -> f32 {
+        120.0
+    }
+    fn rubble(&self) -> CellType {
+        super::gravel::Gravel::id()
+    }
     fn name(&self) -> &str {
         "stone"
     }
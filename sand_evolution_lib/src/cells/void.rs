@@ -1,6 +1,6 @@
 use crate::cs::PointType;
 
-use super::{CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
+use super::{CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext};
 
 pub struct Void;
 impl Void {
@@ -20,15 +20,25 @@ impl CellTrait for Void {
         _: PointType,
         _: PointType,
         _: usize,
-        _: &mut [u8],
+        _: &mut CellGrid,
         _: &CellRegistry,
         _: &mut Prng,
         _: Option<&mut TemperatureContext>,
     ) {
     }
+    fn light_transmission(&self) -> f32 {
+        1.0
+    }
     fn name(&self) -> &str {
         "void"
     }
+    /// Air: insulates, but hot air rises.
+    fn thermal_conductivity(&self) -> f32 {
+        0.3
+    }
+    fn convection_factor(&self) -> f32 {
+        1.0
+    }
     fn casts_shadow(&self) -> bool {
         false
     }
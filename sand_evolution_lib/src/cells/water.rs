@@ -1,4 +1,7 @@
-use super::{helper::fluid_falling_helper, TemperatureContext, *};
+use super::{
+    helper::{change_phase, fluid_falling_helper},
+    TemperatureContext, *,
+};
 use crate::cs::PointType;
 pub struct Water;
 impl Water {
@@ -18,7 +21,7 @@ impl CellTrait for Water {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         pal_container: &CellRegistry,
         dim: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -26,67 +29,39 @@ impl CellTrait for Water {
         if let Some(temp_ctx) = temp_context {
             let temperature = temp_ctx.get_temp(i, j);
 
+            // Boiling takes latent heat, so a heated pool holds at 100 until it is gone. Water
+            // boils as soon as it gets there: heat past the boiling point goes into steam
+            // instead of piling up in the water while it waits for a lucky roll.
             if temperature >= 100.0 {
-                if dim.next() < 120 {
-                    use super::steam::Steam;
-                    container[cur] = Steam::id();
-                    const EVAP_COOLING: f32 = 45.0;
-                    temp_ctx.add_temp(i, j + 1, -EVAP_COOLING);
-                    temp_ctx.add_temp(i, j - 1, -EVAP_COOLING);
-                    temp_ctx.add_temp(i + 1, j, -EVAP_COOLING);
-                    temp_ctx.add_temp(i - 1, j, -EVAP_COOLING);
-                    return;
-                }
+                use super::steam::Steam;
+                change_phase(i, j, cur, Steam::id(), container, pal_container, temp_ctx);
+                return;
             }
 
             if temperature < -3.0 {
-                temp_ctx.add_temp(i, j + 1, 3.0);
-                temp_ctx.add_temp(i, j - 1, 3.0);
-                temp_ctx.add_temp(i + 1, j, 3.0);
-                temp_ctx.add_temp(i - 1, j, 3.0);
-
                 use super::{crushed_ice::CrushedIce, snow::Snow};
                 let roll = dim.next();
-                container[cur] = if roll < 128 {
+                let into = if roll < 128 {
                     CrushedIce::id()
                 } else {
                     Snow::id()
                 };
+                change_phase(i, j, cur, into, container, pal_container, temp_ctx);
                 return;
             }
         }
 
-        let is_falling =
-            fluid_falling_helper(self.den(), i, j, container, pal_container, cur, dim, 1);
-
-        if is_falling {
-            return;
-        }
-
-        let top = cs::xy_to_index(i, j + 1);
-        let down = cs::xy_to_index(i, j - 1);
-        let r = cs::xy_to_index(i + 1, j);
-        let l = cs::xy_to_index(i - 1, j);
-
-        let arr = [top, down, l, r];
-        let cc = arr[(dim.next() % 4) as usize];
-
-        if dim.next() > 50 {
-            let cc_v = container[cc] as usize;
-            let cc_c = &pal_container.pal[cc_v];
-            let cc_pt = cc_c.dissolve();
-
-            if cc_pt != Void::id() {
-                container[cc] = Void::id();
-                container[cur] = cc_pt;
-                return;
-            }
-        }
+        // What water dissolves is in the reaction table (see
+        // `reaction::add_property_reactions`).
+        fluid_falling_helper(self.den(), i, j, container, pal_container, cur, dim);
     }
 
     fn den(&self) -> i8 {
         1
     }
+    fn momentum(&self) -> Momentum {
+        Momentum::Liquid
+    }
 
     fn shadow_rgba(&self) -> [u8; 4] {
         [210, 225, 255, 255]
@@ -97,6 +72,12 @@ impl CellTrait for Water {
     fn convection_factor(&self) -> f32 {
         1.0
     }
+    fn heat_capacity(&self) -> f32 {
+        4.0
+    }
+    fn latent_heat(&self) -> f32 {
+        40.0
+    }
 
     fn display_color(&self) -> [u8; 3] {
         [26, 38, 255]
@@ -105,6 +86,12 @@ impl CellTrait for Water {
     fn needs_temp(&self) -> bool {
         true
     }
+    fn light_transmission(&self) -> f32 {
+        0.985
+    }
+    fn refractive_index(&self) -> f32 {
+        1.33
+    }
     fn name(&self) -> &str {
         "water"
     }
@@ -2,7 +2,7 @@ use crate::cs::PointType;
 
 use crate::cs;
 
-use super::{{burning_wood, gas::Gas, void::Void, CellRegistry, CellTrait, CellType, Prng, TemperatureContext}};
+use super::{{burning_wood, gas::Gas, void::Void, CellGrid, CellRegistry, CellTrait, CellType, Prng, TemperatureContext}};
 
 pub struct Wood;
 impl Wood {
@@ -17,17 +17,17 @@ impl Wood {
     }
 }
 
-fn has_adjacent_air(i: PointType, j: PointType, container: &[CellType]) -> bool {
+fn has_adjacent_air(i: PointType, j: PointType, container: &CellGrid) -> bool {
     if i > 0 && container[cs::xy_to_index(i - 1, j)] == Void::id() {
         return true;
     }
-    if i + 1 < cs::SECTOR_SIZE.x && container[cs::xy_to_index(i + 1, j)] == Void::id() {
+    if i + 1 < cs::sector_size().x && container[cs::xy_to_index(i + 1, j)] == Void::id() {
         return true;
     }
     if j > 0 && container[cs::xy_to_index(i, j - 1)] == Void::id() {
         return true;
     }
-    if j + 1 < cs::SECTOR_SIZE.y && container[cs::xy_to_index(i, j + 1)] == Void::id() {
+    if j + 1 < cs::sector_size().y && container[cs::xy_to_index(i, j + 1)] == Void::id() {
         return true;
     }
     false
@@ -38,7 +38,7 @@ impl CellTrait for Wood {
         i: PointType,
         j: PointType,
         cur: usize,
-        container: &mut [CellType],
+        container: &mut CellGrid,
         _pal_container: &CellRegistry,
         prng: &mut Prng,
         temp_context: Option<&mut TemperatureContext>,
@@ -79,6 +79,9 @@ impl CellTrait for Wood {
     fn needs_temp(&self) -> bool {
         true
     }
+    fn blast_resistance(&self) -> f32 {
+        40.0
+    }
     fn name(&self) -> &str {
         "wood"
     }
@@ -0,0 +1,191 @@
+//! Skipping the parts of the grid where nothing happens.
+//!
+//! `update_tick` splits the grid into `CHUNK_SIZE` squares. A chunk whose cells have not changed
+//! and which carries no heat, wind or charge for `SLEEP_AFTER_TICKS` ticks goes to sleep: its
+//! cells are then only updated once every `DOZE_TICKS` ticks, so rare events such as grass
+//! growing still happen and wake it up. Any change to a chunk, made by its own cells, a
+//! neighbour, the brush or a script, wakes it and the eight chunks around it.
+
+use crate::simulation::{Simulation, WIND_CELL_SIZE};
+
+/// Side of a chunk in cells.
+pub const CHUNK_SIZE: u32 = 32;
+/// Quiet ticks before a chunk goes to sleep.
+pub const SLEEP_AFTER_TICKS: u8 = 64;
+/// A sleeping chunk is still updated once every this many ticks. `update_tick` visits a
+/// different quarter of the cells on each of four ticks in a row, so this has to be odd.
+pub const DOZE_TICKS: i64 = 17;
+/// Degrees away from the global temperature that keep a chunk awake.
+const ACTIVE_TEMPERATURE: f32 = 0.5;
+/// Wind in cells per update that keeps a chunk awake; weaker gusts can't move a cell.
+const ACTIVE_WIND: f32 = 0.01;
+
+pub struct ChunkSleep {
+    /// Off, every chunk is updated on every tick.
+    pub enabled: bool,
+    width: usize,
+    /// Ticks since each chunk last had anything going on, up to `SLEEP_AFTER_TICKS`.
+    idle: Vec<u8>,
+    /// Chunks `update_tick` runs this tick; empty while disabled.
+    awake: Vec<bool>,
+    changed: Vec<bool>,
+    /// The grid as of the last `track`, to tell which chunks changed since.
+    snapshot: Vec<u8>,
+    global_temperature: f32,
+}
+
+impl Default for ChunkSleep {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            width: 0,
+            idle: Vec::new(),
+            awake: Vec::new(),
+            changed: Vec::new(),
+            snapshot: Vec::new(),
+            global_temperature: 0.0,
+        }
+    }
+}
+
+impl ChunkSleep {
+    /// Whether `update_tick` updates cell (i, j) this tick.
+    #[inline]
+    pub fn is_awake(&self, i: u32, j: u32) -> bool {
+        let chunk = (j / CHUNK_SIZE) as usize * self.width + (i / CHUNK_SIZE) as usize;
+        self.awake.get(chunk).copied().unwrap_or(true)
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.idle.len()
+    }
+
+    pub fn sleeping(&self) -> usize {
+        self.idle
+            .iter()
+            .filter(|&&idle| idle >= SLEEP_AFTER_TICKS)
+            .count()
+    }
+
+    /// Wakes every chunk, for changes that can set still cells moving, like turning gravity.
+    pub fn wake_all(&mut self) {
+        self.idle.fill(0);
+        self.awake.fill(true);
+    }
+
+    /// Compares the grid and fields of `sim` with the last call and decides which chunks run
+    /// this tick.
+    pub(crate) fn track(&mut self, sim: &Simulation, tick: i64) {
+        let grid = sim.diffuse_rgba.as_raw();
+        let (w, h) = sim.diffuse_rgba.dimensions();
+        let width = w.div_ceil(CHUNK_SIZE) as usize;
+        let count = width * h.div_ceil(CHUNK_SIZE) as usize;
+        if !self.enabled {
+            self.idle.clear();
+            self.awake.clear();
+            self.snapshot.clear();
+            return;
+        }
+        if self.snapshot.len() != grid.len() || self.width != width {
+            self.width = width;
+            self.idle = vec![0; count];
+            self.awake = vec![true; count];
+            self.changed = vec![false; count];
+            self.snapshot = grid.to_vec();
+            self.global_temperature = sim.global_temperature;
+            return;
+        }
+        if self.global_temperature != sim.global_temperature {
+            self.global_temperature = sim.global_temperature;
+            self.idle.fill(0);
+        }
+
+        let w = w as usize;
+        let chunk = CHUNK_SIZE as usize;
+        for c in 0..count {
+            let (x0, y0) = (c % width * chunk, c / width * chunk);
+            let x1 = (x0 + chunk).min(w);
+            let mut changed = false;
+            for y in y0..(y0 + chunk).min(h as usize) {
+                let row = y * w + x0..y * w + x1;
+                if grid[row.clone()] != self.snapshot[row.clone()] {
+                    self.snapshot[row.clone()].copy_from_slice(&grid[row]);
+                    changed = true;
+                }
+            }
+            self.changed[c] = changed;
+        }
+
+        let keeps_awake: [bool; 256] =
+            std::array::from_fn(|id| sim.pal_container.pal[id].keeps_awake());
+        let height = count / width;
+        for c in 0..count {
+            let (cx, cy) = (c % width, c / width);
+            let neighbour_changed = (cy.saturating_sub(1)..(cy + 2).min(height)).any(|y| {
+                (cx.saturating_sub(1)..(cx + 2).min(width)).any(|x| self.changed[y * width + x])
+            });
+            if neighbour_changed || fields_active(sim, cx, cy) {
+                self.idle[c] = 0;
+            } else if self.idle[c] < SLEEP_AFTER_TICKS {
+                self.idle[c] += 1;
+                // Cells that act without changing the grid, like fans, must not be put to sleep.
+                if self.idle[c] == SLEEP_AFTER_TICKS && holds(sim, cx, cy, &keeps_awake) {
+                    self.idle[c] = 0;
+                }
+            }
+            self.awake[c] = self.idle[c] < SLEEP_AFTER_TICKS || (tick + c as i64) % DOZE_TICKS == 0;
+        }
+    }
+}
+
+/// Cell range of chunk (cx, cy) along both axes, clipped to the grid.
+fn chunk_cells(sim: &Simulation, cx: usize, cy: usize) -> [std::ops::Range<usize>; 2] {
+    let (w, h) = sim.diffuse_rgba.dimensions();
+    let chunk = CHUNK_SIZE as usize;
+    [
+        cx * chunk..((cx + 1) * chunk).min(w as usize),
+        cy * chunk..((cy + 1) * chunk).min(h as usize),
+    ]
+}
+
+/// Whether the temperature, wind or charge under chunk (cx, cy) can still change its cells.
+fn fields_active(sim: &Simulation, cx: usize, cy: usize) -> bool {
+    let [xs, ys] = chunk_cells(sim, cx, cy);
+    let width = sim.diffuse_rgba.width() as usize;
+    let any_in = |scale: usize, active: &dyn Fn(usize) -> bool| {
+        let field_width = width / scale;
+        (ys.start / scale..ys.end.div_ceil(scale)).any(|y| {
+            (xs.start / scale..xs.end.div_ceil(scale).min(field_width))
+                .any(|x| active(y * field_width + x))
+        })
+    };
+
+    let temp_scale = sim.temperature_resolution().cell_size() as usize;
+    let temps = &sim.cell_temperatures;
+    if any_in(temp_scale, &|idx| {
+        temps.get(idx).is_some_and(|t| t.abs() > ACTIVE_TEMPERATURE)
+    }) {
+        return true;
+    }
+    let wind = &sim.cell_wind;
+    if any_in(WIND_CELL_SIZE as usize, &|idx| {
+        wind.get(idx)
+            .is_some_and(|w| w[0].abs().max(w[1].abs()) > ACTIVE_WIND)
+    }) {
+        return true;
+    }
+    let charge = &sim.cell_charge;
+    !charge.is_empty() && any_in(1, &|idx| charge[idx] > 0.0)
+}
+
+/// Whether chunk (cx, cy) holds any cell marked in `ids`.
+fn holds(sim: &Simulation, cx: usize, cy: usize, ids: &[bool; 256]) -> bool {
+    let [xs, ys] = chunk_cells(sim, cx, cy);
+    let width = sim.diffuse_rgba.width() as usize;
+    let grid = sim.diffuse_rgba.as_raw();
+    ys.into_iter().any(|y| {
+        grid[y * width + xs.start..y * width + xs.end]
+            .iter()
+            .any(|&id| ids[id as usize])
+    })
+}
@@ -1,27 +1,80 @@
 pub type PointType = u16;
 pub type IndexType = usize;
 
+use std::cell::Cell;
+
 use cgmath::Point2;
 
-pub const SECTOR_SIZE: Point2<PointType> = Point2::new(1024, 512);
+/// Grid size of `Simulation::new`.
+pub const DEFAULT_SECTOR_SIZE: Point2<PointType> = Point2::new(1024, 512);
+
+/// Both grid dimensions must be a multiple of this, so the coarse temperature, pressure and
+/// wind grids cover the cell grid exactly.
+pub const SECTOR_ALIGN: PointType = 8;
+pub const MIN_SECTOR_SIZE: PointType = 16;
+/// Largest texture every wgpu backend we ship on accepts.
+pub const MAX_SECTOR_SIZE: PointType = 8192;
+
+thread_local! {
+    // Size of the grid being simulated on this thread. The simulation sets it when it is
+    // created, resized or stepped, so cell code can keep addressing cells by (i, j).
+    static SECTOR_SIZE: Cell<Point2<PointType>> = const { Cell::new(DEFAULT_SECTOR_SIZE) };
+    // Box `[x0, y0, x1, y1)` that walks across the grid stay in, see `set_reach`.
+    static REACH: Cell<Option<[PointType; 4]>> = const { Cell::new(None) };
+}
+
+pub fn sector_size() -> Point2<PointType> {
+    SECTOR_SIZE.with(Cell::get)
+}
+
+pub fn set_sector_size(size: Point2<PointType>) {
+    SECTOR_SIZE.with(|cell| cell.set(size));
+}
+
+/// Keeps cell code on this thread that walks far from the updated cell, like
+/// `helper::level_liquid`, inside `[x0, y0, x1, y1)`; `None` allows the whole grid. The parallel
+/// cell pass sets it so a worker never reaches into chunks updated at the same time.
+pub fn set_reach(reach: Option<[PointType; 4]>) {
+    REACH.with(|cell| cell.set(reach));
+}
+
+pub fn within_reach(x: i32, y: i32) -> bool {
+    REACH.with(Cell::get).is_none_or(|[x0, y0, x1, y1]| {
+        (x0 as i32..x1 as i32).contains(&x) && (y0 as i32..y1 as i32).contains(&y)
+    })
+}
+
+/// Checks that a `width` x `height` grid can be simulated (see `SECTOR_ALIGN`).
+pub fn check_sector_size(width: u32, height: u32) -> Result<Point2<PointType>, String> {
+    let range = MIN_SECTOR_SIZE as u32..=MAX_SECTOR_SIZE as u32;
+    if !range.contains(&width) || !range.contains(&height) {
+        return Err(format!(
+            "grid must be between {MIN_SECTOR_SIZE} and {MAX_SECTOR_SIZE} cells per side, got {width}x{height}"
+        ));
+    }
+    if !width.is_multiple_of(SECTOR_ALIGN as u32) || !height.is_multiple_of(SECTOR_ALIGN as u32) {
+        return Err(format!(
+            "grid size must be a multiple of {SECTOR_ALIGN}, got {width}x{height}"
+        ));
+    }
+    Ok(Point2::new(width as PointType, height as PointType))
+}
 
 pub fn point_to_index(pos: Point2<PointType>) -> IndexType {
-    pos.y as IndexType * SECTOR_SIZE.x as IndexType + pos.x as IndexType
+    pos.y as IndexType * sector_size().x as IndexType + pos.x as IndexType
 }
 
 pub fn tuple_to_index(pos: (PointType, PointType)) -> IndexType {
-    pos.1 as IndexType * SECTOR_SIZE.x as IndexType + pos.0 as IndexType
+    pos.1 as IndexType * sector_size().x as IndexType + pos.0 as IndexType
 }
 
 pub fn xy_to_index(i: PointType, j: PointType) -> IndexType {
-    j as IndexType * SECTOR_SIZE.x as IndexType + i as IndexType
+    j as IndexType * sector_size().x as IndexType + i as IndexType
 }
 
 pub fn index_to_cell(index: IndexType) -> Point2<PointType> {
-    Point2::new(
-        (index % SECTOR_SIZE.y as IndexType) as PointType,
-        (index / SECTOR_SIZE.y as IndexType) as PointType,
-    )
+    let width = sector_size().x as IndexType;
+    Point2::new((index % width) as PointType, (index / width) as PointType)
 }
 
 // Temperature system by sections
@@ -34,12 +87,13 @@ pub fn get_temp_section_coords(i: PointType, j: PointType) -> (PointType, PointT
 
 pub fn get_temp_section_index(i: PointType, j: PointType) -> usize {
     let (sx, sy) = get_temp_section_coords(i, j);
-    let sections_x = (SECTOR_SIZE.x + TEMP_SECTION_SIZE - 1) / TEMP_SECTION_SIZE;
+    let sections_x = sector_size().x.div_ceil(TEMP_SECTION_SIZE);
     (sy as usize * sections_x as usize) + sx as usize
 }
 
 pub fn get_temp_sections_count() -> (usize, usize) {
-    let sections_x = ((SECTOR_SIZE.x + TEMP_SECTION_SIZE - 1) / TEMP_SECTION_SIZE) as usize;
-    let sections_y = ((SECTOR_SIZE.y + TEMP_SECTION_SIZE - 1) / TEMP_SECTION_SIZE) as usize;
+    let size = sector_size();
+    let sections_x = size.x.div_ceil(TEMP_SECTION_SIZE) as usize;
+    let sections_y = size.y.div_ceil(TEMP_SECTION_SIZE) as usize;
     (sections_x, sections_y)
 }
@@ -114,3 +114,65 @@ impl Default for Scale {
 impl Component for Scale {
     type Storage = specs::VecStorage<Self>;
 }
+
+/// A solid made of grid cells, moved by `ecs::rigid_body::step` instead of `MoveSystem`.
+///
+/// `cells` is a `width` x `height` stencil, row-major with the bottom row first; `Void`
+/// entries are empty. It is stamped into the grid centred on the entity's `Position` and
+/// turned by its `Rotation`. Stencil cells should be static (wood, stone, ...) so loose cells
+/// can't swap through the body; a stamped cell that changes anyway (burns, melts) is dropped
+/// from the stencil.
+#[derive(Debug, Clone)]
+pub struct RigidBody {
+    pub cells: Vec<u8>,
+    pub width: u16,
+    pub height: u16,
+    /// On the scale of cell `den()`: the body pushes lighter loose cells aside and floats on
+    /// liquids denser than this.
+    pub density: f32,
+    /// Radians per tick, counter-clockwise.
+    pub angular_velocity: f32,
+    /// Grid index and stencil index of every cell stamped by the last step.
+    pub stamped: Vec<(usize, usize)>,
+}
+
+impl RigidBody {
+    pub fn rectangle(width: u16, height: u16, cell: u8, density: f32) -> Self {
+        Self {
+            cells: vec![cell; width as usize * height as usize],
+            width,
+            height,
+            density,
+            angular_velocity: 0.0,
+            stamped: Vec::new(),
+        }
+    }
+
+    pub fn circle(radius: u16, cell: u8, density: f32) -> Self {
+        let size = 2 * radius + 1;
+        let mut body = Self::rectangle(size, size, cell, density);
+        let r = radius as i32;
+        for y in -r..=r {
+            for x in -r..=r {
+                if x * x + y * y > r * r + r {
+                    body.cells[((y + r) * size as i32 + x + r) as usize] = 0;
+                }
+            }
+        }
+        body
+    }
+
+    /// Wooden box, light enough to float on water.
+    pub fn wooden_crate(width: u16, height: u16) -> Self {
+        Self::rectangle(width, height, crate::cells::wood::Wood::id(), 0.6)
+    }
+
+    /// Round stone; sinks in liquids but rests on sand and gravel.
+    pub fn boulder(radius: u16) -> Self {
+        Self::circle(radius, crate::cells::stone::Stone::id(), 9.0)
+    }
+}
+
+impl Component for RigidBody {
+    type Storage = specs::HashMapStorage<Self>;
+}
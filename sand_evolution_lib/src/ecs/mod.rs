@@ -1,3 +1,4 @@
 pub mod components;
 pub mod hierarchy;
+pub mod rigid_body;
 pub mod systems;
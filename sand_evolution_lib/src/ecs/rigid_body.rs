@@ -0,0 +1,365 @@
+//! Rigid bodies: entities with a `RigidBody` stencil of cells.
+//!
+//! Every tick each body is lifted out of the grid, moved a cell at a time along its velocity,
+//! turned by its angular velocity and stamped back. Static cells, other bodies and the grid
+//! edge stop it. Loose cells in the way are pushed into the cells the body just left, and
+//! slow it down by how dense they are. Liquids around a body buoy it up, and a body that lands
+//! on one side of its centre tips over.
+
+use specs::{Builder, Entity, Join, World, WorldExt};
+
+use crate::cells::{void::Void, CellRegistry, CellType, Momentum};
+use crate::cs;
+
+use super::components::{Name, Position, RigidBody, Rotation, Velocity};
+
+/// Speed gained per tick, the same pull `GravitySystem` gives other entities.
+const GRAVITY: f32 = 0.1;
+/// Cells per tick.
+const MAX_SPEED: f32 = 3.0;
+/// Radians per tick.
+const MAX_SPIN: f32 = 0.08;
+const SPIN_DAMPING: f32 = 0.97;
+/// Spin gained per unit of torque from an off-centre landing.
+const TIP: f32 = 0.004;
+/// Share of the speed kept, reversed, after hitting something.
+const BOUNCE: f32 = 0.2;
+/// Share of the sideways speed and spin kept on landing.
+const FRICTION: f32 = 0.8;
+/// Speed lost per cell moved through loose cells as dense as the body.
+const DRAG: f32 = 0.5;
+/// Speed lost per tick while fully immersed.
+const LIQUID_DRAG: f32 = 0.05;
+
+/// (grid index, stencil index) of every cell a body covers, sorted by grid index.
+type Footprint = Vec<(usize, usize)>;
+
+/// Per cell id properties, looked up once per step.
+struct Materials {
+    den: [f32; 256],
+    stat: [bool; 256],
+    liquid: [bool; 256],
+}
+
+impl Materials {
+    /// Whether a body of `density` pushes this cell aside rather than stopping on it.
+    fn loose(&self, id: CellType, density: f32) -> bool {
+        let id = id as usize;
+        !self.stat[id] && (self.liquid[id] || self.den[id] < density)
+    }
+}
+
+/// Creates a rigid body entity at (`x`, `y`), in grid cells.
+pub fn spawn(world: &mut World, name: String, x: f32, y: f32, body: RigidBody) -> Entity {
+    world
+        .create_entity()
+        .with(Name { name })
+        .with(Position { x, y })
+        .with(Velocity { x: 0.0, y: 0.0 })
+        .with(Rotation::default())
+        .with(body)
+        .build()
+}
+
+/// Moves every rigid body one tick and stamps it into `container`.
+pub fn step(world: &World, container: &mut [CellType], pal_container: &CellRegistry) {
+    let mut bodies = world.write_storage::<RigidBody>();
+    if (&bodies).join().next().is_none() {
+        return;
+    }
+    let mut positions = world.write_storage::<Position>();
+    let mut velocities = world.write_storage::<Velocity>();
+    let mut rotations = world.write_storage::<Rotation>();
+
+    let mut materials = Materials {
+        den: [0.0; 256],
+        stat: [false; 256],
+        liquid: [false; 256],
+    };
+    for (id, cell) in pal_container.pal.iter().enumerate() {
+        materials.den[id] = cell.den() as f32;
+        materials.stat[id] = cell.stat();
+        materials.liquid[id] = cell.momentum() == Momentum::Liquid;
+    }
+    let (gx, gy) = pal_container.gravity.vector();
+    let gravity = [gx as f32, gy as f32];
+
+    for (body, pos, vel, rot) in
+        (&mut bodies, &mut positions, &mut velocities, &mut rotations).join()
+    {
+        step_body(body, pos, vel, rot, gravity, container, &materials);
+    }
+}
+
+fn step_body(
+    body: &mut RigidBody,
+    pos: &mut Position,
+    vel: &mut Velocity,
+    rot: &mut Rotation,
+    gravity: [f32; 2],
+    container: &mut [CellType],
+    materials: &Materials,
+) {
+    // Lift the body out; whatever replaced one of its cells since the last step keeps it.
+    for (idx, local) in body.stamped.drain(..) {
+        if container[idx] == body.cells[local] {
+            container[idx] = Void::id();
+        } else {
+            body.cells[local] = Void::id();
+        }
+    }
+    let mut at = [pos.x.round() as i32, pos.y.round() as i32];
+    let Some(mut covered) = footprint(body, at, rot.angle) else {
+        return;
+    };
+    if covered.is_empty() {
+        return;
+    }
+
+    let (wet, liquid_den) = immersion(&covered, container, materials);
+    let lift = wet * liquid_den / body.density.max(0.01);
+    let mut v = [vel.x, vel.y];
+    for axis in 0..2 {
+        v[axis] = (v[axis] + gravity[axis] * GRAVITY * (1.0 - lift)) * (1.0 - LIQUID_DRAG * wet);
+    }
+    let speed = (v[0] * v[0] + v[1] * v[1]).sqrt();
+    if speed > MAX_SPEED {
+        v = v.map(|c| c * MAX_SPEED / speed);
+    }
+
+    let mut p = [pos.x + v[0], pos.y + v[1]];
+    let mut target = p.map(|c| c.round() as i32);
+    let mut pivot = None;
+    while at != target {
+        let axis = if (target[0] - at[0]).abs() > (target[1] - at[1]).abs() {
+            0
+        } else {
+            1
+        };
+        let mut next = at;
+        next[axis] += (target[axis] - at[axis]).signum();
+        match shift(body, &covered, next, rot.angle, container, materials) {
+            Ok((moved, resistance)) => {
+                covered = moved;
+                at = next;
+                v[axis] *= 1.0 - DRAG * resistance.min(1.0);
+            }
+            Err(contacts) => {
+                let step = (next[axis] - at[axis]) as f32;
+                if step * gravity[axis] > 0.0 && !contacts.is_empty() {
+                    // Landed: drag along the ground, and tip over about the contacts unless
+                    // they are right under the centre (a cell either way is just the grid).
+                    let side = 1 - axis;
+                    let centre = mean(&contacts);
+                    let overhang = at[side] as f32 - centre[side];
+                    v[side] *= FRICTION;
+                    body.angular_velocity *= FRICTION;
+                    if overhang.abs() > 1.0 {
+                        body.angular_velocity += TIP * torque(at, centre, gravity);
+                        pivot = Some(centre);
+                    }
+                }
+                v[axis] *= -BOUNCE;
+                target[axis] = at[axis];
+                p[axis] = at[axis] as f32;
+            }
+        }
+    }
+
+    body.angular_velocity = (body.angular_velocity * SPIN_DAMPING).clamp(-MAX_SPIN, MAX_SPIN);
+    if body.angular_velocity != 0.0 {
+        // A body standing on something turns about where it stands, not about its centre.
+        let turn = body.angular_velocity;
+        let ideal = pivot.map_or(p, |pivot| {
+            let (sin, cos) = turn.sin_cos();
+            let arm = [p[0] - pivot[0], p[1] - pivot[1]];
+            [
+                pivot[0] + cos * arm[0] - sin * arm[1],
+                pivot[1] + sin * arm[0] + cos * arm[1],
+            ]
+        });
+        // The grid only approximates the turn: take the nearest free spot a cell either way.
+        let mut candidates: Vec<[i32; 2]> = (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| [dx, dy]))
+            .map(|[dx, dy]| [ideal[0].round() as i32 + dx, ideal[1].round() as i32 + dy])
+            .collect();
+        let miss =
+            |c: &[i32; 2]| (c[0] as f32 - ideal[0]).powi(2) + (c[1] as f32 - ideal[1]).powi(2);
+        candidates.sort_by(|a, b| miss(a).total_cmp(&miss(b)));
+        let turned = candidates.into_iter().find_map(|centre| {
+            shift(
+                body,
+                &covered,
+                centre,
+                rot.angle + turn,
+                container,
+                materials,
+            )
+            .ok()
+            .map(|(turned, _)| (centre, turned))
+        });
+        match turned {
+            Some((centre, turned)) => {
+                covered = turned;
+                rot.angle += turn;
+                if centre != at {
+                    p = centre.map(|c| c as f32);
+                }
+            }
+            None => body.angular_velocity *= -BOUNCE,
+        }
+    }
+    *pos = Position { x: p[0], y: p[1] };
+    *vel = Velocity { x: v[0], y: v[1] };
+
+    for &(idx, local) in &covered {
+        container[idx] = body.cells[local];
+    }
+    body.stamped = covered;
+}
+
+/// Grid cells the body covers centred on `at` and turned by `angle`. `None` if any of them
+/// would be off the grid.
+fn footprint(body: &RigidBody, at: [i32; 2], angle: f32) -> Option<Footprint> {
+    let (w, h) = (body.width as i32, body.height as i32);
+    let centre = [(w - 1) as f32 / 2.0, (h - 1) as f32 / 2.0];
+    let reach = ((w * w + h * h) as f32).sqrt() as i32 / 2 + 1;
+    let (sin, cos) = angle.sin_cos();
+    let size = cs::sector_size();
+    let (width, height) = (size.x as i32, size.y as i32);
+
+    let mut cells = Vec::new();
+    for dy in -reach..=reach {
+        for dx in -reach..=reach {
+            // Turn the grid offset back into the stencil's frame.
+            let lx = (cos * dx as f32 + sin * dy as f32 + centre[0]).round() as i32;
+            let ly = (-sin * dx as f32 + cos * dy as f32 + centre[1]).round() as i32;
+            if lx < 0 || ly < 0 || lx >= w || ly >= h {
+                continue;
+            }
+            let local = (ly * w + lx) as usize;
+            if body.cells[local] == Void::id() {
+                continue;
+            }
+            let (x, y) = (at[0] + dx, at[1] + dy);
+            if x < 1 || y < 1 || x >= width - 1 || y >= height - 1 {
+                return None;
+            }
+            cells.push((
+                cs::xy_to_index(x as cs::PointType, y as cs::PointType),
+                local,
+            ));
+        }
+    }
+    Some(cells)
+}
+
+/// Moves the lifted body from `covered` to `at` and `angle`, pushing loose cells it runs into
+/// over to the cells it leaves. Returns the new footprint and how hard the pushed cells were
+/// to move (their mean density over the body's), or the positions of the cells that stop it.
+fn shift(
+    body: &RigidBody,
+    covered: &[(usize, usize)],
+    at: [i32; 2],
+    angle: f32,
+    container: &mut [CellType],
+    materials: &Materials,
+) -> Result<(Footprint, f32), Vec<[i32; 2]>> {
+    let inside = |cells: &[(usize, usize)], idx: usize| {
+        cells.binary_search_by_key(&idx, |&(i, _)| i).is_ok()
+    };
+    let Some(moved) = footprint(body, at, angle) else {
+        return Err(Vec::new());
+    };
+
+    let width = cs::sector_size().x as usize;
+    let mut fresh = 0;
+    let mut pushed = Vec::new();
+    let mut contacts = Vec::new();
+    for &(idx, _) in &moved {
+        if inside(covered, idx) {
+            continue;
+        }
+        fresh += 1;
+        let id = container[idx];
+        if id == Void::id() {
+            continue;
+        }
+        if materials.loose(id, body.density) {
+            pushed.push(idx);
+        } else {
+            contacts.push([(idx % width) as i32, (idx / width) as i32]);
+        }
+    }
+    if !contacts.is_empty() {
+        return Err(contacts);
+    }
+
+    let mut left: Vec<usize> = covered
+        .iter()
+        .map(|&(idx, _)| idx)
+        .filter(|&idx| !inside(&moved, idx) && container[idx] == Void::id())
+        .collect();
+    if left.len() < pushed.len() {
+        return Err(Vec::new());
+    }
+    let distance = |a: usize, b: usize| {
+        let (dx, dy) = (
+            (a % width) as i32 - (b % width) as i32,
+            (a / width) as i32 - (b / width) as i32,
+        );
+        dx * dx + dy * dy
+    };
+    let mut resistance = 0.0;
+    for idx in pushed {
+        let (k, _) = left
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, &free)| distance(idx, free))
+            .unwrap();
+        let free = left.swap_remove(k);
+        let id = container[idx];
+        resistance += materials.den[id as usize].max(0.0) / body.density.max(0.01);
+        container[free] = id;
+        container[idx] = Void::id();
+    }
+    Ok((moved, resistance / fresh.max(1) as f32))
+}
+
+/// Share of the cells around the body that are liquid, and their mean density.
+fn immersion(
+    covered: &[(usize, usize)],
+    container: &[CellType],
+    materials: &Materials,
+) -> (f32, f32) {
+    let width = cs::sector_size().x as usize;
+    let (mut around, mut wet, mut den) = (0, 0, 0.0);
+    for &(idx, _) in covered {
+        for next in [idx - 1, idx + 1, idx - width, idx + width] {
+            if covered.binary_search_by_key(&next, |&(i, _)| i).is_ok() {
+                continue;
+            }
+            around += 1;
+            let id = container[next] as usize;
+            if materials.liquid[id] {
+                wet += 1;
+                den += materials.den[id];
+            }
+        }
+    }
+    if wet == 0 {
+        return (0.0, 0.0);
+    }
+    (wet as f32 / around as f32, den / wet as f32)
+}
+
+fn mean(cells: &[[i32; 2]]) -> [f32; 2] {
+    let n = cells.len() as f32;
+    [0, 1].map(|axis| cells.iter().map(|c| c[axis] as f32).sum::<f32>() / n)
+}
+
+/// Torque of the body's weight about `pivot`, counter-clockwise positive.
+fn torque(at: [i32; 2], pivot: [f32; 2], gravity: [f32; 2]) -> f32 {
+    let arm = [at[0] as f32 - pivot[0], at[1] as f32 - pivot[1]];
+    arm[0] * gravity[1] - arm[1] * gravity[0]
+}
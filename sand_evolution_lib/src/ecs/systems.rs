@@ -1,6 +1,7 @@
-use crate::ecs::components::{Name, Position, Script, Velocity};
+use crate::cells::Gravity;
+use crate::ecs::components::{Name, Position, RigidBody, Script, Velocity};
 use crate::resources::rhai_resource::RhaiResource;
-use specs::{Entities, Join, ReadStorage, System, Write, WriteStorage};
+use specs::{Entities, Join, Read, ReadStorage, System, Write, WriteStorage};
 use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::rc::Rc;
@@ -8,10 +9,15 @@ use std::rc::Rc;
 pub struct MoveSystem;
 
 impl<'a> System<'a> for MoveSystem {
-    type SystemData = (ReadStorage<'a, Velocity>, WriteStorage<'a, Position>);
+    type SystemData = (
+        ReadStorage<'a, Velocity>,
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, RigidBody>,
+    );
 
-    fn run(&mut self, (vel, mut pos): Self::SystemData) {
-        for (vel, pos) in (&vel, &mut pos).join() {
+    fn run(&mut self, (vel, mut pos, bodies): Self::SystemData) {
+        // Rigid bodies are moved by `rigid_body::step`, which stops them at walls.
+        for (vel, pos, _) in (&vel, &mut pos, !&bodies).join() {
             pos.x += vel.x;
             pos.y += vel.y;
         }
@@ -21,11 +27,18 @@ impl<'a> System<'a> for MoveSystem {
 pub struct GravitySystem;
 
 impl<'a> System<'a> for GravitySystem {
-    type SystemData = (WriteStorage<'a, Velocity>, ReadStorage<'a, Position>);
+    type SystemData = (
+        WriteStorage<'a, Velocity>,
+        ReadStorage<'a, Position>,
+        Read<'a, Gravity>,
+        ReadStorage<'a, RigidBody>,
+    );
 
-    fn run(&mut self, (mut vel, pos): Self::SystemData) {
-        for (vel, pos) in (&mut vel, &pos).join() {
-            vel.y -= 0.1;
+    fn run(&mut self, (mut vel, pos, gravity, bodies): Self::SystemData) {
+        let (gx, gy) = gravity.vector();
+        for (vel, pos, _) in (&mut vel, &pos, !&bodies).join() {
+            vel.x += 0.1 * gx as f32;
+            vel.y += 0.1 * gy as f32;
         }
     }
 }
@@ -1,4 +1,4 @@
-use crate::ecs::components::{Name, Position, Rotation, Scale};
+use crate::ecs::components::{Name, Position, RigidBody, Rotation, Scale};
 use crate::editor::state::EditorState;
 use egui::Ui;
 use specs::{Builder, World, WorldExt};
@@ -27,6 +27,23 @@ impl AddPanel {
 
         ui.separator();
 
+        ui.label("Rigid bodies:");
+
+        if ui.button("Crate").clicked() {
+            Self::add_rigid_body(
+                world,
+                editor_state,
+                "Crate",
+                RigidBody::wooden_crate(16, 16),
+            );
+        }
+
+        if ui.button("Boulder").clicked() {
+            Self::add_rigid_body(world, editor_state, "Boulder", RigidBody::boulder(8));
+        }
+
+        ui.separator();
+
         ui.label("Drag objects here to add to scene");
     }
 
@@ -81,6 +98,25 @@ impl AddPanel {
         );
     }
 
+    /// Drops the body in from the top middle of the grid.
+    fn add_rigid_body(
+        world: &mut World,
+        editor_state: &mut EditorState,
+        base: &str,
+        body: RigidBody,
+    ) {
+        let name = Self::generate_unique_name(world, base);
+        let size = crate::cs::sector_size();
+        let (x, y) = (size.x as f32 / 2.0, size.y as f32 * 7.0 / 8.0);
+        let entity = crate::ecs::rigid_body::spawn(world, name.clone(), x, y, body);
+
+        editor_state.select_entity(entity, false);
+        editor_state.add_toast(
+            format!("Created: {}", name),
+            crate::editor::state::ToastLevel::Info,
+        );
+    }
+
     fn generate_unique_name(world: &World, base: &str) -> String {
         use crate::ecs::components::Name;
         use specs::{Join, WorldExt};
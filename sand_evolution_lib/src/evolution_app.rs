@@ -5,7 +5,7 @@ use std::collections::VecDeque;
 use std::rc::Rc;
 use winit::{dpi::PhysicalPosition, event_loop::EventLoopProxy};
 
-use crate::export_file::{code_to_file, scene_to_file};
+use crate::export_file::{code_to_file, save_to_file, scene_to_file};
 use crate::projects::ProjectDescription;
 use crate::resources::rhai_resource::RhaiResourceStorage;
 use crate::{
@@ -14,6 +14,7 @@ use crate::{
     editor::{EditorHierarchy, EditorInspector, EditorState, UndoRedo},
     export_file::write_to_file,
     fps_meter::FpsMeter,
+    simulation::{Simulation, TemperatureResolution},
     state::{State, UpdateResult},
 };
 use specs::WorldExt;
@@ -115,6 +116,12 @@ pub struct EvolutionApp {
     // Last generated share URL for templates
     pub last_load_url: String,
 
+    // Text of the seed field in the Simulation window (empty = show current seed)
+    pub seed_text: String,
+
+    // Size picked in the "Grid size" section of the Simulation window
+    pub new_grid_size: (u32, u32),
+
     // Editor state
     pub editor_state: EditorState,
     pub undo_redo: UndoRedo,
@@ -183,6 +190,10 @@ pub enum UserEventInfo {
     ImageImport(Vec<u8>),
     TextImport(Vec<u8>),
     SceneImport(Vec<u8>),
+    /// Full world save (see `save_file`).
+    SaveImport(Vec<u8>),
+    /// TOML materials file (see `cells::data_cell`).
+    MaterialsImport(Vec<u8>),
     /// GitHub templates currently don't ship an entity list / scene.
     /// When applying such a template, we reset the ECS world entities to the hardcoded defaults.
     ResetWorldEntitiesToHardcoded,
@@ -191,6 +202,25 @@ pub enum UserEventInfo {
 }
 
 impl EvolutionApp {
+    pub fn set_cell_options(&mut self, registry: &crate::cells::CellRegistry) {
+        self.options = registry
+            .pal
+            .iter()
+            .filter(|cell| cell.id() != 0)
+            .map(|cell| cell.name().to_owned())
+            .collect();
+    }
+
+    /// Toasts the cell names a loaded map used that the current palette doesn't have.
+    pub fn warn_unknown_cells(&mut self, names: &[String]) {
+        if !names.is_empty() {
+            self.editor_state.add_toast(
+                format!("Unknown cells replaced with void: {}", names.join(", ")),
+                crate::editor::state::ToastLevel::Warning,
+            );
+        }
+    }
+
     pub fn get_script(&mut self) -> &str {
         self.script.as_str()
     }
@@ -223,23 +253,8 @@ impl EvolutionApp {
 
     /// Set object script by name in world
     pub fn set_object_script(&mut self, world: &mut specs::World, object_name: &str, script: &str) {
-        use crate::ecs::components::{Name, Script};
-        use specs::Join;
-
-        let names = world.read_storage::<Name>();
-        let mut scripts = world.write_storage::<Script>();
-        let entities = world.entities();
-
-        for (entity, name_comp) in (&entities, &names).join() {
-            if name_comp.name == object_name {
-                if let Some(script_comp) = scripts.get_mut(entity) {
-                    script_comp.script = script.to_owned();
-                    script_comp.raw = true;
-                    // If this is a one-shot script, allow it to run again after updating the code.
-                    script_comp.has_run = false;
-                    self.need_to_recompile = true;
-                }
-            }
+        if crate::simulation::set_object_script(world, object_name, script) {
+            self.need_to_recompile = true;
         }
     }
 
@@ -329,11 +344,11 @@ impl EvolutionApp {
         &mut self,
         context: &Context,
         state: &mut State,
+        sim: &mut Simulation,
         fps_meter: &mut FpsMeter,
         upd_result: &UpdateResult,
         event_loop_proxy: &EventLoopProxy<UserEventInfo>,
         any_win_hovered: &mut bool,
-        world: &mut specs::World,
     ) {
         // Update editor toasts
         self.editor_state
@@ -383,7 +398,7 @@ impl EvolutionApp {
             .default_size(egui::vec2(hierarchy_w, hierarchy_h))
             .show(context, |ui| {
                 self.window_style.apply_to_ui(ui);
-                EditorHierarchy::ui(ui, &mut self.editor_state, world);
+                EditorHierarchy::ui(ui, &mut self.editor_state, &mut sim.world);
             });
 
         // Editor Inspector - right column, bottom half
@@ -395,7 +410,7 @@ impl EvolutionApp {
             .default_size(egui::vec2(inspector_w, inspector_h))
             .show(context, |ui| {
                 self.window_style.apply_to_ui(ui);
-                EditorInspector::ui(ui, &mut self.editor_state, world);
+                EditorInspector::ui(ui, &mut self.editor_state, &mut sim.world);
             });
 
         // Handle request to open scripts window for a specific object
@@ -480,7 +495,7 @@ impl EvolutionApp {
                 ui.add_space(4.0);
 
                 if let Some(info) = self.hover_info {
-                    let cell_name = state
+                    let cell_name = sim
                         .pal_container
                         .pal
                         .get(info.cell_id as usize)
@@ -519,19 +534,25 @@ impl EvolutionApp {
                     .show(ui, |ui| {
                         ui.heading("Edit");
                         if ui.button("🧹 Clear").clicked() {
-                            Self::clear_map(state);
+                            Self::clear_map(sim);
                         }
                         if ui.button("🎲 Generate random (basic)").clicked() {
-                            state.generate_simple();
+                            sim.reseed(Simulation::random_seed());
+                            sim.generate_simple();
+                            self.seed_text.clear();
                         }
                         if ui.button("↩ Restore from URL").clicked() {
-                            state.diffuse_rgba = state.loaded_rgba.clone();
+                            sim.diffuse_rgba = sim.loaded_rgba.clone();
                         }
 
                         ui.separator();
                         ui.heading("Import / Export");
                         if ui.button("💾 Export PNG").clicked() {
-                            if let Err(err) = write_to_file(&state.diffuse_rgba) {
+                            let png = crate::map_file::encode_png(
+                                &sim.diffuse_rgba,
+                                &sim.pal_container,
+                            );
+                            if let Err(err) = png.and_then(|png| write_to_file(&png)) {
                                 panic!("Error: {}", err);
                             }
                         }
@@ -560,7 +581,7 @@ impl EvolutionApp {
                     .show(ui, |ui| {
                         ui.heading("Import / Export");
                         if ui.button("💾 Export TOML").clicked() {
-                            let toml_text = self.export_scene_to_toml(world);
+                            let toml_text = self.export_scene_to_toml(&sim.world);
                             if let Err(err) = scene_to_file(&toml_text) {
                                 panic!("Error: {}", err);
                             }
@@ -584,6 +605,93 @@ impl EvolutionApp {
                         }
                     });
 
+                ui.separator();
+
+                egui::CollapsingHeader::new("💽 World save")
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        ui.label("Map, temperatures, time and entities in one file");
+                        if ui.button("💾 Save world").clicked() {
+                            let scene = self.export_scene_to_toml(&sim.world);
+                            let bytes = crate::save_file::write_save(sim, &scene);
+                            if let Err(err) = save_to_file(&bytes) {
+                                self.editor_state.add_toast(
+                                    format!("Save error: {}", err),
+                                    crate::editor::state::ToastLevel::Error,
+                                );
+                            }
+                        }
+
+                        if ui.button("📂 Load world").clicked() {
+                            let dialog = rfd::AsyncFileDialog::new()
+                                .add_filter("Sand Evolution save", &["sandsave"])
+                                .add_filter("All", &["*"])
+                                .pick_file();
+
+                            let event_loop_proxy = event_loop_proxy.clone();
+                            self.executor.execute(async move {
+                                if let Some(file) = dialog.await {
+                                    let bytes = file.read().await;
+                                    event_loop_proxy
+                                        .send_event(create_event_with_save(bytes))
+                                        .ok();
+                                }
+                            });
+                        }
+                    });
+
+                ui.separator();
+
+                egui::CollapsingHeader::new("🧪 Materials")
+                    .default_open(false)
+                    .show(ui, |ui| {
+                        ui.label("Extra cell types from a TOML materials file");
+                        if ui.button("📂 Load materials TOML").clicked() {
+                            let dialog = rfd::AsyncFileDialog::new()
+                                .add_filter("TOML", &["toml"])
+                                .add_filter("All", &["*"])
+                                .pick_file();
+
+                            let event_loop_proxy = event_loop_proxy.clone();
+                            self.executor.execute(async move {
+                                if let Some(file) = dialog.await {
+                                    let bytes = file.read().await;
+                                    event_loop_proxy
+                                        .send_event(create_event_with_materials(bytes))
+                                        .ok();
+                                }
+                            });
+                        }
+
+                        egui::CollapsingHeader::new("Reactions").show(ui, |ui| {
+                            let pal = &sim.pal_container.pal;
+                            let name = |id: u8| pal[id as usize].name();
+                            for (a, b, r) in sim.pal_container.reactions.list() {
+                                let mut line = format!(
+                                    "{} + {} → {} + {} ({}/255",
+                                    name(a),
+                                    name(b),
+                                    name(r.into),
+                                    name(r.other_into),
+                                    r.chance
+                                );
+                                if r.heat != 0.0 {
+                                    line.push_str(&format!(", {:+}°", r.heat));
+                                }
+                                if r.min_temperature > f32::NEG_INFINITY
+                                    || r.max_temperature < f32::INFINITY
+                                {
+                                    line.push_str(&format!(
+                                        ", {}..{}°",
+                                        r.min_temperature, r.max_temperature
+                                    ));
+                                }
+                                line.push(')');
+                                ui.label(line);
+                            }
+                        });
+                    });
+
                 *any_win_hovered |= context.is_pointer_over_area();
             });
         self.win_files = win_files;
@@ -607,8 +715,8 @@ impl EvolutionApp {
                 // Get the list of all objects (first collect the data)
                 let mut object_names: Vec<String> = Vec::new();
                 {
-                    let names = world.read_storage::<Name>();
-                    let entities = world.entities();
+                    let names = sim.world.read_storage::<Name>();
+                    let entities = sim.world.entities();
                     for (_, name_comp) in (&entities, &names).join() {
                         object_names.push(name_comp.name.clone());
                     }
@@ -634,7 +742,7 @@ impl EvolutionApp {
 
                 if self.selected_object_name != self.last_loaded_object {
                     if let Some(script_text) =
-                        self.get_object_script(world, &self.selected_object_name)
+                        self.get_object_script(&sim.world, &self.selected_object_name)
                     {
                         self.script = script_text;
                     } else {
@@ -652,14 +760,14 @@ impl EvolutionApp {
                 ui.horizontal(|ui| {
                     // Enable/disable script button
                     if ui
-                        .button(if state.toggled {
+                        .button(if sim.toggled {
                             "⏸ Disable"
                         } else {
                             "▶ Enable"
                         })
                         .clicked()
                     {
-                        state.toggled = !state.toggled;
+                        sim.toggled = !sim.toggled;
                     }
 
                     ui.separator();
@@ -909,43 +1017,197 @@ impl EvolutionApp {
                         .clamp_to_range(true)
                         .text("Cell diffusion iterations / tick"),
                 );
+                let mut full_res = sim.temperature_resolution() == TemperatureResolution::Full;
+                if ui
+                    .checkbox(&mut full_res, "Per-cell temperature")
+                    .on_hover_text(
+                        "One temperature per cell instead of per 4x4 block. Sharper gradients \
+                         along thin conductors; heat spreads fewer cells per pass and costs 16x more.",
+                    )
+                    .changed()
+                {
+                    sim.set_temperature_resolution(if full_res {
+                        TemperatureResolution::Full
+                    } else {
+                        TemperatureResolution::Reduced
+                    });
+                }
+
+                ui.add_space(6.0);
+                ui.heading("Movement");
+                let mut momentum = sim.momentum_enabled();
+                if ui
+                    .checkbox(&mut momentum, "Momentum")
+                    .on_hover_text(
+                        "Powders and liquids keep their velocity: falls accelerate, landing \
+                         water splashes and explosions throw cells along arcs.",
+                    )
+                    .changed()
+                {
+                    sim.set_momentum(momentum);
+                }
+                let mut aux = sim.aux_buffer_enabled();
+                if ui
+                    .checkbox(&mut aux, "Cell memory")
+                    .on_hover_text(
+                        "Every cell keeps a small counter between updates, so burning wood \
+                         burns for a set time instead of going out at random.",
+                    )
+                    .changed()
+                {
+                    sim.set_aux_buffer(aux);
+                }
+                let mut gravity = sim.gravity();
+                ui.horizontal(|ui| {
+                    ui.label("Gravity:");
+                    egui::ComboBox::from_id_source("gravity_selector")
+                        .selected_text(gravity.name())
+                        .show_ui(ui, |ui| {
+                            for option in crate::cells::Gravity::ALL {
+                                ui.selectable_value(&mut gravity, option, option.name());
+                            }
+                        });
+                });
+                if gravity != sim.gravity() {
+                    sim.set_gravity(gravity);
+                }
+                let mut levelling = sim.liquid_levelling();
+                if ui
+                    .checkbox(&mut levelling, "Liquid levelling")
+                    .on_hover_text(
+                        "Liquid surfaces even out through the liquid below them, so \
+                         connected vessels fill to the same height.",
+                    )
+                    .changed()
+                {
+                    sim.set_liquid_levelling(levelling);
+                }
+                ui.checkbox(&mut sim.chunk_sleep.enabled, "Sleep idle chunks")
+                    .on_hover_text(
+                        "Skip 32x32 chunks where nothing has changed for a while; they wake \
+                         up as soon as something around them moves.",
+                    );
+                if sim.chunk_sleep.enabled {
+                    ui.label(format!(
+                        "Sleeping chunks: {} / {}",
+                        sim.chunk_sleep.sleeping(),
+                        sim.chunk_sleep.chunk_count()
+                    ));
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+                    let mut threads = sim.threads();
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut threads, 1..=cores.min(crate::simulation::MAX_THREADS))
+                                .text("Cell threads"),
+                        )
+                        .on_hover_text(
+                            "Update the grid on several cores, in chunks far enough apart not \
+                             to touch. Runs differ from single-threaded ones.",
+                        )
+                        .changed()
+                    {
+                        if let Err(err) = sim.set_threads(threads) {
+                            self.editor_state.add_toast(
+                                err,
+                                crate::editor::state::ToastLevel::Error,
+                            );
+                        }
+                    }
+                }
+
+                ui.separator();
+                ui.heading("Seed");
+                if self.seed_text.is_empty() {
+                    self.seed_text = sim.seed.to_string();
+                }
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.seed_text).desired_width(160.0));
+                    let parsed = self.seed_text.trim().parse::<u64>();
+                    if ui
+                        .add_enabled(parsed.is_ok(), egui::Button::new("Reseed"))
+                        .on_hover_text("Restart cell and script randomness from this seed")
+                        .clicked()
+                    {
+                        if let Ok(seed) = parsed {
+                            sim.reseed(seed);
+                        }
+                    }
+                    if ui
+                        .add_enabled(parsed.is_ok(), egui::Button::new("Generate map"))
+                        .on_hover_text("Reseed and generate the random map for this seed")
+                        .clicked()
+                    {
+                        if let Ok(seed) = parsed {
+                            sim.reseed(seed);
+                            sim.generate_simple();
+                        }
+                    }
+                });
+                ui.label(format!("Current seed: {}", sim.seed));
+
+                ui.separator();
+                ui.heading("Grid size");
+                ui.horizontal(|ui| {
+                    let range = cs::MIN_SECTOR_SIZE as u32..=cs::MAX_SECTOR_SIZE as u32;
+                    let step = cs::SECTOR_ALIGN as f64;
+                    let (width, height) = &mut self.new_grid_size;
+                    ui.add(egui::DragValue::new(width).clamp_range(range.clone()).speed(step));
+                    ui.label("x");
+                    ui.add(egui::DragValue::new(height).clamp_range(range).speed(step));
+                    if ui
+                        .button("New grid")
+                        .on_hover_text("Start over on an empty grid of this size")
+                        .clicked()
+                    {
+                        let (width, height) = self.new_grid_size;
+                        if let Err(err) = sim.resize_grid(width, height) {
+                            self.editor_state
+                                .add_toast(err, crate::editor::state::ToastLevel::Error);
+                        }
+                    }
+                });
+                let size = sim.grid_size();
+                ui.label(format!("Current grid: {}x{}", size.x, size.y));
 
                 ui.separator();
                 ui.heading("Temperature");
                 ui.add(
                     egui::Slider::new(
-                        &mut state.global_temperature,
-                        crate::state::TEMP_MIN..=1000.0,
+                        &mut sim.global_temperature,
+                        crate::simulation::TEMP_MIN..=1000.0,
                     )
                     // Keep slider limited, but allow any numeric input via the slider's value editor.
                     .clamp_to_range(false)
                     .text("Global temperature (°)"),
                 );
                 if ui.button("Reset global temperature").clicked() {
-                    state.global_temperature = 21.0;
+                    sim.global_temperature = 21.0;
                 }
 
                 ui.separator();
                 ui.heading("Day / Night");
                 ui.horizontal(|ui| {
-                    ui.checkbox(&mut state.day_night.paused, "Pause cycle");
+                    ui.checkbox(&mut sim.day_night.paused, "Pause cycle");
                     if ui.button("Reset").clicked() {
-                        state.day_night.time_of_day_seconds = 0.0;
+                        sim.day_night.time_of_day_seconds = 0.0;
                     }
                 });
                 ui.add(
-                    egui::Slider::new(&mut state.day_night.day_length_seconds, 5.0..=600.0)
+                    egui::Slider::new(&mut sim.day_night.day_length_seconds, 5.0..=600.0)
                         .clamp_to_range(false)
                         .text("Day length (s)"),
                 );
                 ui.add(
-                    egui::Slider::new(&mut state.day_night.speed, 0.0..=20.0)
+                    egui::Slider::new(&mut sim.day_night.speed, 0.0..=20.0)
                         .clamp_to_range(false)
                         .text("Speed"),
                 );
                 ui.label(format!(
                     "sim_time: {:.1}s | time_of_day: {:.1}s",
-                    state.sim_time_seconds, state.day_night.time_of_day_seconds
+                    sim.sim_time_seconds, sim.day_night.time_of_day_seconds
                 ));
 
                 ui.separator();
@@ -994,11 +1256,11 @@ impl EvolutionApp {
                 ui.label("Click to add");
 
                 if ui.button("Wooden platforms").clicked() {
-                    self.spawn_platforms(state);
+                    self.spawn_platforms(sim);
                 }
 
                 if ui.button("Cubes").clicked() {
-                    self.spawn_blocks(state);
+                    self.spawn_blocks(sim);
                 }
 
                 *any_win_hovered |= context.is_pointer_over_area()
@@ -1020,19 +1282,19 @@ impl EvolutionApp {
 
                 ui.add(
                     // 0..1 = normal strength, 1..2 = push towards pure black.
-                    egui::Slider::new(&mut state.day_night.shadow_strength, 0.0..=2.0)
+                    egui::Slider::new(&mut sim.day_night.shadow_strength, 0.0..=2.0)
                         .clamp_to_range(false)
                         .text("Strength"),
                 );
 
                 ui.add(
-                    egui::Slider::new(&mut state.day_night.shadow_length_steps, 1.0..=64.0)
+                    egui::Slider::new(&mut sim.day_night.shadow_length_steps, 1.0..=64.0)
                         .clamp_to_range(false)
                         .text("Length (steps)"),
                 );
 
                 ui.add(
-                    egui::Slider::new(&mut state.day_night.shadow_distance_falloff, 0.0..=4.0)
+                    egui::Slider::new(&mut sim.day_night.shadow_distance_falloff, 0.0..=4.0)
                         .clamp_to_range(false)
                         .text("Distance falloff"),
                 );
@@ -1262,7 +1524,7 @@ impl EvolutionApp {
                                 ui.spacing_mut().item_spacing = egui::vec2(4.0, 4.0);
 
                                 // Iterate over all registered cell types (except id 0 / Void).
-                                for cell in state.pal_container.pal.iter() {
+                                for cell in sim.pal_container.pal.iter() {
                                     let id = cell.id();
                                     if id == 0 {
                                         continue;
@@ -1422,19 +1684,20 @@ impl EvolutionApp {
         }
     }
 
-    fn spawn_blocks(&mut self, state: &mut State) {
+    fn spawn_blocks(&mut self, sim: &mut Simulation) {
+        let (width, height) = sim.diffuse_rgba.dimensions();
         for _ in 0..self.number_of_structures_to_add {
             let mut buf = [0u8; 4];
             _ = getrandom::getrandom(&mut buf);
 
-            let nx = (((buf[0] as u32) << 8) | buf[1] as u32) % cs::SECTOR_SIZE.x as u32;
-            let ny = (((buf[2] as u32) << 8) | buf[3] as u32) % cs::SECTOR_SIZE.y as u32;
+            let nx = (((buf[0] as u32) << 8) | buf[1] as u32) % width;
+            let ny = (((buf[2] as u32) << 8) | buf[3] as u32) % height;
 
             for x in 0..20 {
                 for y in 0..20 {
-                    state.diffuse_rgba.put_pixel(
-                        clamp(nx + x, 0, cs::SECTOR_SIZE.x as u32 - 1),
-                        clamp(ny + y, 0, cs::SECTOR_SIZE.y as u32 - 1),
+                    sim.diffuse_rgba.put_pixel(
+                        clamp(nx + x, 0, width - 1),
+                        clamp(ny + y, 0, height - 1),
                         image::Luma([Wood::id()]),
                     );
                 }
@@ -1442,40 +1705,34 @@ impl EvolutionApp {
         }
     }
 
-    fn spawn_platforms(&mut self, state: &mut State) {
+    fn spawn_platforms(&mut self, sim: &mut Simulation) {
+        let (width, height) = sim.diffuse_rgba.dimensions();
         for _ in 0..self.number_of_structures_to_add {
             let mut buf = [0u8; 4];
             _ = getrandom::getrandom(&mut buf);
 
-            let nx = (((buf[0] as u32) << 8) | buf[1] as u32) % cs::SECTOR_SIZE.x as u32;
-            let ny = (((buf[2] as u32) << 8) | buf[3] as u32) % cs::SECTOR_SIZE.y as u32;
+            let nx = (((buf[0] as u32) << 8) | buf[1] as u32) % width;
+            let ny = (((buf[2] as u32) << 8) | buf[3] as u32) % height;
 
             for x in 0..50 {
-                state.diffuse_rgba.put_pixel(
-                    clamp(nx + x, 0, cs::SECTOR_SIZE.x as u32 - 1),
-                    clamp(ny, 0, cs::SECTOR_SIZE.y as u32 - 1),
+                sim.diffuse_rgba.put_pixel(
+                    clamp(nx + x, 0, width - 1),
+                    clamp(ny, 0, height - 1),
                     image::Luma([Wood::id()]),
                 );
             }
         }
     }
 
-    fn clear_map(state: &mut State) {
-        state.diffuse_rgba = image::GrayImage::from_fn(
-            cs::SECTOR_SIZE.x as u32,
-            cs::SECTOR_SIZE.y as u32,
-            |x, y| {
-                if x > 1
-                    && y > 1
-                    && x < cs::SECTOR_SIZE.x as u32 - 2
-                    && y < cs::SECTOR_SIZE.y as u32 - 2
-                {
-                    return image::Luma([Void::id()]);
-                } else {
-                    return image::Luma([Stone::id()]);
-                }
-            },
-        );
+    fn clear_map(sim: &mut Simulation) {
+        let (width, height) = sim.diffuse_rgba.dimensions();
+        sim.diffuse_rgba = image::GrayImage::from_fn(width, height, |x, y| {
+            if x > 1 && y > 1 && x < width - 2 && y < height - 2 {
+                return image::Luma([Void::id()]);
+            } else {
+                return image::Luma([Stone::id()]);
+            }
+        });
     }
 
     pub fn new() -> Self {
@@ -1525,6 +1782,11 @@ impl EvolutionApp {
             projects_fetched: false,
 
             last_load_url: String::new(),
+            seed_text: String::new(),
+            new_grid_size: (
+                cs::DEFAULT_SECTOR_SIZE.x as u32,
+                cs::DEFAULT_SECTOR_SIZE.y as u32,
+            ),
 
             editor_state: EditorState::new(),
             undo_redo: UndoRedo::new(),
@@ -1558,6 +1820,14 @@ fn create_event_with_scene(bytes: Vec<u8>) -> UserEventInfo {
     UserEventInfo::SceneImport(bytes)
 }
 
+fn create_event_with_save(bytes: Vec<u8>) -> UserEventInfo {
+    UserEventInfo::SaveImport(bytes)
+}
+
+fn create_event_with_materials(bytes: Vec<u8>) -> UserEventInfo {
+    UserEventInfo::MaterialsImport(bytes)
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 struct SceneToml {
     #[serde(default = "default_scene_version")]
@@ -0,0 +1,103 @@
+//! Map PNGs: luma8 cell ids plus a `tEXt` chunk naming every id used in the map.
+//!
+//! Cell ids are just indices into `cells::setup_palette`, so on import the names are looked up
+//! in the current `CellRegistry::dict` and the grid is remapped. Maps without the chunk
+//! (everything saved before it existed) are loaded as-is.
+
+use std::error::Error;
+
+use crate::cells::{void::Void, CellRegistry};
+
+pub const CELL_TABLE_KEYWORD: &str = "sand_evolution_cells";
+
+/// A decoded map and the names of cells the current palette doesn't know (replaced with void).
+pub struct LoadedMap {
+    pub grid: image::GrayImage,
+    pub unknown_cells: Vec<String>,
+}
+
+/// `id=name` per line for every id present in `grid`.
+pub fn cell_table(grid: &image::GrayImage, registry: &CellRegistry) -> String {
+    let mut used = [false; 256];
+    for &id in grid.as_raw() {
+        used[id as usize] = true;
+    }
+    let mut table = String::new();
+    for (id, _) in used.iter().enumerate().filter(|(_, used)| **used) {
+        table.push_str(&format!("{}={}\n", id, registry.pal[id].name()));
+    }
+    table
+}
+
+/// Rewrites ids saved with `table` to the ids of the same cells in `registry`.
+/// Returns the names that no longer exist; their cells become void.
+pub fn remap_cells(
+    grid: &mut image::GrayImage,
+    table: &str,
+    registry: &CellRegistry,
+) -> Vec<String> {
+    let mut remap: [u8; 256] = std::array::from_fn(|id| id as u8);
+    let mut unknown = Vec::new();
+    for line in table.lines() {
+        let Some((id, name)) = line.split_once('=') else {
+            continue;
+        };
+        let Ok(id) = id.trim().parse::<u8>() else {
+            continue;
+        };
+        // Void fills every unused palette slot, so `dict` doesn't point it at 0.
+        remap[id as usize] = if name == registry.pal[Void::id() as usize].name() {
+            Void::id()
+        } else if let Some(&new_id) = registry.dict.get(name) {
+            new_id
+        } else {
+            unknown.push(name.to_owned());
+            Void::id()
+        };
+    }
+
+    if remap.iter().enumerate().any(|(id, &new_id)| id as u8 != new_id) {
+        for id in grid.iter_mut() {
+            *id = remap[*id as usize];
+        }
+    }
+    unknown
+}
+
+pub fn encode_png(
+    grid: &image::GrayImage,
+    registry: &CellRegistry,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, grid.width(), grid.height());
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.add_text_chunk(CELL_TABLE_KEYWORD.to_owned(), cell_table(grid, registry))?;
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(grid.as_raw())?;
+    writer.finish()?;
+    Ok(out)
+}
+
+pub fn decode_map(bytes: &[u8], registry: &CellRegistry) -> Result<LoadedMap, Box<dyn Error>> {
+    let mut grid = image::load_from_memory(bytes)?.to_luma8();
+
+    // Only PNG has the table; other formats `image` can read are loaded as raw ids.
+    let table = png::Decoder::new(bytes).read_info().ok().and_then(|reader| {
+        reader
+            .info()
+            .uncompressed_latin1_text
+            .iter()
+            .find(|chunk| chunk.keyword == CELL_TABLE_KEYWORD)
+            .map(|chunk| chunk.text.clone())
+    });
+
+    let unknown_cells = match table {
+        Some(table) => remap_cells(&mut grid, &table, registry),
+        None => Vec::new(),
+    };
+    Ok(LoadedMap {
+        grid,
+        unknown_cells,
+    })
+}
@@ -6,7 +6,7 @@ use std::rc::Rc;
 pub struct RhaiResourceStorage {
     pub engine: rhai::Engine,
     pub scope: rhai::Scope<'static>,
-    pub state_ptr: std::cell::Cell<*mut crate::State>,
+    pub sim_ptr: std::cell::Cell<*mut crate::simulation::Simulation>,
     pub script_log: Rc<RefCell<VecDeque<String>>>,
 }
 
@@ -1,3 +1,4 @@
+use crate::ecs::components::RigidBody;
 use crate::shared_state::SharedState;
 use cgmath::{InnerSpace, Matrix, Matrix2, Matrix3, SquareMatrix, Vector2, Vector3};
 use specs::{Builder, Join, WorldExt};
@@ -7,15 +8,31 @@ use std::collections::{HashMap, VecDeque};
 use std::rc::Rc;
 
 thread_local! {
-    static STATE_PTR: Cell<*mut crate::State> = Cell::new(std::ptr::null_mut());
+    static STATE_PTR: Cell<*mut crate::simulation::Simulation> = Cell::new(std::ptr::null_mut());
 }
 
-pub fn set_state_ptr(ptr: *mut crate::State) {
+pub fn set_state_ptr(ptr: *mut crate::simulation::Simulation) {
     STATE_PTR.with(|cell| {
         cell.set(ptr);
     });
 }
 
+/// Resets the pointer if it still points at `ptr` (called when that simulation is dropped).
+pub fn clear_state_ptr(ptr: *mut crate::simulation::Simulation) {
+    STATE_PTR.with(|cell| {
+        if cell.get() == ptr {
+            cell.set(std::ptr::null_mut());
+        }
+    });
+}
+
+/// Whether (x, y) is a cell of the grid. Checked on the script's i64 coordinates, before they
+/// are cast to `PointType` and could wrap around into it.
+fn in_grid(state: &crate::simulation::Simulation, x: i64, y: i64) -> bool {
+    let size = state.grid_size();
+    (0..size.x as i64).contains(&x) && (0..size.y as i64).contains(&y)
+}
+
 pub fn register_rhai(
     rhai: &mut rhai::Engine,
     scope: &mut rhai::Scope,
@@ -172,10 +189,20 @@ pub fn register_rhai(
     }
 
     rhai.register_fn("fract", move |v: f64| v.fract());
-    rhai.register_fn("rand", move || -> i64 { crate::random::my_rand() });
+    // Seeded from the simulation when one is being stepped, so runs are reproducible.
+    rhai.register_fn("rand", move || -> i64 {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            if state_ptr.is_null() {
+                crate::random::my_rand()
+            } else {
+                unsafe { (*state_ptr).script_rand() }
+            }
+        })
+    });
     scope.push("time", 0f64);
-    scope.push("GRID_WIDTH", 1024i64);
-    scope.push("GRID_HEIGHT", 512i64);
+    scope.push("GRID_WIDTH", crate::cs::sector_size().x as i64);
+    scope.push("GRID_HEIGHT", crate::cs::sector_size().y as i64);
 
     // Register set_temperature function - reads state pointer from thread_local
     // Overload for i64, i64, f64 (for integer loop variables)
@@ -225,6 +252,237 @@ pub fn register_rhai(
         });
     });
 
+    // Register add_pressure function - blast impulse that throws loose cells (see Simulation::add_pressure)
+    rhai.register_fn("add_pressure", |x: i64, y: i64, amount: f64| {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            if !state_ptr.is_null() && in_grid(unsafe { &*state_ptr }, x, y) {
+                unsafe {
+                    (*state_ptr).add_pressure(
+                        x as crate::cs::PointType,
+                        y as crate::cs::PointType,
+                        amount as f32,
+                    );
+                }
+            }
+        });
+    });
+
+    rhai.register_fn("add_pressure", |x: f64, y: f64, amount: f64| {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            let (x_cell, y_cell) = (x.floor() as i64, y.floor() as i64);
+            if !state_ptr.is_null() && in_grid(unsafe { &*state_ptr }, x_cell, y_cell) {
+                unsafe {
+                    (*state_ptr).add_pressure(
+                        x as crate::cs::PointType,
+                        y as crate::cs::PointType,
+                        amount as f32,
+                    );
+                }
+            }
+        });
+    });
+
+    // Register set_velocity function - launches a cell when momentum is on (see Simulation::set_velocity)
+    rhai.register_fn("set_velocity", |x: i64, y: i64, vx: f64, vy: f64| {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            if !state_ptr.is_null() && in_grid(unsafe { &*state_ptr }, x, y) {
+                unsafe {
+                    (*state_ptr).set_velocity(
+                        x as crate::cs::PointType,
+                        y as crate::cs::PointType,
+                        [vx as f32, vy as f32],
+                    );
+                }
+            }
+        });
+    });
+
+    // Register set_gravity function - "down", "up", "left", "right" or "zero" (see Simulation::set_gravity)
+    rhai.register_fn("set_gravity", |direction: &str| {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            if !state_ptr.is_null() {
+                if let Some(gravity) = crate::cells::Gravity::from_name(direction) {
+                    unsafe {
+                        (*state_ptr).set_gravity(gravity);
+                    }
+                }
+            }
+        });
+    });
+
+    // Register set_liquid_levelling function - lets connected vessels even out (see helper::level_liquid)
+    rhai.register_fn("set_liquid_levelling", |enabled: bool| {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            if !state_ptr.is_null() {
+                unsafe {
+                    (*state_ptr).set_liquid_levelling(enabled);
+                }
+            }
+        });
+    });
+
+    // Register get_gravity function - current gravity direction name
+    rhai.register_fn("get_gravity", || -> String {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            if !state_ptr.is_null() {
+                unsafe {
+                    return (*state_ptr).gravity().name().to_owned();
+                }
+            }
+            crate::cells::Gravity::Down.name().to_owned()
+        })
+    });
+
+    // Register get_charge function - electric charge of a cell, 0.0 when unpowered (see Simulation::step_electricity)
+    rhai.register_fn("get_charge", |x: i64, y: i64| -> f64 {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            if !state_ptr.is_null() && in_grid(unsafe { &*state_ptr }, x, y) {
+                unsafe {
+                    return (*state_ptr)
+                        .get_charge(x as crate::cs::PointType, y as crate::cs::PointType)
+                        as f64;
+                }
+            }
+            0.0
+        })
+    });
+
+    // Register get_light function - laser beam power crossing a cell, 0.0 when dark (see laser::cast_beams)
+    rhai.register_fn("get_light", |x: i64, y: i64| -> f64 {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            if !state_ptr.is_null() && in_grid(unsafe { &*state_ptr }, x, y) {
+                unsafe {
+                    return (*state_ptr)
+                        .get_light(x as crate::cs::PointType, y as crate::cs::PointType)
+                        as f64;
+                }
+            }
+            0.0
+        })
+    });
+
+    // Register get_aux function - value a cell keeps between updates, 0 while the aux buffer is off (see CellGrid::aux)
+    rhai.register_fn("get_aux", |x: i64, y: i64| -> i64 {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            if !state_ptr.is_null() && in_grid(unsafe { &*state_ptr }, x, y) {
+                unsafe {
+                    return (*state_ptr)
+                        .get_aux(x as crate::cs::PointType, y as crate::cs::PointType)
+                        as i64;
+                }
+            }
+            0
+        })
+    });
+
+    // Register set_aux function - e.g. primes a fuse; clamped to 0..=65535, ignored while the aux buffer is off
+    rhai.register_fn("set_aux", |x: i64, y: i64, value: i64| {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            if !state_ptr.is_null() && in_grid(unsafe { &*state_ptr }, x, y) {
+                unsafe {
+                    (*state_ptr).set_aux(
+                        x as crate::cs::PointType,
+                        y as crate::cs::PointType,
+                        value.clamp(0, u16::MAX as i64) as u16,
+                    );
+                }
+            }
+        });
+    });
+
+    // Register add_wind function - blows at a point; the gust spreads and dies down (see Simulation::step_wind)
+    rhai.register_fn("add_wind", |x: i64, y: i64, vx: f64, vy: f64| {
+        STATE_PTR.with(|ptr| {
+            let state_ptr = ptr.get();
+            if !state_ptr.is_null() && x >= 0 && y >= 0 {
+                unsafe {
+                    (*state_ptr).add_wind(
+                        x as crate::cs::PointType,
+                        y as crate::cs::PointType,
+                        [vx as f32, vy as f32],
+                    );
+                }
+            }
+        });
+    });
+
+    // Register get_wind_x / get_wind_y functions - air flow at a cell in cells per update
+    for (name, axis) in [("get_wind_x", 0), ("get_wind_y", 1)] {
+        rhai.register_fn(name, move |x: i64, y: i64| -> f64 {
+            STATE_PTR.with(|ptr| {
+                let state_ptr = ptr.get();
+                if !state_ptr.is_null() && x >= 0 && y >= 0 {
+                    unsafe {
+                        return (*state_ptr)
+                            .get_wind(x as crate::cs::PointType, y as crate::cs::PointType)[axis]
+                            as f64;
+                    }
+                }
+                0.0
+            })
+        });
+    }
+
+    // Register focus_sector function - moves the sector window on the next tick (see sectors)
+    {
+        let shared_state_clone = shared_state_rc.clone();
+        rhai.register_fn("focus_sector", move |x: i64, y: i64| {
+            shared_state_clone.borrow_mut().focus = Some((x as i32, y as i32));
+        });
+    }
+
+    // Register focus_sector_x / focus_sector_y functions - sector in the middle of the window, 0 without sectors
+    for (name, axis) in [("focus_sector_x", 0), ("focus_sector_y", 1)] {
+        rhai.register_fn(name, move || -> i64 {
+            STATE_PTR.with(|ptr| {
+                let state_ptr = ptr.get();
+                if !state_ptr.is_null() {
+                    if let Some(world) = unsafe { &(*state_ptr).sectors } {
+                        let (x, y) = world.focus();
+                        return if axis == 0 { x } else { y } as i64;
+                    }
+                }
+                0
+            })
+        });
+    }
+
+    // Register add_crate / add_boulder functions - rigid bodies, spawned on the next tick (see ecs::rigid_body)
+    {
+        let shared_state_clone = shared_state_rc.clone();
+        rhai.register_fn("add_crate", move |x: i64, y: i64, w: i64, h: i64| {
+            let body = RigidBody::wooden_crate(w.clamp(1, 128) as u16, h.clamp(1, 128) as u16);
+            shared_state_clone.borrow_mut().bodies.push((
+                "Crate".to_owned(),
+                x as f32,
+                y as f32,
+                body,
+            ));
+        });
+    }
+    {
+        let shared_state_clone = shared_state_rc.clone();
+        rhai.register_fn("add_boulder", move |x: i64, y: i64, r: i64| {
+            let body = RigidBody::boulder(r.clamp(1, 64) as u16);
+            shared_state_clone.borrow_mut().bodies.push((
+                "Boulder".to_owned(),
+                x as f32,
+                y as f32,
+                body,
+            ));
+        });
+    }
+
     // Register try_set_cell function - sets cell only if position is void (empty)
     // String-based cell type overloads for try_set_cell
     {
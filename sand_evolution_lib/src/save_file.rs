@@ -0,0 +1,587 @@
+//! Versioned binary world save.
+//!
+//! Layout: `SEVSAVE\0` magic, `u32` format version, then sections until EOF.
+//! Every section is a 4-byte tag, a `u32` payload length and a deflate-compressed payload;
+//! all integers are little endian. Readers skip tags they don't know, and sections only
+//! ever grow by appending fields, so saves from newer builds still load (minus the new data).
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::cells::{CellAux, Gravity, Prng};
+use crate::map_file;
+use crate::simulation::{Simulation, TemperatureResolution, PRESSURE_CELL_SIZE, WIND_CELL_SIZE};
+
+const MAGIC: &[u8; 8] = b"SEVSAVE\0";
+/// Magic of `write_region`, which is followed by the same version and sections.
+const REGION_MAGIC: &[u8; 8] = b"SEVREGN\0";
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Counters, update parity, RNG state, global temperature, day/night phase, gravity
+/// (absent in older saves: down) and liquid levelling (absent in older saves: on).
+const TAG_META: [u8; 4] = *b"META";
+/// Cell ids, row-major.
+const TAG_GRID: [u8; 4] = *b"GRID";
+/// Temperature grid (deltas from global temperature), row-major `f32`, then its cell size
+/// (see `TemperatureResolution`; absent in older saves, which are always reduced).
+const TAG_TEMPERATURE: [u8; 4] = *b"TEMP";
+/// Blast pressure grid, `u32` count then row-major `f32` (absent in older saves: no explosion).
+const TAG_PRESSURE: [u8; 4] = *b"PRES";
+/// Cell velocities, `u32` count then row-major `[f32; 2]`; a count of 0 (or no section in
+/// older saves) means momentum is off.
+const TAG_VELOCITY: [u8; 4] = *b"VELO";
+/// Aux values (see `CellGrid`), `u32` count then row-major `u16`, each for the cell loaded
+/// into its place; a count of 0 (or no section in older saves) means the aux buffer is off.
+const TAG_AUX: [u8; 4] = *b"AUXV";
+/// Wind field, `u32` count then row-major `[f32; 2]` (absent in older saves: still air).
+const TAG_WIND: [u8; 4] = *b"WIND";
+/// Entities as scene TOML (same text as "Export TOML").
+const TAG_SCENE: [u8; 4] = *b"SCEN";
+/// `id=name` lines for the ids in GRID, so saves survive palette renumbering (see `map_file`).
+const TAG_CELL_IDS: [u8; 4] = *b"CIDS";
+
+const COMPRESSION_LEVEL: u8 = 6;
+
+/// What `read_save` hands back to the caller after restoring the simulation.
+pub struct LoadedSave {
+    /// Scene TOML for `EvolutionApp::import_scene_from_toml`, if the save has one.
+    pub scene: Option<String>,
+    /// Cells the current palette doesn't know; they were loaded as void.
+    pub unknown_cells: Vec<String>,
+}
+
+/// Serializes the simulation together with the scene TOML of its entities.
+pub fn write_save(sim: &Simulation, scene_toml: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+
+    let mut meta = ByteWriter::default();
+    meta.i64(sim.tick);
+    meta.i64(sim.frame);
+    meta.f64(sim.sim_time_seconds);
+    meta.f32(sim.global_temperature);
+    meta.i32(sim.steps_per_second);
+    meta.i32(sim.cell_diffusion_iterations);
+    meta.u64(sim.seed);
+    meta.u64(sim.prng.state());
+    meta.i32(sim.prng.carb());
+    meta.u64(sim.script_prng.state());
+    let dn = &sim.day_night;
+    meta.f32(dn.day_length_seconds);
+    meta.f32(dn.time_of_day_seconds);
+    meta.f32(dn.speed);
+    meta.u8(dn.paused as u8);
+    meta.f32(dn.sun_angle_offset_rad);
+    meta.f32(dn.shadow_strength);
+    meta.f32(dn.shadow_length_steps);
+    meta.f32(dn.shadow_distance_falloff);
+    meta.u8(sim.toggled as u8);
+    meta.u8(sim.flip as u8);
+    meta.u8(sim.flop as u8);
+    meta.u8(sim.gravity() as u8);
+    meta.u8(sim.liquid_levelling() as u8);
+    write_section(&mut out, TAG_META, &meta.0);
+
+    let mut grid = ByteWriter::default();
+    grid.u32(sim.diffuse_rgba.width());
+    grid.u32(sim.diffuse_rgba.height());
+    grid.0.extend_from_slice(sim.diffuse_rgba.as_raw());
+    write_section(&mut out, TAG_GRID, &grid.0);
+    let cell_table = map_file::cell_table(&sim.diffuse_rgba, &sim.pal_container);
+    write_section(&mut out, TAG_CELL_IDS, cell_table.as_bytes());
+
+    let mut temps = ByteWriter::default();
+    temps.u32(sim.cell_temperatures.len() as u32);
+    for t in &sim.cell_temperatures {
+        temps.f32(*t);
+    }
+    temps.u32(sim.temperature_resolution().cell_size());
+    write_section(&mut out, TAG_TEMPERATURE, &temps.0);
+
+    let mut pressures = ByteWriter::default();
+    pressures.u32(sim.cell_pressures.len() as u32);
+    for p in &sim.cell_pressures {
+        pressures.f32(*p);
+    }
+    write_section(&mut out, TAG_PRESSURE, &pressures.0);
+
+    let mut velocities = ByteWriter::default();
+    velocities.u32(sim.cell_velocities.len() as u32);
+    for [vx, vy] in &sim.cell_velocities {
+        velocities.f32(*vx);
+        velocities.f32(*vy);
+    }
+    write_section(&mut out, TAG_VELOCITY, &velocities.0);
+
+    let mut aux = ByteWriter::default();
+    aux.u32(sim.cell_aux.len() as u32);
+    for (slot, &cell) in sim.cell_aux.iter().zip(sim.diffuse_rgba.as_raw()) {
+        // Only values the cell in their place can read; `read_save` tags them with that cell again.
+        aux.u16(if slot.owner == cell { slot.value } else { 0 });
+    }
+    write_section(&mut out, TAG_AUX, &aux.0);
+
+    let mut wind = ByteWriter::default();
+    wind.u32(sim.cell_wind.len() as u32);
+    for [wx, wy] in &sim.cell_wind {
+        wind.f32(*wx);
+        wind.f32(*wy);
+    }
+    write_section(&mut out, TAG_WIND, &wind.0);
+
+    write_section(&mut out, TAG_SCENE, scene_toml.as_bytes());
+    out
+}
+
+/// Restores a save written by `write_save`.
+///
+/// The whole file is validated before anything is applied, so a broken save leaves `sim` untouched.
+pub fn read_save(sim: &mut Simulation, bytes: &[u8]) -> Result<LoadedSave, Box<dyn Error>> {
+    if bytes.len() < MAGIC.len() + 4 || &bytes[..MAGIC.len()] != MAGIC {
+        return Err("not a sand_evolution save file".into());
+    }
+    let mut header = ByteReader(&bytes[MAGIC.len()..]);
+    let version = header.u32()?;
+    if version == 0 {
+        return Err(format!("unsupported save version {version}").into());
+    }
+
+    let sections = read_sections(header.0)?;
+    let required = |tag: [u8; 4]| {
+        sections
+            .get(&tag)
+            .ok_or_else(|| format!("missing {} section", String::from_utf8_lossy(&tag)))
+    };
+
+    let mut grid = ByteReader(required(TAG_GRID)?);
+    let (width, height) = (grid.u32()?, grid.u32()?);
+    crate::cs::check_sector_size(width, height)?;
+    let cells = grid.take(width as usize * height as usize)?.to_vec();
+    let mut cells = image::GrayImage::from_raw(width, height, cells).unwrap();
+    let unknown_cells = match sections.get(&TAG_CELL_IDS) {
+        Some(table) => {
+            map_file::remap_cells(&mut cells, &String::from_utf8_lossy(table), &sim.pal_container)
+        }
+        None => Vec::new(),
+    };
+
+    let mut temps = ByteReader(required(TAG_TEMPERATURE)?);
+    let count = temps.u32()? as usize;
+    let temperatures = (0..count)
+        .map(|_| temps.f32())
+        .collect::<Result<Vec<_>, _>>()?;
+    let temperature_resolution = if temps.0.is_empty() {
+        TemperatureResolution::Reduced
+    } else {
+        match temps.u32()? {
+            4 => TemperatureResolution::Reduced,
+            1 => TemperatureResolution::Full,
+            size => return Err(format!("unsupported temperature cell size {size}").into()),
+        }
+    };
+    let scale = temperature_resolution.cell_size();
+    let expected = ((width / scale) * (height / scale)) as usize;
+    if count != expected {
+        return Err(format!("save has {count} temperature cells, expected {expected}").into());
+    }
+
+    let pressure_cells = ((width / PRESSURE_CELL_SIZE) * (height / PRESSURE_CELL_SIZE)) as usize;
+    let pressures = match sections.get(&TAG_PRESSURE) {
+        Some(payload) => {
+            let mut r = ByteReader(payload);
+            let count = r.u32()? as usize;
+            if count != pressure_cells {
+                return Err(
+                    format!("save has {count} pressure cells, expected {pressure_cells}").into(),
+                );
+            }
+            (0..count).map(|_| r.f32()).collect::<Result<Vec<_>, _>>()?
+        }
+        None => vec![0.0; pressure_cells],
+    };
+
+    let velocities = match sections.get(&TAG_VELOCITY) {
+        Some(payload) => {
+            let mut r = ByteReader(payload);
+            let count = r.u32()? as usize;
+            if count != 0 && count != (width * height) as usize {
+                return Err(format!(
+                    "save has {count} cell velocities, expected 0 or {}",
+                    width * height
+                )
+                .into());
+            }
+            (0..count)
+                .map(|_| Ok([r.f32()?, r.f32()?]))
+                .collect::<Result<Vec<_>, String>>()?
+        }
+        None => Vec::new(),
+    };
+
+    let aux = match sections.get(&TAG_AUX) {
+        Some(payload) => {
+            let mut r = ByteReader(payload);
+            let count = r.u32()? as usize;
+            if count != 0 && count != (width * height) as usize {
+                return Err(format!(
+                    "save has {count} aux values, expected 0 or {}",
+                    width * height
+                )
+                .into());
+            }
+            (0..count).map(|_| r.u16()).collect::<Result<Vec<_>, _>>()?
+        }
+        None => Vec::new(),
+    };
+
+    let wind_cells = ((width / WIND_CELL_SIZE) * (height / WIND_CELL_SIZE)) as usize;
+    let wind = match sections.get(&TAG_WIND) {
+        Some(payload) => {
+            let mut r = ByteReader(payload);
+            let count = r.u32()? as usize;
+            if count != wind_cells {
+                return Err(format!("save has {count} wind cells, expected {wind_cells}").into());
+            }
+            (0..count)
+                .map(|_| Ok([r.f32()?, r.f32()?]))
+                .collect::<Result<Vec<_>, String>>()?
+        }
+        None => vec![[0.0; 2]; wind_cells],
+    };
+
+    let mut meta = ByteReader(required(TAG_META)?);
+    let tick = meta.i64()?;
+    let frame = meta.i64()?;
+    let sim_time_seconds = meta.f64()?;
+    let global_temperature = meta.f32()?;
+    let steps_per_second = meta.i32()?;
+    let cell_diffusion_iterations = meta.i32()?;
+    let seed = meta.u64()?;
+    let prng = Prng::from_state(meta.u64()?, meta.i32()?);
+    let script_prng = Prng::from_state(meta.u64()?, 0);
+    let mut day_night = sim.day_night.clone();
+    day_night.day_length_seconds = meta.f32()?;
+    day_night.time_of_day_seconds = meta.f32()?;
+    day_night.speed = meta.f32()?;
+    day_night.paused = meta.u8()? != 0;
+    day_night.sun_angle_offset_rad = meta.f32()?;
+    day_night.shadow_strength = meta.f32()?;
+    day_night.shadow_length_steps = meta.f32()?;
+    day_night.shadow_distance_falloff = meta.f32()?;
+    let toggled = meta.u8()? != 0;
+    let flip = meta.u8()? as crate::cs::PointType;
+    let flop = meta.u8()? as crate::cs::PointType;
+    let gravity = if meta.0.is_empty() {
+        Gravity::Down
+    } else {
+        let raw = meta.u8()? as usize;
+        *Gravity::ALL
+            .get(raw)
+            .ok_or_else(|| format!("unknown gravity {raw}"))?
+    };
+    let liquid_levelling = meta.0.is_empty() || meta.u8()? != 0;
+
+    let scene = match sections.get(&TAG_SCENE) {
+        Some(text) => Some(String::from_utf8(text.clone()).map_err(|_| "scene is not UTF-8")?),
+        None => None,
+    };
+
+    // Reallocates the per-cell fields when the save is of another size; checked above.
+    sim.set_grid(cells)?;
+    sim.set_temperature_resolution(temperature_resolution);
+    sim.cell_temperatures = temperatures;
+    sim.set_pressures(pressures);
+    sim.cell_velocities = velocities;
+    sim.cell_aux = aux
+        .iter()
+        .zip(sim.diffuse_rgba.as_raw())
+        .map(|(&value, &owner)| CellAux { owner, value })
+        .collect();
+    sim.cell_wind = wind;
+    sim.tick = tick;
+    sim.frame = frame;
+    sim.sim_time_seconds = sim_time_seconds;
+    sim.global_temperature = global_temperature;
+    sim.steps_per_second = steps_per_second;
+    sim.cell_diffusion_iterations = cell_diffusion_iterations;
+    sim.seed = seed;
+    sim.prng = prng;
+    sim.script_prng = script_prng;
+    sim.day_night = day_night;
+    sim.toggled = toggled;
+    sim.flip = flip;
+    sim.flop = flop;
+    sim.set_gravity(gravity);
+    sim.set_liquid_levelling(liquid_levelling);
+    sim.shared_state.borrow_mut().points.clear();
+
+    Ok(LoadedSave {
+        scene,
+        unknown_cells,
+    })
+}
+
+/// Temperature, wind, velocities and aux values of the `width` x `height` cells at (x, y),
+/// for `SectorWorld` to park next to a sector's cells. Same layout as a save, with its own
+/// magic and only these four sections; the region must be aligned to `cs::SECTOR_ALIGN`.
+pub fn write_region(sim: &Simulation, x: u32, y: u32, width: u32, height: u32) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(REGION_MAGIC);
+    out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    let grid_width = sim.diffuse_rgba.width();
+    let region = [x, y, width, height];
+
+    let scale = sim.temperature_resolution().cell_size();
+    let cells = region_indices(grid_width, region, scale);
+    let mut temps = ByteWriter::default();
+    temps.u32(cells.len() as u32);
+    for &i in &cells {
+        temps.f32(sim.cell_temperatures[i]);
+    }
+    temps.u32(scale);
+    write_section(&mut out, TAG_TEMPERATURE, &temps.0);
+
+    let cells = region_indices(grid_width, region, 1);
+    let mut velocities = ByteWriter::default();
+    if sim.cell_velocities.is_empty() {
+        velocities.u32(0);
+    } else {
+        velocities.u32(cells.len() as u32);
+        for &i in &cells {
+            let [vx, vy] = sim.cell_velocities[i];
+            velocities.f32(vx);
+            velocities.f32(vy);
+        }
+    }
+    write_section(&mut out, TAG_VELOCITY, &velocities.0);
+
+    let mut aux = ByteWriter::default();
+    if sim.cell_aux.is_empty() {
+        aux.u32(0);
+    } else {
+        aux.u32(cells.len() as u32);
+        let grid = sim.diffuse_rgba.as_raw();
+        for &i in &cells {
+            let slot = sim.cell_aux[i];
+            aux.u16(if slot.owner == grid[i] { slot.value } else { 0 });
+        }
+    }
+    write_section(&mut out, TAG_AUX, &aux.0);
+
+    let scale = WIND_CELL_SIZE;
+    let cells = region_indices(grid_width, region, scale);
+    let mut wind = ByteWriter::default();
+    wind.u32(cells.len() as u32);
+    for &i in &cells {
+        let [wx, wy] = sim.cell_wind[i];
+        wind.f32(wx);
+        wind.f32(wy);
+    }
+    write_section(&mut out, TAG_WIND, &wind.0);
+    out
+}
+
+/// Puts fields written by `write_region` back at (x, y), over the cells that are there now.
+///
+/// Fields the simulation has since switched off, or whose resolution changed, are left as
+/// they are. Validated before anything is applied, like `read_save`.
+pub fn read_region(
+    sim: &mut Simulation,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    bytes: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    if bytes.len() < REGION_MAGIC.len() + 4 || &bytes[..REGION_MAGIC.len()] != REGION_MAGIC {
+        return Err("not a sand_evolution region file".into());
+    }
+    let mut header = ByteReader(&bytes[REGION_MAGIC.len()..]);
+    let version = header.u32()?;
+    if version == 0 {
+        return Err(format!("unsupported region version {version}").into());
+    }
+    let sections = read_sections(header.0)?;
+    let grid_width = sim.diffuse_rgba.width();
+    let region = [x, y, width, height];
+    let cell_indices = region_indices(grid_width, region, 1);
+
+    let scale = sim.temperature_resolution().cell_size();
+    let temp_indices = region_indices(grid_width, region, scale);
+    let mut temperatures = Vec::new();
+    if let Some(payload) = sections.get(&TAG_TEMPERATURE) {
+        let mut r = ByteReader(payload);
+        let count = r.u32()? as usize;
+        let values = (0..count).map(|_| r.f32()).collect::<Result<Vec<_>, _>>()?;
+        if r.u32()? == scale {
+            if count != temp_indices.len() {
+                let expected = temp_indices.len();
+                let message = format!("region has {count} temperature cells, expected {expected}");
+                return Err(message.into());
+            }
+            temperatures = values;
+        }
+    }
+
+    let mut velocities = Vec::new();
+    if let Some(payload) = sections.get(&TAG_VELOCITY) {
+        let mut r = ByteReader(payload);
+        let count = r.u32()? as usize;
+        if count != 0 && count != cell_indices.len() {
+            let expected = cell_indices.len();
+            let message = format!("region has {count} cell velocities, expected 0 or {expected}");
+            return Err(message.into());
+        }
+        velocities = (0..count)
+            .map(|_| Ok([r.f32()?, r.f32()?]))
+            .collect::<Result<Vec<_>, String>>()?;
+    }
+
+    let mut aux = Vec::new();
+    if let Some(payload) = sections.get(&TAG_AUX) {
+        let mut r = ByteReader(payload);
+        let count = r.u32()? as usize;
+        if count != 0 && count != cell_indices.len() {
+            let expected = cell_indices.len();
+            return Err(format!("region has {count} aux values, expected 0 or {expected}").into());
+        }
+        aux = (0..count).map(|_| r.u16()).collect::<Result<Vec<_>, _>>()?;
+    }
+
+    let scale = WIND_CELL_SIZE;
+    let wind_indices = region_indices(grid_width, region, scale);
+    let mut wind = Vec::new();
+    if let Some(payload) = sections.get(&TAG_WIND) {
+        let mut r = ByteReader(payload);
+        let count = r.u32()? as usize;
+        if count != wind_indices.len() {
+            let expected = wind_indices.len();
+            return Err(format!("region has {count} wind cells, expected {expected}").into());
+        }
+        wind = (0..count)
+            .map(|_| Ok([r.f32()?, r.f32()?]))
+            .collect::<Result<Vec<_>, String>>()?;
+    }
+
+    for (&i, t) in temp_indices.iter().zip(temperatures) {
+        sim.cell_temperatures[i] = t;
+    }
+    if !sim.cell_velocities.is_empty() {
+        for (&i, v) in cell_indices.iter().zip(velocities) {
+            sim.cell_velocities[i] = v;
+        }
+    }
+    if !sim.cell_aux.is_empty() {
+        for (&i, value) in cell_indices.iter().zip(aux) {
+            let owner = sim.diffuse_rgba.as_raw()[i];
+            sim.cell_aux[i] = CellAux { owner, value };
+        }
+    }
+    for (&i, w) in wind_indices.iter().zip(wind) {
+        sim.cell_wind[i] = w;
+    }
+    Ok(())
+}
+
+/// Row-major indices of the grid rectangle `[x, y, width, height]` in a field with one value
+/// per `scale` x `scale` cells (see `TemperatureResolution::cell_size`).
+fn region_indices(grid_width: u32, [x, y, width, height]: [u32; 4], scale: u32) -> Vec<usize> {
+    let stride = grid_width / scale;
+    let (x, y, width, height) = (x / scale, y / scale, width / scale, height / scale);
+    (y..y + height)
+        .flat_map(|j| (x..x + width).map(move |i| (j * stride + i) as usize))
+        .collect()
+}
+
+fn read_sections(mut rest: &[u8]) -> Result<HashMap<[u8; 4], Vec<u8>>, Box<dyn Error>> {
+    let mut sections = HashMap::new();
+    while !rest.is_empty() {
+        let mut r = ByteReader(rest);
+        let tag: [u8; 4] = r.array()?;
+        let len = r.u32()? as usize;
+        let payload = r.take(len)?;
+        rest = r.0;
+        let data = miniz_oxide::inflate::decompress_to_vec(payload)
+            .map_err(|e| format!("corrupt {} section: {e:?}", String::from_utf8_lossy(&tag)))?;
+        sections.insert(tag, data);
+    }
+    Ok(sections)
+}
+
+fn write_section(out: &mut Vec<u8>, tag: [u8; 4], payload: &[u8]) {
+    let compressed = miniz_oxide::deflate::compress_to_vec(payload, COMPRESSION_LEVEL);
+    out.extend_from_slice(&tag);
+    out.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+    out.extend_from_slice(&compressed);
+}
+
+#[derive(Default)]
+struct ByteWriter(Vec<u8>);
+
+impl ByteWriter {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+    fn u16(&mut self, v: u16) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn i32(&mut self, v: i32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn u64(&mut self, v: u64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn i64(&mut self, v: i64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn f32(&mut self, v: f32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn f64(&mut self, v: f64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+struct ByteReader<'a>(&'a [u8]);
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < n {
+            return Err("save file is truncated".to_owned());
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_le_bytes(self.array()?))
+    }
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+    fn i64(&mut self) -> Result<i64, String> {
+        Ok(i64::from_le_bytes(self.array()?))
+    }
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+    fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.array()?))
+    }
+}
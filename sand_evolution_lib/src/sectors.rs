@@ -0,0 +1,324 @@
+//! Worlds larger than one grid.
+//!
+//! A `SectorWorld` cuts an unbounded world into equal sectors addressed by `SectorCoord`
+//! (x to the right, y up, like grid cells). Only the `(2 * radius + 1)²` sectors around the
+//! focus are simulated: they are stitched into the simulation's grid, so cells, heat and wind
+//! cross sector boundaries like anywhere else. Moving the focus scrolls the grid by whole
+//! sectors (see `Simulation::scroll`), parks the sectors that leave the window in the
+//! `SectorStore` and brings in those that enter it, from the store or the generator. The
+//! focus only moves when asked to (`SectorWorld::follow`, `focus_sector` in scripts); the app
+//! always shows the whole window.
+//!
+//! Parked sectors are frozen. They keep their cells along with the heat, wind, velocities and
+//! aux values over them (see `save_file::write_region`); blast pressure, charge and light
+//! start over when they come back.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+use crate::cells::{stone::Stone, void::Void, CellRegistry};
+use crate::cs;
+use crate::map_file;
+use crate::save_file;
+use crate::simulation::Simulation;
+
+pub type SectorCoord = (i32, i32);
+
+/// Fills a sector that has never been visited: `(coord, width, height)`.
+pub type SectorGenerator = fn(SectorCoord, u32, u32) -> image::GrayImage;
+
+/// A sector outside the window.
+#[derive(Clone)]
+pub struct ParkedSector {
+    /// Its cells as a map PNG (see `map_file`), so they survive palette changes like any
+    /// other map.
+    pub png: Vec<u8>,
+    /// The fields over those cells (see `save_file::write_region`); empty if the store has
+    /// none, in which case they start over.
+    pub fields: Vec<u8>,
+}
+
+/// Where sectors outside the window are kept.
+pub enum SectorStore {
+    Memory(HashMap<SectorCoord, ParkedSector>),
+    /// `sector_<x>_<y>.png` and `sector_<x>_<y>.fields` per sector, so the world outlives the
+    /// process.
+    Directory(PathBuf),
+}
+
+impl SectorStore {
+    fn path(dir: &std::path::Path, (x, y): SectorCoord, extension: &str) -> PathBuf {
+        dir.join(format!("sector_{x}_{y}.{extension}"))
+    }
+
+    fn load(&self, coord: SectorCoord) -> Result<Option<ParkedSector>, Box<dyn Error>> {
+        let read = |path: PathBuf| match std::fs::read(path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        };
+        match self {
+            SectorStore::Memory(sectors) => Ok(sectors.get(&coord).cloned()),
+            SectorStore::Directory(dir) => {
+                let Some(png) = read(Self::path(dir, coord, "png"))? else {
+                    return Ok(None);
+                };
+                let fields = read(Self::path(dir, coord, "fields"))?.unwrap_or_default();
+                Ok(Some(ParkedSector { png, fields }))
+            }
+        }
+    }
+
+    fn save(&mut self, coord: SectorCoord, sector: ParkedSector) -> Result<(), Box<dyn Error>> {
+        match self {
+            SectorStore::Memory(sectors) => {
+                sectors.insert(coord, sector);
+            }
+            SectorStore::Directory(dir) => {
+                std::fs::create_dir_all(&*dir)?;
+                std::fs::write(Self::path(dir, coord, "png"), sector.png)?;
+                std::fs::write(Self::path(dir, coord, "fields"), sector.fields)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Default generator: solid stone below sector row 0, a stone floor a quarter of a sector
+/// thick in row 0 and open air above.
+pub fn flat_ground((_, y): SectorCoord, width: u32, height: u32) -> image::GrayImage {
+    image::GrayImage::from_fn(width, height, |_, j| {
+        if y < 0 || (y == 0 && j < height / 4) {
+            image::Luma([Stone::id()])
+        } else {
+            image::Luma([Void::id()])
+        }
+    })
+}
+
+pub struct SectorWorld {
+    sector_width: u32,
+    sector_height: u32,
+    radius: i32,
+    focus: SectorCoord,
+    pub store: SectorStore,
+    pub generator: SectorGenerator,
+}
+
+impl SectorWorld {
+    /// Sectors of `sector_width` x `sector_height` cells, simulated `radius` sectors around
+    /// the focus. Both sides must be multiples of `cs::SECTOR_ALIGN` and the window has to be
+    /// a valid grid (see `cs::check_sector_size`).
+    pub fn new(
+        sector_width: u32,
+        sector_height: u32,
+        radius: u32,
+        store: SectorStore,
+    ) -> Result<Self, String> {
+        let align = cs::SECTOR_ALIGN as u32;
+        if !sector_width.is_multiple_of(align) || !sector_height.is_multiple_of(align) {
+            return Err(format!(
+                "sector size must be a multiple of {align}, got {sector_width}x{sector_height}"
+            ));
+        }
+        let span = 2 * radius as u64 + 1;
+        let (width, height) = (sector_width as u64 * span, sector_height as u64 * span);
+        cs::check_sector_size(
+            width.min(u32::MAX as u64) as u32,
+            height.min(u32::MAX as u64) as u32,
+        )?;
+        Ok(Self {
+            sector_width,
+            sector_height,
+            radius: radius as i32,
+            focus: (0, 0),
+            store,
+            generator: flat_ground,
+        })
+    }
+
+    pub fn focus(&self) -> SectorCoord {
+        self.focus
+    }
+
+    pub fn sector_size(&self) -> (u32, u32) {
+        (self.sector_width, self.sector_height)
+    }
+
+    /// World coordinates of grid cell (0, 0).
+    pub fn origin(&self) -> (i64, i64) {
+        (
+            (self.focus.0 - self.radius) as i64 * self.sector_width as i64,
+            (self.focus.1 - self.radius) as i64 * self.sector_height as i64,
+        )
+    }
+
+    /// Sector holding the world cell (x, y).
+    pub fn sector_at(&self, x: i64, y: i64) -> SectorCoord {
+        (
+            x.div_euclid(self.sector_width as i64) as i32,
+            y.div_euclid(self.sector_height as i64) as i32,
+        )
+    }
+
+    fn in_window(&self, focus: SectorCoord, (x, y): SectorCoord) -> bool {
+        (x - focus.0).abs() <= self.radius && (y - focus.1).abs() <= self.radius
+    }
+
+    /// Every sector of the window around `focus` with the grid position of its corner.
+    fn window(&self, focus: SectorCoord) -> Vec<(SectorCoord, u32, u32)> {
+        let span = -self.radius..=self.radius;
+        span.clone()
+            .flat_map(|dy| span.clone().map(move |dx| (dx, dy)))
+            .map(|(dx, dy)| {
+                (
+                    (focus.0 + dx, focus.1 + dy),
+                    (dx + self.radius) as u32 * self.sector_width,
+                    (dy + self.radius) as u32 * self.sector_height,
+                )
+            })
+            .collect()
+    }
+
+    /// The cells of sector `coord` and its parked fields, empty for a generated sector.
+    fn fetch(
+        &self,
+        coord: SectorCoord,
+        registry: &CellRegistry,
+    ) -> Result<(image::GrayImage, Vec<u8>), Box<dyn Error>> {
+        let Some(parked) = self.store.load(coord)? else {
+            let sector = (self.generator)(coord, self.sector_width, self.sector_height);
+            return Ok((sector, Vec::new()));
+        };
+        let sector = map_file::decode_map(&parked.png, registry)?.grid;
+        if sector.dimensions() != self.sector_size() {
+            return Err(format!(
+                "sector {coord:?} is {}x{}, expected {}x{}",
+                sector.width(),
+                sector.height(),
+                self.sector_width,
+                self.sector_height
+            )
+            .into());
+        }
+        Ok((sector, parked.fields))
+    }
+
+    /// Replaces the grid of `sim` with the window around `focus`.
+    pub fn attach(
+        &mut self,
+        sim: &mut Simulation,
+        focus: SectorCoord,
+    ) -> Result<(), Box<dyn Error>> {
+        let span = 2 * self.radius as u32 + 1;
+        let mut grid = image::GrayImage::new(self.sector_width * span, self.sector_height * span);
+        let mut fields = Vec::new();
+        for (coord, x, y) in self.window(focus) {
+            let (sector, parked) = self.fetch(coord, &sim.pal_container)?;
+            image::imageops::replace(&mut grid, &sector, x as i64, y as i64);
+            fields.push((x, y, parked));
+        }
+        sim.set_grid(grid)?;
+        self.restore_fields(sim, fields)?;
+        self.focus = focus;
+        Ok(())
+    }
+
+    /// Centres the window on `focus`: sectors leaving it are parked, sectors entering it are
+    /// loaded or generated, and everything in between keeps running where it was.
+    pub fn move_focus(
+        &mut self,
+        sim: &mut Simulation,
+        focus: SectorCoord,
+    ) -> Result<(), Box<dyn Error>> {
+        if focus == self.focus {
+            return Ok(());
+        }
+        let old_focus = self.focus;
+        let mut grid = image::GrayImage::new(sim.diffuse_rgba.width(), sim.diffuse_rgba.height());
+        let old_window = self.window(old_focus);
+        let mut fields = Vec::new();
+        for (coord, x, y) in self.window(focus) {
+            let kept = old_window.iter().find(|(old, _, _)| *old == coord);
+            let sector = match kept {
+                Some(&(_, old_x, old_y)) => self.cut(sim, old_x, old_y),
+                None => {
+                    let (sector, parked) = self.fetch(coord, &sim.pal_container)?;
+                    fields.push((x, y, parked));
+                    sector
+                }
+            };
+            image::imageops::replace(&mut grid, &sector, x as i64, y as i64);
+        }
+        for &(coord, x, y) in &old_window {
+            if !self.in_window(focus, coord) {
+                let parked = self.park(sim, x, y)?;
+                self.store.save(coord, parked)?;
+            }
+        }
+
+        let dx = (focus.0 - old_focus.0) * self.sector_width as i32;
+        let dy = (focus.1 - old_focus.1) * self.sector_height as i32;
+        sim.scroll(grid, dx, dy)?;
+        self.restore_fields(sim, fields)?;
+        self.focus = focus;
+        Ok(())
+    }
+
+    /// Moves the focus to the sector holding grid cell (i, j), if it isn't there already.
+    ///
+    /// Nothing calls this on its own: the app draws the whole window and has no camera to
+    /// track, so hosts call it with whatever they want to keep in the middle (the player,
+    /// the brush), and scripts use `focus_sector`.
+    pub fn follow(&mut self, sim: &mut Simulation, i: i64, j: i64) -> Result<(), Box<dyn Error>> {
+        let (x, y) = self.origin();
+        let focus = self.sector_at(x + i, y + j);
+        self.move_focus(sim, focus)
+    }
+
+    /// Writes every sector of the window to the store, e.g. before quitting.
+    pub fn park_all(&mut self, sim: &Simulation) -> Result<(), Box<dyn Error>> {
+        for (coord, x, y) in self.window(self.focus) {
+            let parked = self.park(sim, x, y)?;
+            self.store.save(coord, parked)?;
+        }
+        Ok(())
+    }
+
+    /// The sector whose corner is at grid cell (x, y), ready for the store.
+    fn park(&self, sim: &Simulation, x: u32, y: u32) -> Result<ParkedSector, Box<dyn Error>> {
+        Ok(ParkedSector {
+            png: map_file::encode_png(&self.cut(sim, x, y), &sim.pal_container)?,
+            fields: save_file::write_region(sim, x, y, self.sector_width, self.sector_height),
+        })
+    }
+
+    /// Puts the parked fields of sectors just brought in back over their cells.
+    fn restore_fields(
+        &self,
+        sim: &mut Simulation,
+        fields: Vec<(u32, u32, Vec<u8>)>,
+    ) -> Result<(), Box<dyn Error>> {
+        let (width, height) = self.sector_size();
+        for (x, y, parked) in fields {
+            if !parked.is_empty() {
+                save_file::read_region(sim, x, y, width, height, &parked)?;
+            }
+        }
+        // Chunks only wake up on their own when their cells change.
+        sim.chunk_sleep.wake_all();
+        Ok(())
+    }
+
+    fn cut(&self, sim: &Simulation, x: u32, y: u32) -> image::GrayImage {
+        image::imageops::crop_imm(
+            &sim.diffuse_rgba,
+            x,
+            y,
+            self.sector_width,
+            self.sector_height,
+        )
+        .to_image()
+    }
+}
@@ -317,7 +317,7 @@ var t_shadow_props: texture_2d<f32>;
 var s_shadow_props: sampler;
 
 fn shadow_props(id: u32) -> vec4<f32> {
-    // Texture is 256x1 (RGBA8Unorm). Channels are normalized to [0..1].
+    // Texture is 256x2 (RGBA8Unorm), row 0 holds shadow data. Channels are normalized to [0..1].
     // - rgb: shadow multiplier color (1.0 = no darkening)
     // - a:   shadow opacity/strength (0.0 = does not affect)
     return textureLoad(t_shadow_props, vec2<i32>(i32(id), 0), 0);
@@ -586,21 +586,15 @@ fn brick_heat_color_ramp(heat01: f32) -> vec3<f32> {
 fn fs_main(in: VertexOutput) -> FragmentOutput {
     let uv = in.uv;
 
-    // Temperature texture is lower-res (res/4).
+    // Temperature texture is res/4, or res when the simulation uses full-resolution temperature.
     // In Temperature-only AND Both modes we keep the "cell-accurate" point sampling
     // (blocky on purpose) so it matches the underlying temp map data.
     // In Normal we smooth it with manual bilinear interpolation
     // (works even if the texture is not filterable).
-    let temp_dims_i = vec2<i32>(
-        max(1, i32(settings.res_x / 4.0)),
-        max(1, i32(settings.res_y / 4.0)),
-    );
+    let temp_dims_i = max(vec2<i32>(1, 1), textureDimensions(t_temperature));
     let temp_dims_f = vec2<f32>(f32(temp_dims_i.x), f32(temp_dims_i.y));
 
-    let tex_coord_point = vec2<i32>(
-        i32(uv.x * settings.res_x / 4.0),
-        i32(uv.y * settings.res_y / 4.0),
-    );
+    let tex_coord_point = min(vec2<i32>(uv * temp_dims_f), temp_dims_i - vec2<i32>(1, 1));
     let temp_point = textureLoad(t_temperature, tex_coord_point, 0).r;
     let temp_smooth = sample_temperature_bilinear(uv, temp_dims_i, temp_dims_f);
 
@@ -1027,7 +1021,9 @@ col = vec4<f32>(rgb * intensity + spark_rgb, 1.0);
     }
     else
     {
-      col = vec4<f32>(0.0,1.0,0.0,1.0);
+      // Cells without a hand-written look (e.g. materials loaded from TOML): flat display color.
+      let base = textureLoad(t_shadow_props, vec2<i32>(i32(t), 1), 0);
+      col = vec4<f32>(base.rgb * (0.85 + 0.3 * noise_pixel), 1.0);
     }
 
     // Composite translucent fluids/gases over the wall (so bricks are visible through them).
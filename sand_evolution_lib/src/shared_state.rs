@@ -1,11 +1,21 @@
+use crate::ecs::components::RigidBody;
+
 #[derive(Clone)]
 pub struct SharedState {
     pub points: Vec<(cgmath::Point2<i32>, u8)>,
+    /// Rigid bodies queued by scripts as (name, x, y, body), spawned with the points.
+    pub bodies: Vec<(String, f32, f32, RigidBody)>,
+    /// Sector a script asked to move the window to (see `Simulation::focus_sector`).
+    pub focus: Option<(i32, i32)>,
 }
 
 impl SharedState {
     pub fn new() -> Self {
-        Self { points: vec![] }
+        Self {
+            points: vec![],
+            bodies: vec![],
+            focus: None,
+        }
     }
 
     pub fn set_pixel(&mut self, x: i32, y: i32, t: u8) {
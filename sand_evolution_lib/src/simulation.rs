@@ -0,0 +1,1636 @@
+use cgmath::num_traits::clamp;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use specs::WorldExt;
+
+use crate::cells::{
+    data_cell, electricity::Electricity, molten_base::MoltenBase, molten_salt::MoltenSalt,
+    stone::Stone, void::Void, wood::Wood, CellAux, CellFields, CellRegistry, CellType, Gravity,
+    Momentum, Prng,
+};
+use crate::chunk_sleep::ChunkSleep;
+use crate::cs::{self, PointType};
+use crate::ecs::components::{
+    Children, Name, Parent, Position, RigidBody, Rotation, Scale, Script, Velocity,
+};
+use crate::resources::rhai_resource::{RhaiResource, RhaiResourceStorage};
+use crate::sectors::{SectorCoord, SectorWorld};
+use crate::shared_state::SharedState;
+use crate::{map_file, rhai_lib, update};
+
+pub const TEMP_MIN: f32 = -100.0;
+pub const TEMP_MAX: f32 = 1000.0;
+
+/// Pressure is always kept on a 4x reduced grid; explosions are coarse anyway.
+pub const PRESSURE_CELL_SIZE: u32 = 4;
+
+/// Wind is kept on an 8x reduced grid; it only nudges gases and light powders.
+pub const WIND_CELL_SIZE: u32 = 8;
+
+/// Keeps `energy / heat_capacity` finite for materials that declare 0.
+pub(crate) const MIN_HEAT_CAPACITY: f32 = 0.05;
+/// Upper bound for `Simulation::set_threads`.
+pub const MAX_THREADS: usize = 64;
+
+struct ThermalProperties {
+    conductivity: Vec<f32>,
+    heat_capacity: Vec<f32>,
+    convection: Vec<f32>,
+}
+
+// Every consumer of the simulation seed gets its own stream, so e.g. a script calling
+// rand() more often does not shift the cell PRNG or the generated map.
+const SCRIPT_RNG_STREAM: u64 = 0xA076_1D64_78BD_642F;
+const MAP_RNG_STREAM: u64 = 0xE703_7ED1_A0B4_28DB;
+
+/// How finely `Simulation::cell_temperatures` samples the cell grid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TemperatureResolution {
+    /// One temperature per 4x4 cells. Cheap, but heat smears across everything nearby.
+    #[default]
+    Reduced,
+    /// One temperature per cell, for sharp gradients along thin conductors.
+    Full,
+}
+
+impl TemperatureResolution {
+    /// Width/height of one temperature cell in grid cells.
+    pub fn cell_size(self) -> u32 {
+        match self {
+            TemperatureResolution::Reduced => 4,
+            TemperatureResolution::Full => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DayNightCycle {
+    /// Length of a full day in simulation seconds.
+    pub day_length_seconds: f32,
+    /// Current time of day in [0, day_length_seconds).
+    pub time_of_day_seconds: f32,
+    /// Multiplier for how fast the cycle advances relative to simulation time.
+    pub speed: f32,
+    /// Pause only the day/night cycle (simulation can still run).
+    pub paused: bool,
+    /// Rotation offset so that time=0 matches the initial art direction.
+    pub sun_angle_offset_rad: f32,
+    /// Shadow strength sent to shader (0..1).
+    pub shadow_strength: f32,
+    /// Shadow length in raymarch steps (1..64).
+    pub shadow_length_steps: f32,
+    /// Controls how quickly shadow fades with distance (0 disables distance attenuation).
+    pub shadow_distance_falloff: f32,
+}
+
+impl DayNightCycle {
+    pub fn new(day_length_seconds: f32, initial_dir_xy: (f32, f32)) -> Self {
+        let (mut x, mut y) = initial_dir_xy;
+        let len2 = x * x + y * y;
+        if len2 > 1e-12 {
+            let inv = 1.0 / len2.sqrt();
+            x *= inv;
+            y *= inv;
+        } else {
+            x = -0.8;
+            y = 0.4;
+        }
+
+        Self {
+            day_length_seconds: day_length_seconds.max(0.1),
+            time_of_day_seconds: 0.0,
+            speed: 1.0,
+            // By default keep time-of-day fixed (can be toggled in UI).
+            paused: true,
+            sun_angle_offset_rad: y.atan2(x),
+            shadow_strength: 0.9,
+            // 30% shorter than previous default (26.0 * 0.7 ≈ 18.2).
+            shadow_length_steps: 18.2,
+            shadow_distance_falloff: 1.0,
+        }
+    }
+
+    /// Advances the cycle and returns new normalized light direction (x,y).
+    pub fn advance(&mut self, dt_sim_seconds: f32) -> (f32, f32) {
+        if self.paused {
+            return self.current_dir();
+        }
+
+        let dt = (dt_sim_seconds.max(0.0)) * self.speed.max(0.0);
+        self.time_of_day_seconds =
+            (self.time_of_day_seconds + dt).rem_euclid(self.day_length_seconds.max(0.1));
+        self.current_dir()
+    }
+
+    pub fn current_dir(&self) -> (f32, f32) {
+        let phase = (self.time_of_day_seconds / self.day_length_seconds.max(0.1)).clamp(0.0, 1.0);
+        let angle = phase * std::f32::consts::TAU + self.sun_angle_offset_rad;
+        (angle.cos(), angle.sin())
+    }
+}
+
+/// CPU-only simulation core: cell grid, temperature field, ECS world and all counters.
+///
+/// Nothing in here touches wgpu, so it can be stepped headless (tests, CI, servers).
+/// `state::State` only reads from it to upload textures and draw.
+pub struct Simulation {
+    pub diffuse_rgba: image::GrayImage,
+    /// Last imported map, used by "Restore".
+    pub loaded_rgba: image::GrayImage,
+    pub flip: cs::PointType,
+    pub flop: cs::PointType,
+    pub pal_container: CellRegistry,
+    /// Seed behind `prng`, `script_prng` and `generate_simple`; same map + script + seed
+    /// gives the same grid on every platform.
+    pub seed: u64,
+    pub prng: Prng,
+    /// Backs Rhai `rand()` while this simulation is being stepped.
+    pub script_prng: Prng,
+    /// When false, scripts and ECS systems are not run (cells are still simulated).
+    pub toggled: bool,
+    pub tick: i64,
+    pub frame: i64,
+    /// Simulation time in seconds (advances with ticks, not wall clock).
+    pub sim_time_seconds: f64,
+    pub day_night: DayNightCycle,
+    // Temperature system for each cell (degrees), reduced grid (4x smaller) unless
+    // `temperature_resolution` is `Full`.
+    // Stored as delta from global_temperature: effective_temp = cell_temperatures[idx] + global_temperature
+    pub cell_temperatures: Vec<f32>,
+    /// Change with `set_temperature_resolution`, which resamples `cell_temperatures`.
+    temperature_resolution: TemperatureResolution,
+    /// Global/base temperature (degrees).
+    pub global_temperature: f32,
+    /// Blast pressure on a `PRESSURE_CELL_SIZE` reduced grid, row-major like `cell_temperatures`.
+    /// Written through `add_pressure`, advanced by `step_pressure`.
+    pub cell_pressures: Vec<f32>,
+    /// Set by `add_pressure`, cleared once the field has decayed; lets `step_pressure` skip idle ticks.
+    pressure_active: bool,
+    /// Air flow in cells per update on a `WIND_CELL_SIZE` reduced grid, row-major like
+    /// `cell_pressures`. Driven by `step_wind`, fans and `add_wind`.
+    pub cell_wind: Vec<[f32; 2]>,
+    /// Per-cell velocity in cells per update, indexed like `diffuse_rgba`; empty while momentum
+    /// is off. Only cells with a `momentum()` read it, through `helper::momentum_helper`.
+    pub cell_velocities: Vec<[f32; 2]>,
+    /// Aux values cells keep between updates, indexed like `diffuse_rgba`; empty while the aux
+    /// buffer is off. Cells reach it through the `CellGrid` they are updated with.
+    pub cell_aux: Vec<CellAux>,
+    /// Electric charge per cell (0..=1), indexed like `diffuse_rgba`; rebuilt every tick by
+    /// `step_electricity` and empty until the first power source appears.
+    pub cell_charge: Vec<f32>,
+    /// Laser beam power crossing each cell this tick, indexed like `diffuse_rgba`; recast every
+    /// tick by `laser::cast_beams` and empty while there is no emitter.
+    pub cell_light: Vec<f32>,
+    /// Nominal tick rate; one tick advances `sim_time_seconds` by `1 / steps_per_second`.
+    pub steps_per_second: i32,
+    /// How many temperature diffusion passes run every second tick (clamped to 1..=48).
+    pub cell_diffusion_iterations: i32,
+    pub world: specs::World,
+    /// Cells queued by scripts and the brush, applied at the start of the next tick.
+    pub shared_state: Rc<RefCell<SharedState>>,
+    /// Set by `enable_sectors`: the grid is then a window onto a larger world.
+    pub sectors: Option<SectorWorld>,
+    /// Which chunks of the grid `update_tick` skips because nothing is going on in them.
+    pub chunk_sleep: ChunkSleep,
+    /// Threads the cell pass runs on, see `set_threads`.
+    threads: usize,
+    /// Workers for the cell pass while `threads` is above 1.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) cell_pool: Option<futures::executor::ThreadPool>,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        rhai_lib::clear_state_ptr(self);
+    }
+}
+
+pub(crate) fn set_object_script(world: &mut specs::World, object_name: &str, script: &str) -> bool {
+    use specs::Join;
+
+    let names = world.read_storage::<Name>();
+    let mut scripts = world.write_storage::<Script>();
+    let entities = world.entities();
+
+    let mut found = false;
+    for (entity, name_comp) in (&entities, &names).join() {
+        if name_comp.name == object_name {
+            if let Some(script_comp) = scripts.get_mut(entity) {
+                script_comp.script = script.to_owned();
+                script_comp.raw = true;
+                // If this is a one-shot script, allow it to run again after updating the code.
+                script_comp.has_run = false;
+                found = true;
+            }
+        }
+    }
+    found
+}
+
+/// Moves a row-major field `width` entries wide by `(dx, dy)` entries towards its origin,
+/// filling what comes in with `empty`.
+fn scroll_field<T: Copy>(field: &mut [T], width: usize, dx: i32, dy: i32, empty: T) {
+    if field.is_empty() {
+        return;
+    }
+    let height = field.len() / width;
+    let old = field.to_vec();
+    for y in 0..height {
+        for x in 0..width {
+            let (from_x, from_y) = (x as i64 + dx as i64, y as i64 + dy as i64);
+            field[y * width + x] =
+                if (0..width as i64).contains(&from_x) && (0..height as i64).contains(&from_y) {
+                    old[from_y as usize * width + from_x as usize]
+                } else {
+                    empty
+                };
+        }
+    }
+}
+
+/// Empty grid inside a two cell thick stone wall.
+fn bordered_grid(width: u32, height: u32) -> image::GrayImage {
+    image::GrayImage::from_fn(width, height, |x, y| {
+        if x > 1 && y > 1 && x < width - 2 && y < height - 2 {
+            image::Luma([0])
+        } else {
+            image::Luma([Stone::id()])
+        }
+    })
+}
+
+impl Simulation {
+    /// Simulation with a fresh OS-random seed (see `with_seed` for reproducible runs).
+    pub fn new() -> Self {
+        Self::with_seed(Self::random_seed())
+    }
+
+    pub fn random_seed() -> u64 {
+        let mut seed_bytes = [0u8; 8];
+        let _ = getrandom::getrandom(&mut seed_bytes);
+        u64::from_le_bytes(seed_bytes)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self::build(cs::DEFAULT_SECTOR_SIZE, seed)
+    }
+
+    /// Empty `width` x `height` simulation; see `cs::check_sector_size` for the sizes allowed.
+    pub fn with_grid_size(width: u32, height: u32, seed: u64) -> Result<Self, String> {
+        Ok(Self::build(cs::check_sector_size(width, height)?, seed))
+    }
+
+    fn build(size: cgmath::Point2<PointType>, seed: u64) -> Self {
+        cs::set_sector_size(size);
+        let diffuse_rgba = bordered_grid(size.x as u32, size.y as u32);
+
+        // Reduced grid (4x smaller) for optimization.
+        let temperature_resolution = TemperatureResolution::default();
+        let temp_scale = temperature_resolution.cell_size();
+        let temp_width = (size.x as u32 / temp_scale) as usize;
+        let temp_height = (size.y as u32 / temp_scale) as usize;
+
+        let mut world = specs::World::new();
+
+        // Register all components
+        world.register::<Name>();
+        world.register::<Script>();
+        world.register::<Position>();
+        world.register::<Velocity>();
+        world.register::<Rotation>();
+        world.register::<Scale>();
+        world.register::<Parent>();
+        world.register::<Children>();
+        world.register::<RigidBody>();
+        world.insert(RhaiResource::default());
+        world.insert(Gravity::default());
+
+        crate::init_hardcoded_entities(&mut world);
+
+        // Day/night defaults (also used to seed shader light direction).
+        // "Almost strictly down" in texel space.
+        // Note: in this project, positive Y in texel space corresponds to "up" on screen,
+        // so "down" is negative Y.
+        let initial_sun_dir = (0.03f32, -1.0f32);
+
+        Self {
+            loaded_rgba: diffuse_rgba.clone(),
+            diffuse_rgba,
+            flip: 0,
+            flop: 0,
+            pal_container: CellRegistry::new(),
+            seed,
+            prng: Prng::from_seed(seed),
+            script_prng: Prng::from_seed(seed ^ SCRIPT_RNG_STREAM),
+            toggled: true,
+            tick: 0,
+            frame: 0,
+            sim_time_seconds: 0.0,
+            day_night: DayNightCycle::new(120.0, initial_sun_dir),
+            // Temperatures stored as delta from global_temperature, so initialize to 0.0
+            cell_temperatures: vec![0.0; temp_width * temp_height],
+            temperature_resolution,
+            global_temperature: 21.0,
+            cell_pressures: vec![
+                0.0;
+                ((size.x as u32 / PRESSURE_CELL_SIZE) * (size.y as u32 / PRESSURE_CELL_SIZE))
+                    as usize
+            ],
+            pressure_active: false,
+            cell_wind: vec![
+                [0.0; 2];
+                ((size.x as u32 / WIND_CELL_SIZE) * (size.y as u32 / WIND_CELL_SIZE))
+                    as usize
+            ],
+            cell_velocities: Vec::new(),
+            cell_aux: Vec::new(),
+            cell_charge: Vec::new(),
+            cell_light: Vec::new(),
+            steps_per_second: 240,
+            cell_diffusion_iterations: 1,
+            world,
+            shared_state: Rc::new(RefCell::new(SharedState::new())),
+            sectors: None,
+            chunk_sleep: ChunkSleep::default(),
+            threads: 1,
+            #[cfg(not(target_arch = "wasm32"))]
+            cell_pool: None,
+        }
+    }
+
+    /// Builds the Rhai engine with the full script API and installs it into the ECS world,
+    /// so that `EntityScriptSystem` runs entity scripts during `step`.
+    pub fn init_scripting(&mut self, script_log: Rc<RefCell<VecDeque<String>>>) {
+        let mut id_dict: HashMap<String, u8> = HashMap::new();
+        for a in self.pal_container.pal.iter() {
+            if a.id() != 0 {
+                id_dict.insert(a.name().to_owned(), a.id());
+            }
+        }
+
+        let mut rhai = rhai::Engine::new();
+        let mut rhai_scope = rhai::Scope::new();
+
+        // Register functions
+        rhai_lib::register_rhai(
+            &mut rhai,
+            &mut rhai_scope,
+            self.shared_state.clone(),
+            id_dict,
+            None,
+            script_log.clone(),
+            None,
+        );
+
+        self.world.insert(RhaiResource {
+            storage: Some(RhaiResourceStorage {
+                engine: rhai,
+                scope: rhai_scope,
+                sim_ptr: std::cell::Cell::new(std::ptr::null_mut()),
+                script_log,
+            }),
+        });
+    }
+
+    /// Restarts the cell PRNG and script `rand()` from `seed`. Call `generate_simple`
+    /// afterwards to also get the map for that seed.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.prng = Prng::from_seed(seed);
+        self.script_prng = Prng::from_seed(seed ^ SCRIPT_RNG_STREAM);
+    }
+
+    /// Value for Rhai `rand()`, in 0..10000 like `random::my_rand`.
+    pub fn script_rand(&mut self) -> i64 {
+        (self.script_prng.next_u64() % 10_000) as i64
+    }
+
+    /// Replaces the grid with a luma8 PNG of cell ids (same format as "Export PNG"); the
+    /// simulation takes the size of the map.
+    /// Returns the names of cells the map uses that no longer exist (they are loaded as void).
+    pub fn load_map(&mut self, png: &[u8]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        let map = map_file::decode_map(png, &self.pal_container)?;
+        self.set_grid(map.grid)?;
+        Ok(map.unknown_cells)
+    }
+
+    pub fn grid_size(&self) -> cgmath::Point2<PointType> {
+        let (width, height) = self.diffuse_rgba.dimensions();
+        cgmath::Point2::new(width as PointType, height as PointType)
+    }
+
+    /// Replaces the grid with `grid`, which may be of another size: every per-cell field is
+    /// reallocated to match and starts out cleared. Fails, changing nothing, if
+    /// `cs::check_sector_size` rejects the size.
+    pub fn set_grid(&mut self, grid: image::GrayImage) -> Result<(), String> {
+        let size = cs::check_sector_size(grid.width(), grid.height())?;
+        if size != self.grid_size() {
+            cs::set_sector_size(size);
+            self.diffuse_rgba = grid;
+            let (temp_width, temp_height) = self.temperature_grid_size();
+            self.cell_temperatures = vec![0.0; temp_width * temp_height];
+            let (pressure_width, pressure_height) = self.pressure_grid_size();
+            self.cell_pressures = vec![0.0; pressure_width * pressure_height];
+            let (wind_width, wind_height) = self.wind_grid_size();
+            self.cell_wind = vec![[0.0; 2]; wind_width * wind_height];
+            if self.momentum_enabled() {
+                self.cell_velocities = vec![[0.0; 2]; self.diffuse_rgba.len()];
+            }
+            if self.aux_buffer_enabled() {
+                self.cell_aux = vec![CellAux::default(); self.diffuse_rgba.len()];
+            }
+            self.cell_charge.clear();
+            self.cell_light.clear();
+        } else {
+            self.diffuse_rgba = grid;
+        }
+        self.loaded_rgba = self.diffuse_rgba.clone();
+        self.reset_temperatures();
+        self.reset_pressure();
+        self.reset_wind();
+        self.reset_velocities();
+        self.reset_aux();
+        // The new grid doesn't hold the rigid bodies; they are stamped in again on the next step.
+        {
+            use specs::Join;
+            for body in (&mut self.world.write_storage::<RigidBody>()).join() {
+                body.stamped.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts over on an empty `width` x `height` grid walled in with stone.
+    pub fn resize_grid(&mut self, width: u32, height: u32) -> Result<(), String> {
+        self.set_grid(bordered_grid(width, height))
+    }
+
+    /// Replaces the grid with `grid`, the same world seen from `(dx, dy)` cells further right
+    /// and up. Heat, pressure, wind, velocities, aux values and entity positions move along
+    /// with the cells; whatever scrolls in starts out cleared, and rigid body cells scrolled out
+    /// are left behind. Both offsets must be multiples of `cs::SECTOR_ALIGN`.
+    pub fn scroll(&mut self, grid: image::GrayImage, dx: i32, dy: i32) -> Result<(), String> {
+        use specs::Join;
+
+        if grid.dimensions() != self.diffuse_rgba.dimensions() {
+            return Err(format!(
+                "scrolled grid is {}x{}, expected {}x{}",
+                grid.width(),
+                grid.height(),
+                self.diffuse_rgba.width(),
+                self.diffuse_rgba.height()
+            ));
+        }
+        let align = cs::SECTOR_ALIGN as i32;
+        if dx % align != 0 || dy % align != 0 {
+            return Err(format!(
+                "scroll must be a multiple of {align}, got {dx}, {dy}"
+            ));
+        }
+
+        let width = self.diffuse_rgba.width() as usize;
+        self.diffuse_rgba = grid;
+        self.loaded_rgba = self.diffuse_rgba.clone();
+
+        let scale = self.temperature_resolution.cell_size() as i32;
+        let (temp_width, _) = self.temperature_grid_size();
+        let temps = &mut self.cell_temperatures;
+        scroll_field(temps, temp_width, dx / scale, dy / scale, 0.0);
+        let scale = PRESSURE_CELL_SIZE as i32;
+        let (pressure_width, _) = self.pressure_grid_size();
+        let pressures = &mut self.cell_pressures;
+        scroll_field(pressures, pressure_width, dx / scale, dy / scale, 0.0);
+        let scale = WIND_CELL_SIZE as i32;
+        let (wind_width, _) = self.wind_grid_size();
+        let wind = &mut self.cell_wind;
+        scroll_field(wind, wind_width, dx / scale, dy / scale, [0.0; 2]);
+        scroll_field(&mut self.cell_velocities, width, dx, dy, [0.0; 2]);
+        scroll_field(&mut self.cell_aux, width, dx, dy, CellAux::default());
+        self.cell_charge.clear();
+        self.cell_light.clear();
+
+        for pos in (&mut self.world.write_storage::<Position>()).join() {
+            pos.x -= dx as f32;
+            pos.y -= dy as f32;
+        }
+        let height = self.diffuse_rgba.height() as i64;
+        for body in (&mut self.world.write_storage::<RigidBody>()).join() {
+            let mut stamped = Vec::with_capacity(body.stamped.len());
+            for (idx, local) in body.stamped.drain(..) {
+                let x = (idx % width) as i64 - dx as i64;
+                let y = (idx / width) as i64 - dy as i64;
+                if (0..width as i64).contains(&x) && (0..height).contains(&y) {
+                    stamped.push((y as usize * width + x as usize, local));
+                } else {
+                    body.cells[local] = Void::id();
+                }
+            }
+            body.stamped = stamped;
+        }
+        Ok(())
+    }
+
+    /// Turns the grid into a window onto `world`, centred on the sector `focus`.
+    pub fn enable_sectors(
+        &mut self,
+        mut world: SectorWorld,
+        focus: SectorCoord,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        world.attach(self, focus)?;
+        self.sectors = Some(world);
+        Ok(())
+    }
+
+    /// Moves the sector window (see `SectorWorld::move_focus`); does nothing without
+    /// `enable_sectors`.
+    pub fn focus_sector(&mut self, focus: SectorCoord) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(mut world) = self.sectors.take() else {
+            return Ok(());
+        };
+        let result = world.move_focus(self, focus);
+        self.sectors = Some(world);
+        result
+    }
+
+    /// Registers the materials of a TOML materials file (see `cells::data_cell`).
+    pub fn load_materials(&mut self, toml_text: &str) -> Result<Vec<CellType>, String> {
+        let ids = data_cell::register_materials(&mut self.pal_container, toml_text)?;
+        self.chunk_sleep.wake_all();
+
+        // Cell names for `set_cell` are captured when the engine is built, so rebuild it.
+        let script_log = self
+            .world
+            .read_resource::<RhaiResource>()
+            .storage
+            .as_ref()
+            .map(|storage| storage.script_log.clone());
+        if let Some(script_log) = script_log {
+            self.init_scripting(script_log);
+        }
+        Ok(ids)
+    }
+
+    /// Replaces the script of the named entity; it is recompiled on the next script run.
+    /// Returns false if no entity with a script has that name.
+    pub fn set_object_script(&mut self, object_name: &str, script: &str) -> bool {
+        set_object_script(&mut self.world, object_name, script)
+    }
+
+    /// Lets `chunk_sleep` look at what changed since the last tick; run before the cell pass.
+    pub fn track_chunk_activity(&mut self) {
+        let mut chunk_sleep = std::mem::take(&mut self.chunk_sleep);
+        chunk_sleep.track(self, self.tick);
+        self.chunk_sleep = chunk_sleep;
+    }
+
+    /// Advances the simulation by `n` ticks.
+    pub fn step(&mut self, n: i32) {
+        if n > 0 {
+            update::update_tick(self, n);
+        }
+    }
+
+    /// Returns the names of unknown cells in the imported map (see `map_file`).
+    pub(crate) fn update_with_data(&mut self, p0: &[u8]) -> Vec<String> {
+        if p0.is_empty() {
+            self.generate_simple();
+            Vec::new()
+        } else {
+            let map =
+                map_file::decode_map(p0, &self.pal_container).expect("Load from memory failed");
+            // Imported map should not inherit previous temperature field; `set_grid` clears it.
+            if let Err(err) = self.set_grid(map.grid) {
+                println!("Image not loaded: {err}");
+                return Vec::new();
+            }
+            println!("Some image loaded");
+            map.unknown_cells
+        }
+    }
+
+    /// Fills the grid with a random level derived from `seed`.
+    pub fn generate_simple(&mut self) {
+        let mut rng = Prng::from_seed(self.seed ^ MAP_RNG_STREAM);
+        let mut buf = [0u8; 4];
+        let (width, height) = self.diffuse_rgba.dimensions();
+        self.diffuse_rgba = image::GrayImage::from_fn(width, height, |x, y| {
+            if x > 1 && y > 1 && x < width - 2 && y < height - 2 {
+                buf[0] = rng.next();
+                buf[1] = rng.next();
+                return image::Luma([if buf[0] % 7 == 0 && y < height / 2 {
+                    buf[1] % 4
+                } else {
+                    0
+                }]);
+            } else {
+                return image::Luma([Stone::id()]);
+            }
+        });
+
+        // The patch counts below fill the default grid; smaller grids get proportionally fewer.
+        let default_area = cs::DEFAULT_SECTOR_SIZE.x as u32 * cs::DEFAULT_SECTOR_SIZE.y as u32;
+        let stride = (default_area / (width * height)).max(1);
+
+        for _ in 0..150 / stride {
+            buf = (rng.next_u64() as u32).to_le_bytes();
+
+            let nx = (((buf[0] as u32) << 8) | buf[1] as u32) % width;
+            let ny = (((buf[2] as u32) << 8) | buf[3] as u32) % height;
+
+            for x in 0..50 {
+                self.diffuse_rgba.put_pixel(
+                    clamp(nx + x, 0, width - 1),
+                    clamp(ny, 0, height - 1),
+                    image::Luma([Wood::id()]),
+                );
+            }
+        }
+
+        for _ in 0..100 / stride {
+            buf = (rng.next_u64() as u32).to_le_bytes();
+
+            let nx = (((buf[0] as u32) << 8) | buf[1] as u32) % width;
+            let ny = (((buf[2] as u32) << 8) | buf[3] as u32) % height;
+
+            for x in 0..20 {
+                for y in 0..20 {
+                    self.diffuse_rgba.put_pixel(
+                        clamp(nx + x, 0, width - 1),
+                        clamp(ny + y, 0, height - 1),
+                        image::Luma([Wood::id()]),
+                    );
+                }
+            }
+        }
+
+        let mut patches = 0;
+        for _ in 0..3 {
+            for cell in self.pal_container.pal.iter() {
+                let id = cell.id();
+                if id != 0 && id != MoltenSalt::id() && id != MoltenBase::id() {
+                    patches += 1;
+                    if patches % stride != 0 {
+                        continue;
+                    }
+                    buf = (rng.next_u64() as u32).to_le_bytes();
+
+                    let nx = (((buf[0] as u32) << 8) | buf[1] as u32) % width;
+                    let ny = (((buf[2] as u32) << 8) | buf[3] as u32) % height;
+
+                    for x in 0..35 {
+                        for y in 0..20 {
+                            self.diffuse_rgba.put_pixel(
+                                clamp(nx + x, 0, width - 1),
+                                clamp(ny + y, 0, height - 1),
+                                image::Luma([cell.id()]),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        // New random map should start from a clean temperature field.
+        self.reset_temperatures();
+        self.reset_pressure();
+        self.reset_wind();
+        self.reset_velocities();
+        self.reset_aux();
+    }
+
+    pub fn set_cell(&mut self, x: i32, y: i32, t: u8) {
+        self.diffuse_rgba
+            .put_pixel(x as u32, y as u32, image::Luma([t]));
+    }
+
+    pub fn get_cell(&self, x: i32, y: i32) -> u8 {
+        self.diffuse_rgba.get_pixel(x as u32, y as u32).0[0]
+    }
+
+    pub fn temperature_resolution(&self) -> TemperatureResolution {
+        self.temperature_resolution
+    }
+
+    /// Width and height of the temperature grid (`cell_temperatures` is row-major, bottom row first).
+    pub fn temperature_grid_size(&self) -> (usize, usize) {
+        let scale = self.temperature_resolution.cell_size();
+        (
+            (self.diffuse_rgba.width() / scale) as usize,
+            (self.diffuse_rgba.height() / scale) as usize,
+        )
+    }
+
+    /// Switches the temperature grid resolution, keeping the current heat: going to `Full`
+    /// copies each reduced cell into its 4x4 block, going back averages every block.
+    pub fn set_temperature_resolution(&mut self, resolution: TemperatureResolution) {
+        if resolution == self.temperature_resolution {
+            return;
+        }
+        let (old_width, _) = self.temperature_grid_size();
+        let old_scale = self.temperature_resolution.cell_size() as usize;
+        self.temperature_resolution = resolution;
+        let (width, height) = self.temperature_grid_size();
+        let scale = resolution.cell_size() as usize;
+
+        let mut temps = vec![0.0f32; width * height];
+        if scale < old_scale {
+            let ratio = old_scale / scale;
+            for (idx, t) in temps.iter_mut().enumerate() {
+                let (x, y) = (idx % width, idx / width);
+                *t = self.cell_temperatures[(y / ratio) * old_width + x / ratio];
+            }
+        } else {
+            let ratio = scale / old_scale;
+            let weight = 1.0 / (ratio * ratio) as f32;
+            for (idx, &t) in self.cell_temperatures.iter().enumerate() {
+                let (x, y) = (idx % old_width / ratio, idx / old_width / ratio);
+                if x < width && y < height {
+                    temps[y * width + x] += t * weight;
+                }
+            }
+        }
+        self.cell_temperatures = temps;
+    }
+
+    // Convert full grid coordinates to a temperature grid index
+    fn temp_coords_to_index(&self, i: PointType, j: PointType) -> usize {
+        let scale = self.temperature_resolution.cell_size() as PointType;
+        let temp_x = (i / scale) as usize;
+        let temp_y = (j / scale) as usize;
+        let (temp_width, _) = self.temperature_grid_size();
+        temp_y * temp_width + temp_x
+    }
+
+    // Get cell temperature by index (in temperature grid)
+    // Returns effective temperature: stored_delta + global_temperature
+    pub fn get_cell_temperature(&self, index: usize) -> f32 {
+        if index < self.cell_temperatures.len() {
+            (self.cell_temperatures[index] + self.global_temperature)
+                .max(TEMP_MIN)
+                .min(TEMP_MAX)
+        } else {
+            0.0
+        }
+    }
+
+    // Get cell temperature by coordinates (full grid)
+    pub fn get_temperature(&self, i: PointType, j: PointType) -> f32 {
+        let idx = self.temp_coords_to_index(i, j);
+        self.get_cell_temperature(idx)
+    }
+
+
+    // Set cell temperature by index (in temperature grid)
+    // Stores as delta from global_temperature: stored = temp - global_temperature
+    pub fn set_cell_temperature(&mut self, index: usize, temp: f32) {
+        if index < self.cell_temperatures.len() {
+            let clamped = temp.max(TEMP_MIN).min(TEMP_MAX);
+            self.cell_temperatures[index] = clamped - self.global_temperature;
+        }
+    }
+
+    // Set cell temperature by coordinates (full grid)
+    pub fn set_temperature(&mut self, i: PointType, j: PointType, temp: f32) {
+        let idx = self.temp_coords_to_index(i, j);
+        self.set_cell_temperature(idx, temp);
+    }
+
+    // Add temperature to cell (for heat generation)
+    pub fn add_temperature(&mut self, i: PointType, j: PointType, delta: f32) {
+        let idx = self.temp_coords_to_index(i, j);
+        if idx < self.cell_temperatures.len() {
+            self.cell_temperatures[idx] += delta;
+            // Clamp effective temperature to reasonable limits
+            let eff = self.cell_temperatures[idx] + self.global_temperature;
+            let clamped = eff.max(TEMP_MIN).min(TEMP_MAX);
+            self.cell_temperatures[idx] = clamped - self.global_temperature;
+        }
+    }
+
+    // Add temperature to cell by index (in temperature grid)
+    pub fn add_cell_temperature(&mut self, index: usize, delta: f32) {
+        if index < self.cell_temperatures.len() {
+            self.cell_temperatures[index] += delta;
+            // Clamp effective temperature to reasonable limits
+            let eff = self.cell_temperatures[index] + self.global_temperature;
+            let clamped = eff.max(TEMP_MIN).min(TEMP_MAX);
+            self.cell_temperatures[index] = clamped - self.global_temperature;
+        }
+    }
+
+    /// Reset per-cell temperature field (local delta) to zero.
+    /// Note: global temperature offset is preserved.
+    pub fn reset_temperatures(&mut self) {
+        self.cell_temperatures.as_mut_slice().fill(0.0);
+    }
+
+    /// Slow exchange: part of excess heat from ambient is transferred to cell "mass" (historical workaround).
+    pub fn absorb_ambient_heat(&mut self) {
+    }
+
+    /// Per temperature cell: mean `thermal_conductivity`, `heat_capacity` and `convection_factor`
+    /// of the grid cells it covers (a 4x4 block on the reduced grid, a single cell at full resolution).
+    fn thermal_properties(&self) -> ThermalProperties {
+        let mut cell_props = [[0.0f32; 3]; 256];
+        for (id, cell) in self.pal_container.pal.iter().enumerate().take(256) {
+            cell_props[id] = [
+                cell.thermal_conductivity().max(0.0),
+                cell.heat_capacity().max(MIN_HEAT_CAPACITY),
+                cell.convection_factor().max(0.0),
+            ];
+        }
+
+        let (width, height) = self.temperature_grid_size();
+        let scale = self.temperature_resolution.cell_size() as usize;
+        let mut props = ThermalProperties {
+            conductivity: vec![0.0; width * height],
+            heat_capacity: vec![0.0; width * height],
+            convection: vec![0.0; width * height],
+        };
+        let grid_width = self.diffuse_rgba.width() as usize;
+        for (i, &id) in self.diffuse_rgba.as_raw().iter().enumerate() {
+            let (x, y) = (i % grid_width / scale, i / grid_width / scale);
+            if x < width && y < height {
+                let [k, c, v] = cell_props[id as usize];
+                props.conductivity[y * width + x] += k;
+                props.heat_capacity[y * width + x] += c;
+                props.convection[y * width + x] += v;
+            }
+        }
+        if scale > 1 {
+            let inv = 1.0 / (scale * scale) as f32;
+            for map in [
+                &mut props.conductivity,
+                &mut props.heat_capacity,
+                &mut props.convection,
+            ] {
+                map.iter_mut().for_each(|v| *v *= inv);
+            }
+        }
+        props
+    }
+
+    /// Runs `iterations` passes of `diffuse_temperature_fast`. Materials are sampled once,
+    /// cells don't move between passes.
+    pub fn diffuse_temperature(&mut self, iterations: usize) {
+        let props = self.thermal_properties();
+        for _ in 0..iterations {
+            self.diffuse_temperature_fast(&props);
+        }
+    }
+
+    /// Heat capacity of the temperature cell containing (i, j): the sum over the cells it covers.
+    fn temperature_cell_heat_capacity(&self, i: PointType, j: PointType) -> f32 {
+        let scale = self.temperature_resolution.cell_size();
+        let (x0, y0) = (i as u32 / scale * scale, j as u32 / scale * scale);
+        let mut capacity = 0.0;
+        for y in y0..(y0 + scale).min(self.diffuse_rgba.height()) {
+            for x in x0..(x0 + scale).min(self.diffuse_rgba.width()) {
+                let id = self.diffuse_rgba.get_pixel(x, y).0[0] as usize;
+                capacity += self.pal_container.pal[id].heat_capacity().max(MIN_HEAT_CAPACITY);
+            }
+        }
+        capacity.max(MIN_HEAT_CAPACITY)
+    }
+
+    /// Adds `energy` (degrees times `CellTrait::heat_capacity`) at (i, j); negative takes it away.
+    /// Unlike `add_temperature`, the same energy warms water less than air.
+    pub fn add_heat(&mut self, i: PointType, j: PointType, energy: f32) {
+        let capacity = self.temperature_cell_heat_capacity(i, j);
+        self.add_temperature(i, j, energy / capacity);
+    }
+
+    /// Fast diffusion for the **ambient** (low-frequency) temperature field.
+    ///
+    /// Heat flows between neighbours in proportion to the harmonic mean of their conductivities,
+    /// so a copper wire carries heat along itself while the air around it barely does, and
+    /// warms each side in inverse proportion to its heat capacity.
+    /// Positive heat rises in proportion to `convection`.
+
+    // Fast temperature diffusion - processes all cells of the temperature grid each frame
+    fn diffuse_temperature_fast(&mut self, props: &ThermalProperties) {
+        let ThermalProperties {
+            conductivity,
+            heat_capacity,
+            convection,
+        } = props;
+        // Flow per unit of conductivity; void (0.3) lands close to the old material-blind 0.10,
+        // which was tuned to avoid rapid global heat "flooding" from local sources (fire/wood).
+        let diffusion_rate = 0.25;
+        // Slower cooling so heat persists longer.
+        let cooling_rate = 0.998;
+        // Weak buoyancy: a small fraction of *positive* heat drifts upward each step.
+        // (Reduced grid, so keep it small to avoid visible "staircase" artifacts.)
+        // 2x faster upward drift.
+        let rise_rate = 0.016;
+
+        // Series conduction between two cells, clamped so a pass stays stable
+        // (a cell can't give away more heat than it has above its neighbour).
+        let edge_rate = |a: usize, b: usize| {
+            let (ka, kb) = (conductivity[a], conductivity[b]);
+            if ka + kb > 0.0 {
+                (diffusion_rate * 2.0 * ka * kb / (ka + kb))
+                    .min(1.0)
+                    .min(heat_capacity[a])
+                    .min(heat_capacity[b])
+            } else {
+                0.0
+            }
+        };
+
+        // Work directly with the temperature grid
+        let (width, height) = self.temperature_grid_size();
+
+        // Temporary buffer for new local temperatures (delta from global_temperature).
+        let mut new_temps = vec![0.0f32; width * height];
+
+        // Process all cells of the temperature grid
+        for ty in 1..(height - 1) {
+            for tx in 1..(width - 1) {
+                let idx = ty * width + tx;
+                let current = self.cell_temperatures[idx];
+
+                // Neighbouring cells in the temperature grid
+                let top_idx = (ty + 1) * width + tx;
+                let bot_idx = (ty - 1) * width + tx;
+                let left_idx = ty * width + (tx - 1);
+                let right_idx = ty * width + (tx + 1);
+
+                let mut flow = 0.0;
+                for n in [top_idx, bot_idx, left_idx, right_idx] {
+                    flow += edge_rate(idx, n) * (self.cell_temperatures[n] - current);
+                }
+                let diffused = current + flow / (4.0 * heat_capacity[idx]);
+                // Temperatures are stored as local delta.
+                new_temps[idx] = diffused * cooling_rate;
+            }
+        }
+
+        // Weak upward drift: move a small fraction of positive heat into the cell above.
+        // Note: in this grid, "top" is (ty + 1).
+        if rise_rate > 0.0 {
+            for ty in 1..(height - 2) {
+                for tx in 1..(width - 1) {
+                    let idx = ty * width + tx;
+                    let top_idx = (ty + 1) * width + tx;
+                    let v = new_temps[idx];
+                    if v > 0.0 {
+                        let amount = v * (rise_rate * convection[idx]).min(1.0);
+                        new_temps[idx] -= amount;
+                        new_temps[top_idx] += amount;
+                    }
+                }
+            }
+        }
+
+        // Apply new temperatures with clamping in effective (global + local) domain.
+        for ty in 1..(height - 1) {
+            for tx in 1..(width - 1) {
+                let idx = ty * width + tx;
+                let eff = new_temps[idx] + self.global_temperature;
+                let clamped = eff.max(TEMP_MIN).min(TEMP_MAX);
+                self.cell_temperatures[idx] = clamped - self.global_temperature;
+            }
+        }
+    }
+
+    fn pressure_grid_size(&self) -> (usize, usize) {
+        (
+            (self.diffuse_rgba.width() / PRESSURE_CELL_SIZE) as usize,
+            (self.diffuse_rgba.height() / PRESSURE_CELL_SIZE) as usize,
+        )
+    }
+
+    /// `None` outside the pressure grid, which also leaves out a partial pressure cell at the
+    /// right and top edges of a grid that isn't a multiple of `PRESSURE_CELL_SIZE`.
+    fn pressure_index(&self, i: PointType, j: PointType) -> Option<usize> {
+        let (width, height) = self.pressure_grid_size();
+        let scale = PRESSURE_CELL_SIZE as usize;
+        let (x, y) = (i as usize / scale, j as usize / scale);
+        (x < width && y < height).then_some(y * width + x)
+    }
+
+    pub fn get_pressure(&self, i: PointType, j: PointType) -> f32 {
+        let idx = self.pressure_index(i, j);
+        idx.and_then(|idx| self.cell_pressures.get(idx)).copied().unwrap_or(0.0)
+    }
+
+    /// Injects a pressure impulse at (i, j); `step_pressure` spreads it out as a shock wave
+    /// that throws loose cells away from the source and breaks weak static ones.
+    pub fn add_pressure(&mut self, i: PointType, j: PointType, amount: f32) {
+        let Some(idx) = self.pressure_index(i, j) else {
+            return;
+        };
+        if let Some(p) = self.cell_pressures.get_mut(idx) {
+            *p = (*p + amount).max(0.0);
+            self.pressure_active = true;
+        }
+    }
+
+    pub fn reset_pressure(&mut self) {
+        self.cell_pressures.fill(0.0);
+        self.pressure_active = false;
+    }
+
+    /// Replaces the whole field (used by `save_file`); the length must match `cell_pressures`.
+    pub(crate) fn set_pressures(&mut self, pressures: Vec<f32>) {
+        self.pressure_active = pressures.iter().any(|&p| p > 0.0);
+        self.cell_pressures = pressures;
+    }
+
+    fn wind_grid_size(&self) -> (usize, usize) {
+        (
+            (self.diffuse_rgba.width() / WIND_CELL_SIZE) as usize,
+            (self.diffuse_rgba.height() / WIND_CELL_SIZE) as usize,
+        )
+    }
+
+    fn wind_index(&self, i: PointType, j: PointType) -> usize {
+        let (width, _) = self.wind_grid_size();
+        let scale = WIND_CELL_SIZE as PointType;
+        (j / scale) as usize * width + (i / scale) as usize
+    }
+
+    pub fn get_wind(&self, i: PointType, j: PointType) -> [f32; 2] {
+        let idx = self.wind_index(i, j);
+        self.cell_wind.get(idx).copied().unwrap_or([0.0; 2])
+    }
+
+    /// Blows at (i, j); `step_wind` spreads the gust and lets it die down.
+    pub fn add_wind(&mut self, i: PointType, j: PointType, wind: [f32; 2]) {
+        let idx = self.wind_index(i, j);
+        if let Some(w) = self.cell_wind.get_mut(idx) {
+            *w = [w[0] + wind[0], w[1] + wind[1]];
+        }
+    }
+
+    pub fn reset_wind(&mut self) {
+        self.cell_wind.fill([0.0; 2]);
+    }
+
+    pub fn gravity(&self) -> Gravity {
+        self.pal_container.gravity
+    }
+
+    /// Turns the world: cell helpers read it from `pal_container`, `GravitySystem` from the
+    /// ECS world.
+    pub fn set_gravity(&mut self, gravity: Gravity) {
+        self.pal_container.gravity = gravity;
+        self.world.insert(gravity);
+        self.chunk_sleep.wake_all();
+    }
+
+    pub fn liquid_levelling(&self) -> bool {
+        self.pal_container.liquid_levelling
+    }
+
+    /// Off, liquids only fall and spread sideways, so vessels joined below the surface
+    /// don't even out.
+    pub fn set_liquid_levelling(&mut self, enabled: bool) {
+        self.pal_container.liquid_levelling = enabled;
+        self.chunk_sleep.wake_all();
+    }
+
+    pub fn momentum_enabled(&self) -> bool {
+        !self.cell_velocities.is_empty()
+    }
+
+    /// Allocates (or drops) the velocity buffer. With momentum off, powders and liquids
+    /// move one cell per update as before.
+    pub fn set_momentum(&mut self, enabled: bool) {
+        if enabled == self.momentum_enabled() {
+            return;
+        }
+        self.cell_velocities = if enabled {
+            vec![[0.0; 2]; self.diffuse_rgba.len()]
+        } else {
+            Vec::new()
+        };
+        self.chunk_sleep.wake_all();
+    }
+
+    pub fn aux_buffer_enabled(&self) -> bool {
+        !self.cell_aux.is_empty()
+    }
+
+    /// Allocates (or drops) the aux buffer. Cells that use it, like burning wood counting down
+    /// its fuel, fall back to rolling dice while it is off.
+    pub fn set_aux_buffer(&mut self, enabled: bool) {
+        if enabled == self.aux_buffer_enabled() {
+            return;
+        }
+        self.cell_aux = if enabled {
+            vec![CellAux::default(); self.diffuse_rgba.len()]
+        } else {
+            Vec::new()
+        };
+        self.chunk_sleep.wake_all();
+    }
+
+    /// Raw pointers to the grid and fields for one cell pass, see `CellFields`. Nothing may
+    /// reach those buffers through `self` until the pass is over.
+    pub(crate) fn cell_fields(&mut self) -> CellFields {
+        let (grid_width, grid_height) = self.diffuse_rgba.dimensions();
+        let (temp_width, _) = self.temperature_grid_size();
+        let (wind_width, _) = self.wind_grid_size();
+        let (pressure_width, _) = self.pressure_grid_size();
+        CellFields {
+            grid: self.diffuse_rgba.as_mut_ptr(),
+            grid_width: grid_width as usize,
+            grid_height: grid_height as usize,
+            aux: self.cell_aux.as_mut_ptr(),
+            aux_len: self.cell_aux.len(),
+            velocities: self.cell_velocities.as_mut_ptr(),
+            velocities_len: self.cell_velocities.len(),
+            temps: self.cell_temperatures.as_mut_ptr(),
+            temps_len: self.cell_temperatures.len(),
+            temp_width,
+            temp_scale: self.temperature_resolution.cell_size() as usize,
+            global_temperature: self.global_temperature,
+            heat_capacity: std::array::from_fn(|id| {
+                self.pal_container.pal[id].heat_capacity().max(MIN_HEAT_CAPACITY)
+            }),
+            charge: self.cell_charge.as_mut_ptr(),
+            charge_len: self.cell_charge.len(),
+            wind: self.cell_wind.as_mut_ptr(),
+            wind_len: self.cell_wind.len(),
+            wind_width,
+            pressures: self.cell_pressures.as_mut_ptr(),
+            pressures_len: self.cell_pressures.len(),
+            pressure_width,
+        }
+    }
+
+    /// Takes in what the cell pass did besides writing the fields.
+    pub(crate) fn finish_cell_pass(&mut self, pressure_added: bool) {
+        self.pressure_active |= pressure_added;
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    /// Runs the cell pass of every tick on `threads` worker threads, 1 keeping it on the
+    /// calling thread. A parallel run differs from a single-threaded one, since every chunk
+    /// draws from its own random stream, but is the same for any number of threads. Web
+    /// builds stay on one thread.
+    pub fn set_threads(&mut self, threads: usize) -> Result<(), String> {
+        let threads = threads.clamp(1, MAX_THREADS);
+        #[cfg(target_arch = "wasm32")]
+        let threads = threads.min(1);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.cell_pool = if threads > 1 {
+                let pool = futures::executor::ThreadPool::builder()
+                    .pool_size(threads)
+                    .name_prefix("cells-")
+                    .create()
+                    .map_err(|err| format!("cannot start {threads} threads: {err}"))?;
+                Some(pool)
+            } else {
+                None
+            };
+        }
+        self.threads = threads;
+        Ok(())
+    }
+
+    fn cell_index(&self, i: PointType, j: PointType) -> Option<usize> {
+        let (width, height) = self.diffuse_rgba.dimensions();
+        let (x, y) = (i as usize, j as usize);
+        (x < width as usize && y < height as usize).then_some(y * width as usize + x)
+    }
+
+    pub fn get_velocity(&self, i: PointType, j: PointType) -> [f32; 2] {
+        let idx = self.cell_index(i, j);
+        idx.and_then(|idx| self.cell_velocities.get(idx))
+            .copied()
+            .unwrap_or([0.0; 2])
+    }
+
+    /// Sets the velocity of the cell at (i, j); ignored while momentum is off.
+    pub fn set_velocity(&mut self, i: PointType, j: PointType, velocity: [f32; 2]) {
+        let idx = self.cell_index(i, j);
+        if let Some(v) = idx.and_then(|idx| self.cell_velocities.get_mut(idx)) {
+            *v = velocity;
+        }
+    }
+
+    pub fn reset_velocities(&mut self) {
+        self.cell_velocities.fill([0.0; 2]);
+    }
+
+    /// Aux value of the cell at (i, j), 0 while the buffer is off or if it was written by
+    /// another cell type (see `CellGrid::aux`).
+    pub fn get_aux(&self, i: PointType, j: PointType) -> u16 {
+        let Some(idx) = self.cell_index(i, j) else {
+            return 0;
+        };
+        match (self.cell_aux.get(idx), self.diffuse_rgba.as_raw().get(idx)) {
+            (Some(aux), Some(&cell)) if aux.owner == cell => aux.value,
+            _ => 0,
+        }
+    }
+
+    /// Sets the aux value of the cell at (i, j) for its current type; ignored while the buffer
+    /// is off.
+    pub fn set_aux(&mut self, i: PointType, j: PointType, value: u16) {
+        let Some(idx) = self.cell_index(i, j) else {
+            return;
+        };
+        let owner = self.diffuse_rgba.get(idx).copied();
+        if let (Some(aux), Some(owner)) = (self.cell_aux.get_mut(idx), owner) {
+            *aux = CellAux { owner, value };
+        }
+    }
+
+    pub fn reset_aux(&mut self) {
+        self.cell_aux.fill(CellAux::default());
+    }
+
+    pub fn get_charge(&self, i: PointType, j: PointType) -> f32 {
+        let idx = self.cell_index(i, j);
+        idx.and_then(|idx| self.cell_charge.get(idx))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn get_light(&self, i: PointType, j: PointType) -> f32 {
+        let idx = self.cell_index(i, j);
+        idx.and_then(|idx| self.cell_light.get(idx))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Adds a rigid body entity centred on (x, y); it is stamped into the grid on the next tick.
+    pub fn add_rigid_body(&mut self, name: &str, x: f32, y: f32, body: RigidBody) -> specs::Entity {
+        crate::ecs::rigid_body::spawn(&mut self.world, name.to_owned(), x, y, body)
+    }
+
+    /// One tick of every rigid body: fall, collide, push loose cells aside and tip over.
+    pub fn step_rigid_bodies(&mut self) {
+        crate::ecs::rigid_body::step(&self.world, self.diffuse_rgba.as_mut(), &self.pal_container);
+    }
+
+    /// One tick of the electric circuit: charge flows out of every `electric_source()` cell
+    /// through connected conductors, losing `1 - electrical_conductivity()` per cell.
+    /// The lost share heats the conductor, and charged wire tips throw hot sparks that
+    /// ignite burnables they hit.
+    pub fn step_electricity(&mut self) {
+        // Below this a conductor counts as unpowered.
+        const MIN_CHARGE: f32 = 0.02;
+        // Heat released per tick by a fully charged cell that loses all of its charge.
+        const RESISTIVE_HEAT: f32 = 8.0;
+        // Per-tick chance out of 255 that a fully charged tip sparks.
+        const SPARK_CHANCE: f32 = 48.0;
+        // Temperature kick where a spark lands; a few in a row light gas.
+        const SPARK_TEMPERATURE: f32 = 80.0;
+
+        let width = self.diffuse_rgba.width() as usize;
+        let mut conductivity = [0.0f32; 256];
+        let mut source = [false; 256];
+        let mut output = [None; 256];
+        let mut terminal = [false; 256];
+        for (id, cell) in self.pal_container.pal.iter().enumerate() {
+            conductivity[id] = cell.electrical_conductivity();
+            source[id] = cell.electric_source();
+            output[id] = cell
+                .electric_output()
+                .map(|(dx, dy)| dx as isize + dy as isize * width as isize);
+            terminal[id] = cell.electric_terminal();
+        }
+
+        let container: &[u8] = self.diffuse_rgba.as_raw();
+        let height = self.diffuse_rgba.height() as usize;
+        let inside = |idx: usize| {
+            let (x, y) = (idx % width, idx / width);
+            x >= 1 && y >= 1 && x < width - 1 && y < height - 1
+        };
+        let mut queue: Vec<usize> = container
+            .iter()
+            .enumerate()
+            .filter(|&(idx, &id)| source[id as usize] && inside(idx))
+            .map(|(idx, _)| idx)
+            .collect();
+        if queue.is_empty() {
+            self.cell_charge.clear();
+            return;
+        }
+        if self.cell_charge.len() == container.len() {
+            self.cell_charge.fill(0.0);
+        } else {
+            self.cell_charge = vec![0.0; container.len()];
+        }
+        for &idx in &queue {
+            self.cell_charge[idx] = 1.0;
+        }
+
+        // Flood out from the sources; a cell is revisited when a better path reaches it.
+        let mut head = 0;
+        while head < queue.len() {
+            let idx = queue[head];
+            head += 1;
+            let charge = self.cell_charge[idx];
+            let id = container[idx] as usize;
+            let only = output[id]
+                .filter(|_| source[id])
+                .map(|offset| idx.wrapping_add_signed(offset));
+            for next in [idx - 1, idx + 1, idx - width, idx + width] {
+                if only.is_some_and(|only| only != next) {
+                    continue;
+                }
+                let passed = charge * conductivity[container[next] as usize];
+                if passed >= MIN_CHARGE && passed > self.cell_charge[next] + 1e-4 && inside(next) {
+                    self.cell_charge[next] = passed;
+                    queue.push(next);
+                }
+            }
+        }
+        queue.sort_unstable();
+        queue.dedup();
+
+        let mut effects = Vec::new();
+        for &idx in &queue {
+            let id = container[idx] as usize;
+            let charge = self.cell_charge[idx];
+            let (x, y) = ((idx % width) as PointType, (idx / width) as PointType);
+            if source[id] {
+                continue;
+            }
+            let loss = 1.0 - conductivity[id];
+            if loss > 0.0 {
+                effects.push((x, y, charge * loss * RESISTIVE_HEAT, None));
+            }
+            // A tip has a single wired neighbour; the charge jumps off it into the air.
+            let neighbours = [idx - 1, idx + 1, idx - width, idx + width];
+            let conducting = neighbours
+                .iter()
+                .filter(|&&n| terminal[container[n] as usize])
+                .count();
+            if conducting == 1 && (self.prng.next() as f32) < charge * SPARK_CHANCE {
+                // Into one of the three open sides, never back along the wire.
+                let pick = self.prng.next() as usize % 3;
+                let mut open = neighbours
+                    .iter()
+                    .filter(|&&n| !terminal[container[n] as usize]);
+                effects.push((x, y, 0.0, open.nth(pick).copied()));
+            }
+        }
+
+        for (x, y, heat, target) in effects {
+            if heat > 0.0 {
+                self.add_heat(x, y, heat);
+            }
+            let Some(target) = target else { continue };
+            let (tx, ty) = ((target % width) as PointType, (target / width) as PointType);
+            self.add_temperature(tx, ty, SPARK_TEMPERATURE);
+            let hit = self.diffuse_rgba.as_raw()[target];
+            let into = if hit == Void::id() {
+                Electricity::id()
+            } else {
+                self.pal_container.pal[hit as usize].burnable()
+            };
+            if into != Void::id() {
+                self.diffuse_rgba.as_mut()[target] = into;
+            }
+        }
+    }
+
+    /// One tick of the pressure field: spread and decay, then push cells down the gradient.
+    /// Does nothing while no explosion is in progress.
+    pub fn step_pressure(&mut self) {
+        if !self.pressure_active {
+            return;
+        }
+        const DECAY: f32 = 0.8;
+        // Below this the field is dropped entirely.
+        const PRESSURE_EPSILON: f32 = 0.05;
+        // Pressure that starts moving loose cells.
+        const MIN_PUSH: f32 = 2.0;
+        // Pressure per cell of throw distance, and the longest throw in one tick.
+        const PUSH_SCALE: f32 = 6.0;
+        const MAX_THROW: usize = 8;
+        // How far a buried cell looks for room to be pushed into.
+        const MAX_PUSH_CHAIN: i32 = 48;
+
+        let (width, height) = self.pressure_grid_size();
+        let mut new_pressures = vec![0.0f32; width * height];
+        let mut max_pressure = 0.0f32;
+        for ty in 1..(height - 1) {
+            for tx in 1..(width - 1) {
+                let idx = ty * width + tx;
+                let sum = self.cell_pressures[idx]
+                    + self.cell_pressures[idx - 1]
+                    + self.cell_pressures[idx + 1]
+                    + self.cell_pressures[idx - width]
+                    + self.cell_pressures[idx + width];
+                let p = sum / 5.0 * DECAY;
+                new_pressures[idx] = p;
+                max_pressure = max_pressure.max(p);
+            }
+        }
+        if max_pressure < PRESSURE_EPSILON {
+            self.cell_pressures.fill(0.0);
+            self.pressure_active = false;
+            return;
+        }
+        self.cell_pressures = new_pressures;
+
+        let scale = PRESSURE_CELL_SIZE as usize;
+        let grid_width = self.diffuse_rgba.width() as i32;
+        let grid_height = self.diffuse_rgba.height() as i32;
+        for ty in 1..(height - 1) {
+            for tx in 1..(width - 1) {
+                let idx = ty * width + tx;
+                let p = self.cell_pressures[idx];
+                if p < MIN_PUSH {
+                    continue;
+                }
+                // Pushed from high to low pressure; "top" is ty + 1.
+                let mut dx = self.cell_pressures[idx - 1] - self.cell_pressures[idx + 1];
+                let mut dy = self.cell_pressures[idx - width] - self.cell_pressures[idx + width];
+                let len = (dx * dx + dy * dy).sqrt();
+                if len < 1e-3 {
+                    // Centre of the blast: scatter in a random direction.
+                    let angle = self.prng.next() as f32 / 256.0 * std::f32::consts::TAU;
+                    (dx, dy) = (angle.cos(), angle.sin());
+                } else {
+                    (dx, dy) = (dx / len, dy / len);
+                }
+                let throw = ((p / PUSH_SCALE) as usize).min(MAX_THROW);
+
+                for y in ty * scale..(ty + 1) * scale {
+                    for x in tx * scale..(tx + 1) * scale {
+                        let (x, y) = (x as i32, y as i32);
+                        let id = self.get_cell(x, y);
+                        if id == Void::id() {
+                            continue;
+                        }
+                        let cell = &self.pal_container.pal[id as usize];
+                        if cell.stat() {
+                            if p > cell.blast_resistance() {
+                                let rubble = cell.rubble();
+                                self.set_cell(x, y, rubble);
+                            }
+                            continue;
+                        }
+                        let ballistic = cell.momentum() != Momentum::None && self.momentum_enabled();
+                        let ray = |step: i32| {
+                            let nx = x + (dx * step as f32).round() as i32;
+                            let ny = y + (dy * step as f32).round() as i32;
+                            let inside =
+                                nx >= 1 && ny >= 1 && nx < grid_width - 1 && ny < grid_height - 1;
+                            inside.then_some((nx, ny))
+                        };
+                        // Find the first gap along the push direction, through loose cells only.
+                        let mut gap = None;
+                        for step in 1..=MAX_PUSH_CHAIN {
+                            let Some((nx, ny)) = ray(step) else { break };
+                            let next = self.get_cell(nx, ny);
+                            if next == Void::id() {
+                                gap = Some(step);
+                                break;
+                            }
+                            if self.pal_container.pal[next as usize].stat() {
+                                break;
+                            }
+                        }
+                        match gap {
+                            // Free to fly with momentum on: launch it and let the cell's own
+                            // update carry it along a ballistic path.
+                            Some(1) if ballistic => {
+                                let v = self.get_velocity(x as PointType, y as PointType);
+                                let kick = throw.max(1) as f32;
+                                self.set_velocity(
+                                    x as PointType,
+                                    y as PointType,
+                                    [v[0] + dx * kick, v[1] + dy * kick],
+                                );
+                            }
+                            // Free to fly: slide along the push direction while the way is clear.
+                            Some(1) => {
+                                let distance =
+                                    throw.saturating_sub((self.prng.next() % 3) as usize).max(1);
+                                let (mut to_x, mut to_y) = (x, y);
+                                for step in 1..=distance as i32 {
+                                    match ray(step) {
+                                        Some((nx, ny)) if self.get_cell(nx, ny) == Void::id() => {
+                                            (to_x, to_y) = (nx, ny);
+                                        }
+                                        _ => break,
+                                    }
+                                }
+                                self.swap_cells((x, y), (to_x, to_y));
+                            }
+                            // Buried: shove the whole line of cells in front one step into the gap.
+                            Some(steps) => {
+                                for step in (1..=steps).rev() {
+                                    let to = ray(step).unwrap();
+                                    self.swap_cells(ray(step - 1).unwrap_or((x, y)), to);
+                                }
+                            }
+                            None => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Swaps two cells together with their velocity, aux value and, when it is kept per cell,
+    /// temperature, the way `CellGrid::swap` carries the aux value along.
+    fn swap_cells(&mut self, (ax, ay): (i32, i32), (bx, by): (i32, i32)) {
+        let width = self.diffuse_rgba.width() as usize;
+        let a = ay as usize * width + ax as usize;
+        let b = by as usize * width + bx as usize;
+        self.diffuse_rgba.swap(a, b);
+        if !self.cell_velocities.is_empty() {
+            self.cell_velocities.swap(a, b);
+        }
+        if !self.cell_aux.is_empty() {
+            self.cell_aux.swap(a, b);
+        }
+        if self.temperature_resolution == TemperatureResolution::Full {
+            self.cell_temperatures.swap(a, b);
+        }
+    }
+
+    /// One tick of the wind field: air rises where it is warmer than the world, gusts are carried
+    /// downstream, spread to open neighbours and die down, and static cells stop the flow.
+    pub fn step_wind(&mut self) {
+        // Wind per tick gained per degree above the global temperature.
+        const BUOYANCY: f32 = 0.0004;
+        // Share of the difference to the neighbours' average evened out per tick.
+        const SPREAD: f32 = 0.05;
+        // Share kept per tick.
+        const DAMPING: f32 = 0.99;
+        const MAX_WIND: f32 = 2.0;
+
+        let (width, height) = self.wind_grid_size();
+        let scale = WIND_CELL_SIZE as usize;
+        let (ux, uy) = self.gravity().vector();
+        let up = [-ux as f32, -uy as f32];
+        // A wind cell is open when any of four spread-out samples is not static.
+        let (near, far) = (scale / 4, scale * 3 / 4);
+        let open: Vec<bool> = (0..width * height)
+            .map(|idx| {
+                let (x, y) = (idx % width * scale, idx / width * scale);
+                [(near, near), (far, near), (near, far), (far, far)]
+                    .iter()
+                    .any(|&(dx, dy)| {
+                        let id = self.get_cell((x + dx) as i32, (y + dy) as i32);
+                        !self.pal_container.pal[id as usize].stat()
+                    })
+            })
+            .collect();
+
+        let mut new_wind = vec![[0.0f32; 2]; width * height];
+        for ty in 1..(height - 1) {
+            for tx in 1..(width - 1) {
+                let idx = ty * width + tx;
+                if !open[idx] {
+                    continue;
+                }
+                let (mut avg, mut count) = ([0.0f32; 2], 0.0f32);
+                for n in [idx - 1, idx + 1, idx - width, idx + width] {
+                    if open[n] {
+                        avg[0] += self.cell_wind[n][0];
+                        avg[1] += self.cell_wind[n][1];
+                        count += 1.0;
+                    }
+                }
+                let avg = avg.map(|a| a / count.max(1.0));
+                let (cx, cy) = (tx * scale + scale / 2, ty * scale + scale / 2);
+                let warmth = self.get_temperature(cx as PointType, cy as PointType)
+                    - self.global_temperature;
+                let lift = warmth.max(0.0) * BUOYANCY;
+                // Air blowing in from a neighbour replaces the share of this cell it covers in a tick.
+                let w = self.cell_wind[idx];
+                let mut carried = w;
+                for (axis, step) in [(0, 1), (1, width)] {
+                    for (from, towards) in [(idx - step, 1.0), (idx + step, -1.0)] {
+                        let inflow = self.cell_wind[from][axis] * towards;
+                        if open[from] && inflow > 0.0 {
+                            let share = (inflow / scale as f32).min(0.5);
+                            let upstream = self.cell_wind[from];
+                            carried = [0, 1].map(|k| carried[k] + (upstream[k] - w[k]) * share);
+                        }
+                    }
+                }
+                new_wind[idx] = [0, 1].map(|k| {
+                    let v = (carried[k] + (avg[k] - carried[k]) * SPREAD) * DAMPING + up[k] * lift;
+                    v.clamp(-MAX_WIND, MAX_WIND)
+                });
+            }
+        }
+        self.cell_wind = new_wind;
+    }
+}
@@ -1,16 +1,14 @@
 use cgmath::num_traits::clamp;
-use std::cell::RefCell;
-use std::rc::Rc;
 use wgpu::{util::DeviceExt, TextureFormat, TextureView};
 use winit::dpi::{LogicalPosition, PhysicalSize};
 
-use crate::shared_state::SharedState;
 use crate::{
-    cells::{molten_base::MoltenBase, molten_salt::MoltenSalt, stone::Stone, void::Void, wood::Wood, CellRegistry, Prng},
-    cs::{self, PointType},
+    cells::{void::Void, CellRegistry},
+    cs::PointType,
     evolution_app::EvolutionApp,
     gbuffer::GBuffer,
-    update, Vertex, INDICES, VERTICES,
+    simulation::Simulation,
+    Vertex, INDICES, VERTICES,
 };
 
 #[repr(C)]
@@ -48,71 +46,6 @@ pub struct UpdateResult {
     pub dropping: bool,
 }
 
-#[derive(Debug, Clone)]
-pub struct DayNightCycle {
-    /// Length of a full day in simulation seconds.
-    pub day_length_seconds: f32,
-    /// Current time of day in [0, day_length_seconds).
-    pub time_of_day_seconds: f32,
-    /// Multiplier for how fast the cycle advances relative to simulation time.
-    pub speed: f32,
-    /// Pause only the day/night cycle (simulation can still run).
-    pub paused: bool,
-    /// Rotation offset so that time=0 matches the initial art direction.
-    pub sun_angle_offset_rad: f32,
-    /// Shadow strength sent to shader (0..1).
-    pub shadow_strength: f32,
-    /// Shadow length in raymarch steps (1..64).
-    pub shadow_length_steps: f32,
-    /// Controls how quickly shadow fades with distance (0 disables distance attenuation).
-    pub shadow_distance_falloff: f32,
-}
-
-impl DayNightCycle {
-    pub fn new(day_length_seconds: f32, initial_dir_xy: (f32, f32)) -> Self {
-        let (mut x, mut y) = initial_dir_xy;
-        let len2 = x * x + y * y;
-        if len2 > 1e-12 {
-            let inv = 1.0 / len2.sqrt();
-            x *= inv;
-            y *= inv;
-        } else {
-            x = -0.8;
-            y = 0.4;
-        }
-
-        Self {
-            day_length_seconds: day_length_seconds.max(0.1),
-            time_of_day_seconds: 0.0,
-            speed: 1.0,
-            // By default keep time-of-day fixed (can be toggled in UI).
-            paused: true,
-            sun_angle_offset_rad: y.atan2(x),
-            shadow_strength: 0.9,
-            // 30% shorter than previous default (26.0 * 0.7 ≈ 18.2).
-            shadow_length_steps: 18.2,
-            shadow_distance_falloff: 1.0,
-        }
-    }
-
-    /// Advances the cycle and returns new normalized light direction (x,y).
-    pub fn advance(&mut self, dt_sim_seconds: f32) -> (f32, f32) {
-        if self.paused {
-            return self.current_dir();
-        }
-
-        let dt = (dt_sim_seconds.max(0.0)) * self.speed.max(0.0);
-        self.time_of_day_seconds =
-            (self.time_of_day_seconds + dt).rem_euclid(self.day_length_seconds.max(0.1));
-        self.current_dir()
-    }
-
-    pub fn current_dir(&self) -> (f32, f32) {
-        let phase = (self.time_of_day_seconds / self.day_length_seconds.max(0.1)).clamp(0.0, 1.0);
-        let angle = phase * std::f32::consts::TAU + self.sun_angle_offset_rad;
-        (angle.cos(), angle.sin())
-    }
-}
 
 pub struct State {
     render_pipeline: wgpu::RenderPipeline,
@@ -138,51 +71,98 @@ pub struct State {
     type_bind_group: wgpu::BindGroup,
     shadow_props_bind_group: wgpu::BindGroup,
     gbuffer_combine_bind_group: wgpu::BindGroup,
-    pub diffuse_rgba: image::ImageBuffer<image::Luma<u8>, Vec<u8>>,
-    pub loaded_rgba: image::ImageBuffer<image::Luma<u8>, Vec<u8>>,
     diffuse_texture: wgpu::Texture,
     shadow_props_texture: wgpu::Texture,
-    pub flip: cs::PointType,
-    pub flop: cs::PointType,
     last_spawn: f32,
-    pub pal_container: CellRegistry,
-    pub prng: Prng,
     base_texture: wgpu::Texture,
     glow_texture: wgpu::Texture,
     gbuffer: GBuffer,
     surface_format: TextureFormat,
-    pub toggled: bool,
-    pub tick: i64,
-    pub frame: i64,
-    /// Simulation time in seconds (advances with ticks, not wall clock).
-    pub sim_time_seconds: f64,
-    pub day_night: DayNightCycle,
-    // Temperature system for each cell (degrees), reduced grid (4x smaller for optimization).
-    // Stored as delta from global_temperature: effective_temp = cell_temperatures[idx] + global_temperature
-    pub cell_temperatures: Vec<f32>,
-    /// Global/base temperature (degrees).
-    pub global_temperature: f32,
-    // Temperature texture for GPU (low-res, res/4), 1:1 with `cell_temperatures`.
+    // Temperature texture for GPU, 1:1 with `cell_temperatures` (res/4 unless full resolution).
     temperature_texture: wgpu::Texture,
     temperature_bind_group: wgpu::BindGroup,
+    temperature_texture_size: (usize, usize),
+    temperature_texture_bgl: wgpu::BindGroupLayout,
+    temperature_texture_sampler: wgpu::Sampler,
+    grid_size: (u32, u32),
 }
 
-pub const TEMP_MIN: f32 = -100.0;
-pub const TEMP_MAX: f32 = 1000.0;
+const CELL_PROPS_ROWS: u32 = 2;
+
+fn create_temperature_texture(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    (width, height): (usize, usize),
+) -> (wgpu::Texture, wgpu::BindGroup) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        size: wgpu::Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: TextureFormat::R32Float,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        label: Some("temperature_texture"),
+    });
+
+    let view = texture.create_view(&wgpu::TextureViewDescriptor {
+        format: Some(TextureFormat::R32Float),
+        ..Default::default()
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+        label: Some("temperature_bind_group"),
+    });
+    (texture, bind_group)
+}
 
-impl State {
-    pub(crate) fn update_with_data(&mut self, p0: &[u8]) {
-        if p0.is_empty() || p0.len() == 0 {
-            self.generate_simple();
-        } else {
-            let res = image::load_from_memory(p0).expect("Load from memory failed");
-            self.loaded_rgba = res.to_luma8();
-            self.diffuse_rgba = res.to_luma8();
-            // Imported map should not inherit previous temperature field.
-            self.reset_temperatures();
-            println!("Some image loaded");
-        }
+/// Fills the 256xN per-cell-type texture:
+/// - row 0: shadow color multiplier in RGB (255 = no darkening), shadow opacity in A,
+/// - row 1: `display_color`, used by the shader for ids it has no hand-written look for.
+fn write_cell_props(queue: &wgpu::Queue, texture: &wgpu::Texture, registry: &CellRegistry) {
+    let mut props = [255u8; 256 * 4 * CELL_PROPS_ROWS as usize];
+    for (i, cell) in registry.pal.iter().enumerate().take(256) {
+        let o = i * 4;
+        props[o..o + 4].copy_from_slice(&cell.shadow_rgba());
+        let [r, g, b] = cell.display_color();
+        let o = (256 + i) * 4;
+        props[o..o + 4].copy_from_slice(&[r, g, b, 255]);
     }
+
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        &props,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: std::num::NonZeroU32::new(256 * 4),
+            rows_per_image: std::num::NonZeroU32::new(CELL_PROPS_ROWS),
+        },
+        wgpu::Extent3d {
+            width: 256,
+            height: CELL_PROPS_ROWS,
+            depth_or_array_layers: 1,
+        },
+    );
 }
 
 struct MyState {
@@ -191,110 +171,16 @@ struct MyState {
 }
 
 impl State {
-    pub fn generate_simple(&mut self) {
-        let mut buf = [0u8; 4];
-        self.diffuse_rgba = image::GrayImage::from_fn(
-            cs::SECTOR_SIZE.x as u32,
-            cs::SECTOR_SIZE.y as u32,
-            |x, y| {
-                if x > 1
-                    && y > 1
-                    && x < cs::SECTOR_SIZE.x as u32 - 2
-                    && y < cs::SECTOR_SIZE.y as u32 - 2
-                {
-                    _ = getrandom::getrandom(&mut buf);
-                    return image::Luma([if buf[0] % 7 == 0 && y < cs::SECTOR_SIZE.y as u32 / 2 {
-                        buf[1] % 4
-                    } else {
-                        0
-                    }]);
-                } else {
-                    return image::Luma([Stone::id()]);
-                }
-            },
-        );
-
-        for _ in 0..150 {
-            _ = getrandom::getrandom(&mut buf);
-
-            let nx = (((buf[0] as u32) << 8) | buf[1] as u32) % cs::SECTOR_SIZE.x as u32;
-            let ny = (((buf[2] as u32) << 8) | buf[3] as u32) % cs::SECTOR_SIZE.y as u32;
-
-            for x in 0..50 {
-                self.diffuse_rgba.put_pixel(
-                    clamp(nx + x, 0, cs::SECTOR_SIZE.x as u32 - 1),
-                    clamp(ny, 0, cs::SECTOR_SIZE.y as u32 - 1),
-                    image::Luma([Wood::id()]),
-                );
-            }
-        }
-
-        for _ in 0..100 {
-            _ = getrandom::getrandom(&mut buf);
-
-            let nx = (((buf[0] as u32) << 8) | buf[1] as u32) % cs::SECTOR_SIZE.x as u32;
-            let ny = (((buf[2] as u32) << 8) | buf[3] as u32) % cs::SECTOR_SIZE.y as u32;
-
-            for x in 0..20 {
-                for y in 0..20 {
-                    self.diffuse_rgba.put_pixel(
-                        clamp(nx + x, 0, cs::SECTOR_SIZE.x as u32 - 1),
-                        clamp(ny + y, 0, cs::SECTOR_SIZE.y as u32 - 1),
-                        image::Luma([Wood::id()]),
-                    );
-                }
-            }
-        }
-
-        for _ in 0..3 {
-            for cell in self.pal_container.pal.iter() {
-                let id = cell.id();
-                if id != 0 && id != MoltenSalt::id() && id != MoltenBase::id() {
-                    _ = getrandom::getrandom(&mut buf);
-
-                    let nx = (((buf[0] as u32) << 8) | buf[1] as u32) % cs::SECTOR_SIZE.x as u32;
-                    let ny = (((buf[2] as u32) << 8) | buf[3] as u32) % cs::SECTOR_SIZE.y as u32;
-
-                    for x in 0..35 {
-                        for y in 0..20 {
-                            self.diffuse_rgba.put_pixel(
-                                clamp(nx + x, 0, cs::SECTOR_SIZE.x as u32 - 1),
-                                clamp(ny + y, 0, cs::SECTOR_SIZE.y as u32 - 1),
-                                image::Luma([cell.id()]),
-                            );
-                        }
-                    }
-                }
-            }
-        }
-
-        // New random map should start from a clean temperature field.
-        self.reset_temperatures();
-    }
     pub fn new(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         config: &wgpu::SurfaceConfiguration,
         _surface: &wgpu::Surface,
         surface_format: wgpu::TextureFormat,
+        sim: &Simulation,
     ) -> Self {
-        let diffuse_rgba = image::GrayImage::from_fn(
-            cs::SECTOR_SIZE.x as u32,
-            cs::SECTOR_SIZE.y as u32,
-            |x, y| {
-                if x > 1
-                    && y > 1
-                    && x < cs::SECTOR_SIZE.x as u32 - 2
-                    && y < cs::SECTOR_SIZE.y as u32 - 2
-                {
-                    return image::Luma([0]);
-                } else {
-                    return image::Luma([Stone::id()]);
-                }
-            },
-        );
-
-        let pal_container = CellRegistry::new();
+        let diffuse_rgba = &sim.diffuse_rgba;
+        let pal_container = &sim.pal_container;
 
         fn create_render_target(
             device: &wgpu::Device,
@@ -333,14 +219,11 @@ impl State {
             label: Some("diffuse_texture"),
         });
 
-        // Per-cell-type shadow properties: RGBA where
-        // - RGB is the shadow color multiplier (255 = no darkening),
-        // - A is the shadow opacity (0 = does not affect, 255 = fully affects).
-        // Stored as a 256x1 RGBA8 texture and indexed by cell id in the shader.
+        // Per-cell-type properties, indexed by cell id in the shader (see `write_cell_props`).
         let shadow_props_texture = device.create_texture(&wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
                 width: 256,
-                height: 1,
+                height: CELL_PROPS_ROWS,
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
@@ -351,13 +234,6 @@ impl State {
             label: Some("shadow_props_texture"),
         });
 
-        let mut shadow_props = [255u8; 256 * 4];
-        for (i, cell) in pal_container.pal.iter().enumerate().take(256) {
-            let rgba = cell.shadow_rgba();
-            let o = i * 4;
-            shadow_props[o..o + 4].copy_from_slice(&rgba);
-        }
-
         let viewport_extent = wgpu::Extent3d {
             width: 1024,
             height: 768,
@@ -377,7 +253,7 @@ impl State {
                 aspect: wgpu::TextureAspect::All,
             },
             // The actual pixel data
-            &diffuse_rgba,
+            diffuse_rgba,
             // The layout of the texture
             wgpu::ImageDataLayout {
                 offset: 0,
@@ -387,25 +263,7 @@ impl State {
             texture_size,
         );
 
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &shadow_props_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &shadow_props,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: std::num::NonZeroU32::new(256 * 4),
-                rows_per_image: std::num::NonZeroU32::new(1),
-            },
-            wgpu::Extent3d {
-                width: 256,
-                height: 1,
-                depth_or_array_layers: 1,
-            },
-        );
+        write_cell_props(queue, &shadow_props_texture, pal_container);
 
         let type_render_and_fullscreen_vertex =
             device.create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -435,12 +293,7 @@ impl State {
 
         let start_time = instant::now();
 
-        // Day/night defaults (also used to seed shader light direction).
-        // "Almost strictly down" in texel space.
-        // Note: in this project, positive Y in texel space corresponds to "up" on screen,
-        // so "down" is negative Y.
-        let initial_sun_dir = (0.03f32, -1.0f32);
-        let day_night = DayNightCycle::new(120.0, initial_sun_dir);
+        let day_night = &sim.day_night;
         let (sun_x, sun_y) = day_night.current_dir();
 
         let world_settings = WorldSettings {
@@ -448,7 +301,7 @@ impl State {
             res_x: dimensions.0 as f32,
             res_y: dimensions.1 as f32,
             display_mode: 0.0, // Normal mode by default
-            global_temperature: 21.0,
+            global_temperature: sim.global_temperature,
             // Directional light/shadows (used by shader.wgsl):
             // sun_dir_x/sun_dir_y = sun direction (texel space)
             // shadow_strength = shadow strength [0..2]
@@ -764,26 +617,6 @@ impl State {
             label: Some("gbuffer_combine_bind_group"),
         });
 
-        // Create ambient/visual temperature texture (4x smaller for faster diffusion)
-        let temp_texture_size = wgpu::Extent3d {
-            width: dimensions.0 / 4,
-            height: dimensions.1 / 4,
-            depth_or_array_layers: 1,
-        };
-        let temperature_texture = device.create_texture(&wgpu::TextureDescriptor {
-            size: temp_texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: TextureFormat::R32Float,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            label: Some("temperature_texture"),
-        });
-
-        let mut temperature_texture_view = wgpu::TextureViewDescriptor::default().clone();
-        temperature_texture_view.format = Some(TextureFormat::R32Float);
-        let temperature_texture_view = temperature_texture.create_view(&temperature_texture_view);
-
         let temperature_texture_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
@@ -819,20 +652,14 @@ impl State {
                 label: Some("temperature_texture_bind_group_layout"),
             });
 
-        let temperature_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &temperature_texture_bgl,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&temperature_texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&temperature_texture_sampler),
-                },
-            ],
-            label: Some("temperature_bind_group"),
-        });
+        // Sized like the simulation's temperature grid; recreated by `sync_temperature_texture`.
+        let temperature_texture_size = sim.temperature_grid_size();
+        let (temperature_texture, temperature_bind_group) = create_temperature_texture(
+            device,
+            &temperature_texture_bgl,
+            &temperature_texture_sampler,
+            temperature_texture_size,
+        );
 
         //-------------------------------
 
@@ -1106,20 +933,7 @@ impl State {
 
         let num_indices = INDICES.len() as u32;
 
-        let a = 0;
-        let b = 0;
-
         let last_spawn = -5.0;
-        let prng = Prng::new();
-
-        // Initialize cell temperatures (initial temperature = 0.0)
-        // Initialize cell temperatures (reduced grid, 4x smaller for optimization)
-        // Use a reduced grid (4x smaller) for optimization
-        let temp_width = (cs::SECTOR_SIZE.x / 4) as usize;
-        let temp_height = (cs::SECTOR_SIZE.y / 4) as usize;
-        let total_cells = temp_width * temp_height;
-        // Temperatures stored as delta from global_temperature, so initialize to 0.0
-        let cell_temperatures = vec![0.0; total_cells];
 
         Self {
             render_pipeline: type_render_pipeline,
@@ -1141,34 +955,47 @@ impl State {
             start_time,
             type_bind_group,
             shadow_props_bind_group,
-            loaded_rgba: diffuse_rgba.clone(),
-            diffuse_rgba,
             gbuffer_combine_bind_group,
             diffuse_texture: cell_type_texture,
             shadow_props_texture,
             float_texture_plus_sampler_bgl,
             float_texture_plus_sampler_plus_texture_bgl,
-            flip: a,
-            flop: b,
             last_spawn,
-            pal_container,
-            prng,
             base_texture,
             glow_texture,
             gbuffer,
             surface_format,
-            toggled: true,
-            tick: 0,
-            frame: 0,
-            sim_time_seconds: 0.0,
-            day_night,
-            cell_temperatures,
-            global_temperature: 21.0,
             temperature_texture,
             temperature_bind_group,
+            temperature_texture_size,
+            temperature_texture_bgl,
+            temperature_texture_sampler,
+            grid_size: dimensions,
         }
     }
 
+    /// Size of the cell grid the textures were created for. The state has to be rebuilt
+    /// once `Simulation::set_grid` switches to another size.
+    pub fn grid_size(&self) -> (u32, u32) {
+        self.grid_size
+    }
+
+    /// Recreates the temperature texture after `Simulation::set_temperature_resolution`.
+    /// Must run before `update` uploads `cell_temperatures`.
+    pub fn sync_temperature_texture(&mut self, device: &wgpu::Device, sim: &Simulation) {
+        let size = sim.temperature_grid_size();
+        if size == self.temperature_texture_size {
+            return;
+        }
+        (self.temperature_texture, self.temperature_bind_group) = create_temperature_texture(
+            device,
+            &self.temperature_texture_bgl,
+            &self.temperature_texture_sampler,
+            size,
+        );
+        self.temperature_texture_size = size;
+    }
+
     // pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
     //     if new_size.width > 0 && new_size.height > 0 {
     //         self.size = new_size;
@@ -1183,177 +1010,9 @@ impl State {
     //     falsex
     // }
 
-    fn set_cell(&mut self, x: i32, y: i32, t: u8) {
-        self.diffuse_rgba
-            .put_pixel(x as u32, y as u32, image::Luma([t]));
-    }
-
-    // Convert full grid coordinates to reduced grid temperature index (4x smaller)
-    fn temp_coords_to_index(&self, i: PointType, j: PointType) -> usize {
-        let temp_x = (i / 4) as usize;
-        let temp_y = (j / 4) as usize;
-        let temp_width = (cs::SECTOR_SIZE.x / 4) as usize;
-        temp_y * temp_width + temp_x
-    }
-
-    // Get cell temperature by index (in reduced grid)
-    // Returns effective temperature: stored_delta + global_temperature
-    pub fn get_cell_temperature(&self, index: usize) -> f32 {
-        if index < self.cell_temperatures.len() {
-            (self.cell_temperatures[index] + self.global_temperature)
-                .max(TEMP_MIN)
-                .min(TEMP_MAX)
-        } else {
-            0.0
-        }
-    }
-
-    // Get cell temperature by coordinates (full grid)
-    pub fn get_temperature(&self, i: PointType, j: PointType) -> f32 {
-        let idx = self.temp_coords_to_index(i, j);
-        self.get_cell_temperature(idx)
-    }
-
-
-    // Set cell temperature by index (in reduced grid)
-    // Stores as delta from global_temperature: stored = temp - global_temperature
-    pub fn set_cell_temperature(&mut self, index: usize, temp: f32) {
-        if index < self.cell_temperatures.len() {
-            let clamped = temp.max(TEMP_MIN).min(TEMP_MAX);
-            self.cell_temperatures[index] = clamped - self.global_temperature;
-        }
-    }
-
-    // Set cell temperature by coordinates (full grid)
-    pub fn set_temperature(&mut self, i: PointType, j: PointType, temp: f32) {
-        let idx = self.temp_coords_to_index(i, j);
-        self.set_cell_temperature(idx, temp);
-    }
-
-    // Add temperature to cell (for heat generation)
-    pub fn add_temperature(&mut self, i: PointType, j: PointType, delta: f32) {
-        let idx = self.temp_coords_to_index(i, j);
-        if idx < self.cell_temperatures.len() {
-            self.cell_temperatures[idx] += delta;
-            // Clamp effective temperature to reasonable limits
-            let eff = self.cell_temperatures[idx] + self.global_temperature;
-            let clamped = eff.max(TEMP_MIN).min(TEMP_MAX);
-            self.cell_temperatures[idx] = clamped - self.global_temperature;
-        }
-    }
-
-    // Add temperature to cell by index (in reduced grid)
-    pub fn add_cell_temperature(&mut self, index: usize, delta: f32) {
-        if index < self.cell_temperatures.len() {
-            self.cell_temperatures[index] += delta;
-            // Clamp effective temperature to reasonable limits
-            let eff = self.cell_temperatures[index] + self.global_temperature;
-            let clamped = eff.max(TEMP_MIN).min(TEMP_MAX);
-            self.cell_temperatures[index] = clamped - self.global_temperature;
-        }
-    }
-
-    /// Reset per-cell temperature field (local delta, reduced grid) to zero.
-    /// Note: global temperature offset is preserved.
-    pub fn reset_temperatures(&mut self) {
-        self.cell_temperatures.as_mut_slice().fill(0.0);
-    }
-
-    /// Slow exchange: part of excess heat from ambient is transferred to cell "mass" (historical workaround).
-    pub fn absorb_ambient_heat(&mut self) {
-    }
-
-    /// Fast diffusion for the **ambient** (low-frequency) temperature field.
-
-    // Fast temperature diffusion - processes all cells of the reduced grid each frame
-    pub fn diffuse_temperature_fast(&mut self) {
-        // Tuned to avoid rapid global heat "flooding" from local sources (fire/wood).
-        let diffusion_rate = 0.10;
-        // Slower cooling so heat persists longer.
-        let cooling_rate = 0.998;
-        // Weak buoyancy: a small fraction of *positive* heat drifts upward each step.
-        // (Reduced grid, so keep it small to avoid visible "staircase" artifacts.)
-        // 2x faster upward drift.
-        let rise_rate = 0.016;
-
-        // Work directly with the reduced grid
-        let width = (cs::SECTOR_SIZE.x / 4) as usize;
-        let height = (cs::SECTOR_SIZE.y / 4) as usize;
-
-        // Temporary buffer for new local temperatures (delta from global_temperature).
-        let mut new_temps = vec![0.0f32; width * height];
-
-        // Process all cells of the reduced grid
-        for ty in 1..(height - 1) {
-            for tx in 1..(width - 1) {
-                let idx = ty * width + tx;
-                let current = self.cell_temperatures.get(idx).copied().unwrap_or(0.0);
-
-                // Get temperatures of neighboring cells in the reduced grid
-                let top_idx = (ty + 1) * width + tx;
-                let bot_idx = (ty - 1) * width + tx;
-                let left_idx = ty * width + (tx - 1);
-                let right_idx = ty * width + (tx + 1);
-
-                let top_temp = self
-                    .cell_temperatures
-                    .get(top_idx)
-                    .copied()
-                    .unwrap_or(current);
-                let bot_temp = self
-                    .cell_temperatures
-                    .get(bot_idx)
-                    .copied()
-                    .unwrap_or(current);
-                let left_temp = self
-                    .cell_temperatures
-                    .get(left_idx)
-                    .copied()
-                    .unwrap_or(current);
-                let right_temp = self
-                    .cell_temperatures
-                    .get(right_idx)
-                    .copied()
-                    .unwrap_or(current);
-
-                // Simple diffusion: average with neighbors
-                let avg = (top_temp + bot_temp + left_temp + right_temp) / 4.0;
-                let diffused = current + (avg - current) * diffusion_rate;
-                // Temperatures are stored as local delta.
-                new_temps[idx] = diffused * cooling_rate;
-            }
-        }
-
-        // Weak upward drift: move a small fraction of positive heat into the cell above.
-        // Note: in this reduced grid, "top" is (ty + 1).
-        if rise_rate > 0.0 {
-            for ty in 1..(height - 2) {
-                for tx in 1..(width - 1) {
-                    let idx = ty * width + tx;
-                    let top_idx = (ty + 1) * width + tx;
-                    let v = new_temps[idx];
-                    if v > 0.0 {
-                        let amount = v * rise_rate;
-                        new_temps[idx] -= amount;
-                        new_temps[top_idx] += amount;
-                    }
-                }
-            }
-        }
-
-        // Apply new temperatures with clamping in effective (global + local) domain.
-        for ty in 1..(height - 1) {
-            for tx in 1..(width - 1) {
-                let idx = ty * width + tx;
-                let eff = new_temps[idx] + self.global_temperature;
-                let clamped = eff.max(TEMP_MIN).min(TEMP_MAX);
-                self.cell_temperatures[idx] = clamped - self.global_temperature;
-            }
-        }
-    }
-
     fn spawn(
         &mut self,
+        sim: &mut Simulation,
         evolution_app: &mut EvolutionApp,
         size: PhysicalSize<u32>,
         scale_factor: f64,
@@ -1372,11 +1031,12 @@ impl State {
                 1.0 - logical_position.y / scaled_window_size.height as f64,
             );
 
-            let center_x = percentage_position.0 * cs::SECTOR_SIZE.x as f64;
-            let center_y = percentage_position.1 * cs::SECTOR_SIZE.y as f64;
+            let (width, height) = sim.diffuse_rgba.dimensions();
+            let center_x = percentage_position.0 * width as f64;
+            let center_y = percentage_position.1 * height as f64;
             let radius = evolution_app.brush_radius;
             let radius_squared = radius * radius;
-            let cell_type = self.pal_container.dict[&evolution_app.selected_option];
+            let cell_type = sim.pal_container.dict[&evolution_app.selected_option];
 
             // Iterate through all pixels in a square around the center
             let radius_int = radius.ceil() as i32;
@@ -1387,41 +1047,45 @@ impl State {
                         let px = center_x + dx as f64;
                         let py = center_y + dy as f64;
 
-                        let px_clamped = clamp(px, 0.0, cs::SECTOR_SIZE.x as f64 - 1.0);
-                        let py_clamped = clamp(py, 0.0, cs::SECTOR_SIZE.y as f64 - 1.0);
+                        let px_clamped = clamp(px, 0.0, width as f64 - 1.0);
+                        let py_clamped = clamp(py, 0.0, height as f64 - 1.0);
 
                         let x = px_clamped as u32;
                         let y = py_clamped as u32;
 
                         // Check if we should only place in empty cells
                         if evolution_app.only_empty_cells {
-                            let current_cell = self.diffuse_rgba.get_pixel(x, y).0[0];
+                            let current_cell = sim.diffuse_rgba.get_pixel(x, y).0[0];
                             if current_cell != Void::id() {
                                 continue;
                             }
                         }
 
-                        self.diffuse_rgba.put_pixel(x, y, image::Luma([cell_type]));
+                        sim.diffuse_rgba.put_pixel(x, y, image::Luma([cell_type]));
                     }
                 }
             }
         }
     }
 
+    /// Re-uploads per-cell-type shader data after the palette changed (e.g. materials loaded).
+    pub fn upload_cell_props(&self, queue: &wgpu::Queue, registry: &CellRegistry) {
+        write_cell_props(queue, &self.shadow_props_texture, registry);
+    }
+
     pub fn update(
         &mut self,
         queue: &wgpu::Queue,
-        mut sim_steps: i32,
+        sim: &mut Simulation,
+        sim_steps: i32,
         evolution_app: &mut EvolutionApp,
-        world: &mut specs::World,
-        shared_state: &Rc<RefCell<SharedState>>,
         size: PhysicalSize<u32>,
         scale_factor: f64,
     ) -> UpdateResult {
         let update_start_time = instant::now();
         // Shader time is simulation-time based (deterministic, starts at 0).
         // This makes time "fixed at start" and independent from wall clock.
-        self.world_settings.time = self.sim_time_seconds as f32;
+        self.world_settings.time = sim.sim_time_seconds as f32;
 
         // Update display mode from evolution_app
         self.world_settings.display_mode = match evolution_app.display_mode {
@@ -1429,11 +1093,11 @@ impl State {
             crate::evolution_app::DisplayMode::Temperature => 1.0,
             crate::evolution_app::DisplayMode::Both => 2.0,
         };
-        self.world_settings.global_temperature = self.global_temperature;
+        self.world_settings.global_temperature = sim.global_temperature;
 
         let sim_upd_start_time = instant::now();
 
-        let dimensions = self.diffuse_rgba.dimensions();
+        let dimensions = sim.diffuse_rgba.dimensions();
 
         // Update hover info for UI (cell under cursor + temperatures)
         evolution_app.hover_info = None;
@@ -1458,20 +1122,20 @@ impl State {
                     );
 
                     let px = clamp(
-                        percentage_position.0 * cs::SECTOR_SIZE.x as f64,
+                        percentage_position.0 * dimensions.0 as f64,
                         0.0,
-                        cs::SECTOR_SIZE.x as f64 - 1.0,
+                        dimensions.0 as f64 - 1.0,
                     );
                     let py = clamp(
-                        percentage_position.1 * cs::SECTOR_SIZE.y as f64,
+                        percentage_position.1 * dimensions.1 as f64,
                         0.0,
-                        cs::SECTOR_SIZE.y as f64 - 1.0,
+                        dimensions.1 as f64 - 1.0,
                     );
 
                     let x = px as PointType;
                     let y = py as PointType;
-                    let cell_id = self.diffuse_rgba.get_pixel(x as u32, y as u32).0[0];
-                    let ambient_temperature = self.get_temperature(x, y);
+                    let cell_id = sim.diffuse_rgba.get_pixel(x as u32, y as u32).0[0];
+                    let ambient_temperature = sim.get_temperature(x, y);
 
                     evolution_app.hover_info = Some(crate::evolution_app::HoverInfo {
                         x,
@@ -1484,7 +1148,7 @@ impl State {
         }
 
         if evolution_app.pressed && !evolution_app.hovered {
-            self.spawn(evolution_app, size, scale_factor);
+            self.spawn(sim, evolution_app, size, scale_factor);
         }
 
         let mut dropping = false;
@@ -1492,15 +1156,22 @@ impl State {
         // The MAX_SIM_STEPS_PER_FRAME limit in lib.rs is sufficient to prevent spiral of death
 
         if sim_steps > 0 {
-            update::update_tick(
-                self,
-                sim_steps,
-                dimensions,
-                evolution_app,
-                world,
-                shared_state,
-                update_start_time,
-            );
+            sim.steps_per_second = evolution_app.simulation_steps_per_second;
+            sim.cell_diffusion_iterations = evolution_app.cell_diffusion_iterations;
+            sim.step(sim_steps);
+
+            let (sun_x, sun_y) = sim.day_night.current_dir();
+            self.world_settings.sun_dir_x = sun_x;
+            self.world_settings.sun_dir_y = sun_y;
+            // Shadow params for shader:
+            // - shadow_strength: strength (0..2), where >1 pushes shadows towards pure black
+            // - shadow_length_steps: length in raymarch steps (1..64)
+            // - shadow_distance_falloff: distance falloff exponent (0 disables distance attenuation)
+            self.world_settings.shadow_strength = sim.day_night.shadow_strength.clamp(0.0, 2.0);
+            self.world_settings.shadow_length_steps =
+                sim.day_night.shadow_length_steps.clamp(1.0, 64.0);
+            self.world_settings.shadow_distance_falloff =
+                sim.day_night.shadow_distance_falloff.clamp(0.0, 4.0);
         }
 
         // Upload settings AFTER update_tick so GPU sees current light direction.
@@ -1522,7 +1193,7 @@ impl State {
             depth_or_array_layers: 1,
         };
 
-        //self.diffuse_rgba = output;
+        //sim.diffuse_rgba = output;
 
         queue.write_texture(
             // Tells wgpu where to copy the pixel data
@@ -1533,7 +1204,7 @@ impl State {
                 aspect: wgpu::TextureAspect::All,
             },
             // The actual pixel data
-            &self.diffuse_rgba,
+            &sim.diffuse_rgba,
             // The layout of the texture
             wgpu::ImageDataLayout {
                 offset: 0,
@@ -1545,8 +1216,8 @@ impl State {
 
         // Upload temperature data to GPU.
         //
-        let temp_width = dimensions.0 / 4;
-        let temp_height = dimensions.1 / 4;
+        let (temp_width, temp_height) = sim.temperature_grid_size();
+        let (temp_width, temp_height) = (temp_width as u32, temp_height as u32);
 
         let temp_texture_size = wgpu::Extent3d {
             width: temp_width,
@@ -1561,7 +1232,7 @@ impl State {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            bytemuck::cast_slice(&self.cell_temperatures),
+            bytemuck::cast_slice(&sim.cell_temperatures),
             wgpu::ImageDataLayout {
                 offset: 0,
                 bytes_per_row: std::num::NonZeroU32::new(temp_width * 4), // 4 bytes per f32
@@ -1,14 +1,12 @@
+use crate::cells::{CellGrid, CellRegistry, FieldView, Momentum, Prng, TemperatureContext};
+use crate::cs::{self, PointType};
 use crate::ecs::systems::{EntityScriptSystem, GravitySystem, MoveSystem};
-use crate::evolution_app::EvolutionApp;
 use crate::resources::rhai_resource::{RhaiResource, RhaiResourceStorage};
 use crate::rhai_lib;
-use crate::shared_state::SharedState;
-use crate::{cs, State};
+use crate::simulation::Simulation;
 use specs::RunNow;
-use std::cell::RefCell;
-use std::rc::Rc;
 
-fn set_frame_vars(state: &mut State, storage: &mut RhaiResourceStorage) {
+fn set_frame_vars(state: &Simulation, storage: &mut RhaiResourceStorage) {
     // Make `time` deterministic and tied to simulation time (starts from 0).
     // If scripts need wall clock, they should derive it themselves.
     let frame_time = state.sim_time_seconds as f64;
@@ -24,22 +22,18 @@ fn set_frame_vars(state: &mut State, storage: &mut RhaiResourceStorage) {
         .set_value("day_length", state.day_night.day_length_seconds);
     storage.scope.set_value("frame", state.frame);
     // Re-set GRID_WIDTH and GRID_HEIGHT after scope.clear() - scripts need these variables
-    storage.scope.set_value("GRID_WIDTH", 1024i64);
-    storage.scope.set_value("GRID_HEIGHT", 512i64);
+    let size = state.grid_size();
+    storage.scope.set_value("GRID_WIDTH", size.x as i64);
+    storage.scope.set_value("GRID_HEIGHT", size.y as i64);
 }
 
-pub fn update_tick(
-    state: &mut State,
-    sim_steps: i32,
-    _dimensions: (u32, u32),
-    evolution_app: &mut EvolutionApp,
-    world: &mut specs::World,
-    shared_state: &Rc<RefCell<SharedState>>,
-    _update_start_time: f64,
-) {
+pub fn update_tick(state: &mut Simulation, sim_steps: i32) {
     //let mut output = ImageBuffer::new(texture_size.width, texture_size.height);
     let mut b_index = 0;
     state.frame += 1;
+    // Cell code addresses the grid through `cs`, which has to know which grid it is.
+    let size = state.grid_size();
+    cs::set_sector_size(size);
 
     const BUF_SIZE: usize = 50;
     let mut buf = [0u8; BUF_SIZE];
@@ -48,16 +42,16 @@ pub fn update_tick(
         *b = state.prng.next();
     }
 
-    let one_tick_delta = 1.0 / evolution_app.simulation_steps_per_second as f64;
+    let one_tick_delta = 1.0 / state.steps_per_second.max(1) as f64;
 
     // Set frame variables once before the loop
     if state.toggled {
-        if let Some(rhai_resource) = world.get_mut::<RhaiResource>() {
+        let state_ptr: *mut Simulation = state;
+        if let Some(mut rhai_resource) = state.world.try_fetch_mut::<RhaiResource>() {
             if let Some(storage) = &mut rhai_resource.storage {
                 set_frame_vars(state, storage);
                 // Update state pointer in thread_local
-                let state_ptr: *mut State = state;
-                storage.state_ptr.set(state_ptr);
+                storage.sim_ptr.set(state_ptr);
                 rhai_lib::set_state_ptr(state_ptr);
             }
         }
@@ -68,23 +62,13 @@ pub fn update_tick(
 
         // Advance simulation time + day/night cycle (simulation-time based).
         state.sim_time_seconds += one_tick_delta;
-        let (sun_x, sun_y) = state.day_night.advance(one_tick_delta as f32);
-        state.world_settings.sun_dir_x = sun_x;
-        state.world_settings.sun_dir_y = sun_y;
-        // Shadow params for shader:
-        // - shadow_strength: strength (0..2), where >1 pushes shadows towards pure black
-        // - shadow_length_steps: length in raymarch steps (1..64)
-        // - shadow_distance_falloff: distance falloff exponent (0 disables distance attenuation)
-        state.world_settings.shadow_strength = state.day_night.shadow_strength.clamp(0.0, 2.0);
-        state.world_settings.shadow_length_steps =
-            state.day_night.shadow_length_steps.clamp(1.0, 64.0);
-        state.world_settings.shadow_distance_falloff =
-            state.day_night.shadow_distance_falloff.clamp(0.0, 4.0);
+        state.day_night.advance(one_tick_delta as f32);
 
         if state.toggled {
             // Set the tick variable in scope and update state pointer
             {
-                if let Some(rhai_resource) = world.get_mut::<RhaiResource>() {
+                let state_ptr: *mut Simulation = state;
+                if let Some(mut rhai_resource) = state.world.try_fetch_mut::<RhaiResource>() {
                     if let Some(storage) = &mut rhai_resource.storage {
                         storage.scope.set_value("tick", state.tick);
                         storage
@@ -94,14 +78,13 @@ pub fn update_tick(
                             .scope
                             .set_value("time_of_day", state.day_night.time_of_day_seconds);
                         // Update state pointer each tick to ensure it's valid
-                        let state_ptr: *mut State = state;
-                        storage.state_ptr.set(state_ptr);
+                        storage.sim_ptr.set(state_ptr);
                         rhai_lib::set_state_ptr(state_ptr);
                         if state.tick % 500 == 0 {
                             storage.scope.clear();
                             set_frame_vars(state, storage);
                             // Re-set state pointer after clear
-                            storage.state_ptr.set(state_ptr);
+                            storage.sim_ptr.set(state_ptr);
                             rhai_lib::set_state_ptr(state_ptr);
                         }
                     }
@@ -115,29 +98,27 @@ pub fn update_tick(
                 use specs::WorldExt;
                 if _sim_update == 0 {
                     let mut script_system = EntityScriptSystem;
-                    script_system.run_now(world);
-                    world.maintain();
+                    script_system.run_now(&state.world);
+                    state.world.maintain();
                 }
 
                 let mut gravity_system = GravitySystem;
-                gravity_system.run_now(world);
-                world.maintain();
+                gravity_system.run_now(&state.world);
+                state.world.maintain();
 
                 let mut move_system = MoveSystem;
-                move_system.run_now(world);
-                world.maintain();
+                move_system.run_now(&state.world);
+                state.world.maintain();
             }
         }
 
         // Apply queued brush/input updates.
         // Important on mobile: the queue can spike very large; `clear()` keeps capacity,
         // so we opportunistically shrink it to avoid long-term heap pressure.
-        {
-            let mut ss = shared_state.borrow_mut();
+        let (bodies, focus) = {
+            let mut ss = state.shared_state.borrow_mut();
             for (p, c) in ss.points.iter() {
-                if (0..cs::SECTOR_SIZE.x as i32).contains(&p.x)
-                    && (0..cs::SECTOR_SIZE.y as i32).contains(&p.y)
-                {
+                if (0..size.x as i32).contains(&p.x) && (0..size.y as i32).contains(&p.y) {
                     state
                         .diffuse_rgba
                         .put_pixel(p.x as u32, p.y as u32, image::Luma([*c]));
@@ -152,6 +133,25 @@ pub fn update_tick(
             if ss.points.capacity() > MAX_POINTS_CAP {
                 ss.points.shrink_to(SHRINK_TO);
             }
+
+            (std::mem::take(&mut ss.bodies), ss.focus.take())
+        };
+        for (name, x, y, body) in bodies {
+            state.add_rigid_body(&name, x, y, body);
+        }
+        if let Some(focus) = focus {
+            if let Err(err) = state.focus_sector(focus) {
+                // Only `focus_sector` in scripts asks for a new focus, so report it next to
+                // their own errors.
+                let rhai = state.world.try_fetch::<RhaiResource>();
+                if let Some(storage) = rhai.as_ref().and_then(|rhai| rhai.storage.as_ref()) {
+                    let mut log = storage.script_log.borrow_mut();
+                    if log.len() >= 30 {
+                        log.pop_front();
+                    }
+                    log.push_back(format!("focus_sector error: {err}"));
+                }
+            }
         }
 
         state.flip ^= 1;
@@ -163,50 +163,317 @@ pub fn update_tick(
         state.prng.gen();
 
         if state.tick % 2 == 0 {
-            let iters = evolution_app
-                .cell_diffusion_iterations
-                .clamp(1, 48) as usize;
-            for _ in 0..iters {
-                state.diffuse_temperature_fast();
-            }
+            let iters = state.cell_diffusion_iterations.clamp(1, 48) as usize;
+            state.diffuse_temperature(iters);
         }
+        state.step_pressure();
+        state.step_wind();
+        state.step_electricity();
+        state.step_rigid_bodies();
 
-        // Create temperature context ONCE before the loop for reuse.
-        // Must be allocation-free (hot path).
-        let state_ptr: *mut State = state;
-        let mut temp_context = crate::cells::TemperatureContext::new(state_ptr);
+        state.track_chunk_activity();
 
-        for i in (1..(cs::SECTOR_SIZE.x - 2 - state.flip)).rev().step_by(2) {
-            for j in (1..(cs::SECTOR_SIZE.y - 2 - state.flop)).rev().step_by(2) {
+        // Taken once for the whole pass, the grid and the fields are only reached through
+        // these until `finish_cell_pass`.
+        let fields = state.cell_fields();
+        // SAFETY: nothing below goes through `state` to the buffers in `fields`.
+        let (mut grid, mut temp_context) = unsafe { (fields.grid(), fields.temperature_context()) };
+        crate::cells::laser::cast_beams(
+            &mut grid,
+            &state.pal_container,
+            &mut state.cell_light,
+            &mut temp_context,
+        );
+
+        let wind_drag: [f32; 256] =
+            std::array::from_fn(|id| state.pal_container.pal[id].wind_drag());
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(pool) = state.cell_pool.clone() {
+            let pressure_added = parallel::update_cells(state, fields, &pool, &wind_drag);
+            state.finish_cell_pass(pressure_added || temp_context.pressure_added());
+            continue;
+        }
+
+        let mut sweep = CellSweep {
+            grid,
+            registry: &state.pal_container,
+            // SAFETY: as above.
+            velocities: unsafe { fields.velocities() },
+            wind_drag: &wind_drag,
+        };
+
+        // Columns are swept right to left and left to right in turn: whichever side goes first
+        // wins the cells its neighbours both want to move into, so one fixed direction makes
+        // liquids and powders drift.
+        let last_column = size.x - 3 - state.flip;
+        let columns = last_column.div_ceil(2);
+        for n in 0..columns {
+            let i = if state.flip == state.flop {
+                last_column - 2 * n
+            } else {
+                last_column - 2 * (columns - 1 - n)
+            };
+            for j in (1..(size.y - 2 - state.flop)).rev().step_by(2) {
                 b_index += 1;
                 if b_index >= BUF_SIZE {
                     b_index = 0;
                 }
 
                 // 21.5 % to skip each cell
-                if buf[b_index] > 200 {
+                if buf[b_index] > 200 || !state.chunk_sleep.is_awake(i as u32, j as u32) {
                     continue;
                 }
 
-                let cur = cs::xy_to_index(i, j);
-                let cur_v = *state.diffuse_rgba.get(cur).unwrap();
-
-                let needs_temp = state.pal_container.pal[cur_v as usize].needs_temp();
-
-                state.pal_container.pal[cur_v as usize].update(
-                    i,
-                    j,
-                    cur,
-                    state.diffuse_rgba.as_mut(),
-                    &state.pal_container,
-                    &mut state.prng,
-                    if needs_temp {
-                        Some(&mut temp_context)
-                    } else {
-                        None
-                    },
-                );
+                sweep.update(i, j, &mut state.prng, &mut temp_context);
+            }
+        }
+        state.finish_cell_pass(temp_context.pressure_added());
+    }
+}
+
+/// What updating one cell touches besides `TemperatureContext`, made from `CellFields` so the
+/// parallel pass can hand the same to every worker.
+struct CellSweep<'a> {
+    grid: CellGrid<'a>,
+    registry: &'a CellRegistry,
+    velocities: FieldView<'a, [f32; 2]>,
+    wind_drag: &'a [f32; 256],
+}
+
+impl CellSweep<'_> {
+    /// Reactions, momentum, wind drift and finally `CellTrait::update` for cell (i, j).
+    #[inline]
+    fn update(
+        &mut self,
+        i: PointType,
+        j: PointType,
+        prng: &mut Prng,
+        temp_context: &mut TemperatureContext,
+    ) {
+        let cur = cs::xy_to_index(i, j);
+        let cur_v = self.grid[cur];
+
+        if crate::cells::helper::try_react(
+            i,
+            j,
+            cur,
+            &mut self.grid,
+            self.registry,
+            prng,
+            Some(temp_context),
+        ) {
+            return;
+        }
+
+        if !self.velocities.is_empty() {
+            if self.registry.pal[cur_v as usize].momentum() == Momentum::None {
+                // Left behind by cells that changed type or were moved by plain swaps.
+                self.velocities[cur] = [0.0; 2];
+            } else if crate::cells::helper::momentum_helper(
+                i,
+                j,
+                &mut self.grid,
+                &mut self.velocities,
+                self.registry,
+                cur,
+                prng,
+            ) {
+                return;
+            }
+        }
+
+        let (i, j, cur) = if self.wind_drag[cur_v as usize] > 0.0 {
+            let wind = temp_context.get_wind(i, j);
+            let (x, y) = crate::cells::helper::wind_helper(
+                i,
+                j,
+                &mut self.grid,
+                wind,
+                self.wind_drag[cur_v as usize],
+                prng,
+            );
+            (x, y, cs::xy_to_index(x, y))
+        } else {
+            (i, j, cur)
+        };
+
+        let needs_temp = self.registry.pal[cur_v as usize].needs_temp();
+
+        self.registry.pal[cur_v as usize].update(
+            i,
+            j,
+            cur,
+            &mut self.grid,
+            self.registry,
+            prng,
+            if needs_temp { Some(temp_context) } else { None },
+        );
+    }
+}
+
+/// The cell pass on worker threads (see `Simulation::set_threads`).
+///
+/// The grid is cut into `CHUNK_SIZE` chunks and swept in four phases, one per corner of each
+/// 2x2 block of chunks. Chunks of one phase are a whole chunk apart, farther than any cell
+/// update reaches (momentum moves cells at most 8 cells, fans blow 4 cells ahead and liquid
+/// levelling is held to `REACH_MARGIN` past the chunk), so they can run at the same time
+/// without touching the same cells or the same temperature, wind and pressure entries. Every
+/// chunk draws from its own `Prng` stream, seeded from the simulation PRNG and the chunk
+/// index, so the result doesn't depend on the number of threads.
+#[cfg(not(target_arch = "wasm32"))]
+mod parallel {
+    use super::CellSweep;
+    use crate::cells::{CellFields, CellRegistry, Prng};
+    use crate::chunk_sleep::CHUNK_SIZE;
+    use crate::cs::{self, PointType};
+    use crate::simulation::Simulation;
+    use futures::executor::ThreadPool;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::mpsc;
+
+    /// Mixed with the chunk index into the seed of its `Prng`.
+    const CHUNK_STREAM: u64 = 0xD1B5_4A32_D192_ED03;
+    /// How far past its chunk a worker may walk (see `cs::set_reach`); with the few cells a
+    /// single update moves on top, well under half a chunk.
+    const REACH_MARGIN: PointType = 8;
+
+    /// What the workers of one phase share.
+    #[derive(Clone, Copy)]
+    struct Shared {
+        fields: CellFields,
+        registry: *const CellRegistry,
+        wind_drag: [f32; 256],
+        size: cgmath::Point2<PointType>,
+        flip: PointType,
+        flop: PointType,
+        seed: u64,
+    }
+
+    // SAFETY: the pointers stay valid until `update_cells` has heard back from every job, and
+    // nothing else reaches the buffers meanwhile. Jobs running at the same time only touch cells
+    // and field entries near their own chunk, which never overlap (see the module docs), and
+    // only one element at a time through `FieldView`s, so no two threads ever hold references
+    // into the same part of a buffer. The registry is only read.
+    unsafe impl Send for Shared {}
+
+    /// Runs the pass over `fields`, which `update_tick` took from `state`; returns whether any
+    /// cell added pressure.
+    pub(super) fn update_cells(
+        state: &mut Simulation,
+        fields: CellFields,
+        pool: &ThreadPool,
+        wind_drag: &[f32; 256],
+    ) -> bool {
+        let threads = state.threads();
+        let size = state.grid_size();
+        let chunks_x = (size.x as u32).div_ceil(CHUNK_SIZE) as usize;
+        let chunks_y = (size.y as u32).div_ceil(CHUNK_SIZE) as usize;
+        let shared = Shared {
+            fields,
+            registry: &state.pal_container,
+            wind_drag: *wind_drag,
+            size,
+            flip: state.flip,
+            flop: state.flop,
+            seed: state.prng.next_u64(),
+        };
+
+        let mut pressure_added = false;
+        for phase in 0..4 {
+            let chunks: Vec<(usize, usize)> = (phase / 2..chunks_y)
+                .step_by(2)
+                .flat_map(|cy| (phase % 2..chunks_x).step_by(2).map(move |cx| (cx, cy)))
+                .filter(|&(cx, cy)| {
+                    let (x, y) = (cx as u32 * CHUNK_SIZE, cy as u32 * CHUNK_SIZE);
+                    state.chunk_sleep.is_awake(x, y)
+                })
+                .collect();
+            let jobs = threads.min(chunks.len());
+            let (done, finished) = mpsc::channel();
+            for job in 0..jobs {
+                let mine: Vec<(usize, usize)> =
+                    chunks.iter().copied().skip(job).step_by(jobs).collect();
+                let done = done.clone();
+                pool.spawn_ok(async move {
+                    // Caught so the pool keeps its thread and the panic reaches the caller.
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                        let mut pressure_added = false;
+                        for (cx, cy) in mine {
+                            // SAFETY: see `Shared`.
+                            pressure_added |=
+                                unsafe { update_chunk(shared, cx, cy, cy * chunks_x + cx) };
+                        }
+                        pressure_added
+                    }));
+                    let _ = done.send(result);
+                });
+            }
+            drop(done);
+            // Every job has to finish before a panic is passed on: the others still write
+            // through `shared`.
+            let mut failure = None;
+            for result in finished.iter() {
+                match result {
+                    Ok(pressure) => pressure_added |= pressure,
+                    Err(payload) => {
+                        failure.get_or_insert(payload);
+                    }
+                }
+            }
+            if let Some(payload) = failure {
+                panic::resume_unwind(payload);
+            }
+        }
+        pressure_added
+    }
+
+    /// Same sweep as the single-threaded loop, limited to one chunk; returns whether it added
+    /// pressure. Only for chunks of the phase being run, see `Shared`.
+    unsafe fn update_chunk(shared: Shared, cx: usize, cy: usize, index: usize) -> bool {
+        let chunk = CHUNK_SIZE as PointType;
+        let (size, flip, flop) = (shared.size, shared.flip, shared.flop);
+        let (x0, y0) = (cx as PointType * chunk, cy as PointType * chunk);
+        // `cs` is per thread.
+        cs::set_sector_size(size);
+        cs::set_reach(Some([
+            x0.saturating_sub(REACH_MARGIN),
+            y0.saturating_sub(REACH_MARGIN),
+            (x0 + chunk + REACH_MARGIN).min(size.x),
+            (y0 + chunk + REACH_MARGIN).min(size.y),
+        ]));
+        let mut sweep = CellSweep {
+            grid: shared.fields.grid(),
+            registry: &*shared.registry,
+            velocities: shared.fields.velocities(),
+            wind_drag: &shared.wind_drag,
+        };
+        let mut temp_context = shared.fields.temperature_context();
+        let mut prng = Prng::from_seed(shared.seed ^ (index as u64 + 1).wrapping_mul(CHUNK_STREAM));
+
+        // Same cells as the serial loop: columns 1..=size.x - 3 of the parity picked by `flip`,
+        // rows 1..=size.y - 3 of the parity picked by `flop`.
+        let first = |start: PointType, parity: PointType| start + (start + parity) % 2;
+        let x_end = (x0 + chunk).min(size.x - 2);
+        let y_end = (y0 + chunk).min(size.y - 2);
+        let mut columns: Vec<PointType> = (first(x0.max(1), size.x - 3 - flip)..x_end)
+            .step_by(2)
+            .collect();
+        if flip == flop {
+            columns.reverse();
+        }
+        let rows = (first(y0.max(1), size.y - 3 - flop)..y_end)
+            .step_by(2)
+            .rev();
+        for i in columns {
+            for j in rows.clone() {
+                // 21.5 % to skip each cell, as in the serial loop.
+                if prng.next() > 200 {
+                    continue;
+                }
+                sweep.update(i, j, &mut prng, &mut temp_context);
             }
         }
+        temp_context.pressure_added()
     }
 }
@@ -0,0 +1,249 @@
+//! Headless batch runner: loads a map and a world script, runs N ticks without a window
+//! and writes the resulting grid, temperature field and script log to disk.
+//!
+//! ```text
+//! sand_evolution_cli --map level.png --script level.rhai --ticks 600 --seed 42 --out previews/level
+//! ```
+//!
+//! Outputs (`--out` is a path prefix):
+//! - `<out>.png` - luma8 cell ids, same format as "Export PNG" in the app,
+//! - `<out>_temperature.csv` - effective temperatures of the temperature grid (1/4 of the map,
+//!   or per cell with `--temperature full`), one row per line, bottom row first,
+//! - `<out>_log.txt` - everything the script printed.
+//!
+//! Without `--map` the run starts on an empty grid, 1024x512 unless `--size` says otherwise;
+//! a map brings its own size.
+//!
+//! `--sectors <dir>` runs a sectored world instead (see `sectors`): 3x3 sectors of 256x256
+//! around the focus, which scripts move with `focus_sector`. The sectors are read from and
+//! written back to `<dir>`.
+//!
+//! `--threads <n>` spreads the cell update over n threads. The result then depends on the seed
+//! but not on n; it differs from a single-threaded run with the same seed.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::rc::Rc;
+
+use sand_evolution_lib::cells::Gravity;
+use sand_evolution_lib::map_file;
+use sand_evolution_lib::sectors::{SectorStore, SectorWorld};
+use sand_evolution_lib::simulation::{Simulation, TemperatureResolution};
+
+/// Ticks per script run. The app runs 240 ticks/s at 60 fps and scripts run once per frame,
+/// so batch runs behave like the interactive ones.
+const TICKS_PER_FRAME: u64 = 4;
+
+const USAGE: &str = "usage: sand_evolution_cli [--materials <toml>] [--map <png>] [--size <w>x<h>] [--sectors <dir>] [--script <rhai>] [--ticks <n>] [--seed <u64>] [--temperature reduced|full] [--momentum on|off] [--cell-memory on|off] [--gravity down|up|left|right|zero] [--levelling on|off] [--threads <n>] [--out <prefix>]";
+
+struct Args {
+    materials: Option<String>,
+    map: Option<String>,
+    size: Option<(u32, u32)>,
+    sectors: Option<String>,
+    script: Option<String>,
+    ticks: u64,
+    seed: Option<u64>,
+    temperature: TemperatureResolution,
+    momentum: bool,
+    cell_memory: bool,
+    gravity: Gravity,
+    levelling: bool,
+    threads: usize,
+    out: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        materials: None,
+        map: None,
+        size: None,
+        sectors: None,
+        script: None,
+        ticks: 600,
+        seed: None,
+        temperature: TemperatureResolution::Reduced,
+        momentum: false,
+        cell_memory: false,
+        gravity: Gravity::Down,
+        levelling: true,
+        threads: 1,
+        out: "out".to_owned(),
+    };
+
+    let mut it = std::env::args().skip(1);
+    while let Some(flag) = it.next() {
+        if flag == "-h" || flag == "--help" {
+            return Err(USAGE.to_owned());
+        }
+        let value = it
+            .next()
+            .ok_or_else(|| format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--materials" => args.materials = Some(value),
+            "--map" => args.map = Some(value),
+            "--size" => {
+                args.size = value
+                    .split_once('x')
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)));
+                if args.size.is_none() {
+                    return Err(format!("invalid --size: {value}"));
+                }
+            }
+            "--sectors" => args.sectors = Some(value),
+            "--script" => args.script = Some(value),
+            "--ticks" => {
+                args.ticks = value
+                    .parse()
+                    .map_err(|_| format!("invalid --ticks: {value}"))?
+            }
+            "--seed" => {
+                args.seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid --seed: {value}"))?,
+                )
+            }
+            "--temperature" => {
+                args.temperature = match value.as_str() {
+                    "reduced" => TemperatureResolution::Reduced,
+                    "full" => TemperatureResolution::Full,
+                    _ => return Err(format!("invalid --temperature: {value}")),
+                }
+            }
+            "--momentum" => {
+                args.momentum = match value.as_str() {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("invalid --momentum: {value}")),
+                }
+            }
+            "--cell-memory" => {
+                args.cell_memory = match value.as_str() {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("invalid --cell-memory: {value}")),
+                }
+            }
+            "--gravity" => {
+                args.gravity = Gravity::from_name(&value)
+                    .ok_or_else(|| format!("invalid --gravity: {value}"))?
+            }
+            "--levelling" => {
+                args.levelling = match value.as_str() {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err(format!("invalid --levelling: {value}")),
+                }
+            }
+            "--threads" => {
+                args.threads = value
+                    .parse()
+                    .map_err(|_| format!("invalid --threads: {value}"))?
+            }
+            "--out" => args.out = value,
+            _ => return Err(format!("unknown flag {flag}\n{USAGE}")),
+        }
+    }
+    Ok(args)
+}
+
+fn temperature_csv(sim: &Simulation) -> String {
+    let (width, _) = sim.temperature_grid_size();
+    let mut csv = String::with_capacity(sim.cell_temperatures.len() * 6);
+    for row in 0..sim.cell_temperatures.len() / width {
+        for col in 0..width {
+            if col > 0 {
+                csv.push(',');
+            }
+            let _ = write!(csv, "{:.1}", sim.get_cell_temperature(row * width + col));
+        }
+        csv.push('\n');
+    }
+    csv
+}
+
+fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    let mut sim = match (args.size, args.seed) {
+        (Some((width, height)), seed) => {
+            Simulation::with_grid_size(width, height, seed.unwrap_or_else(Simulation::random_seed))?
+        }
+        (None, Some(seed)) => Simulation::with_seed(seed),
+        (None, None) => Simulation::new(),
+    };
+    sim.set_temperature_resolution(args.temperature);
+    sim.set_momentum(args.momentum);
+    sim.set_aux_buffer(args.cell_memory);
+    sim.set_gravity(args.gravity);
+    sim.set_liquid_levelling(args.levelling);
+    sim.set_threads(args.threads)?;
+    // Before the map, so its cell table can name the new materials.
+    if let Some(materials) = &args.materials {
+        sim.load_materials(&std::fs::read_to_string(materials)?)?;
+    }
+    if let Some(map) = &args.map {
+        for name in sim.load_map(&std::fs::read(map)?)? {
+            eprintln!("Warning: unknown cell {name:?} in {map}, loaded as void");
+        }
+    }
+    if let Some(dir) = &args.sectors {
+        if args.map.is_some() || args.size.is_some() {
+            return Err("--sectors can't be combined with --map or --size".into());
+        }
+        let world = SectorWorld::new(256, 256, 1, SectorStore::Directory(dir.into()))?;
+        sim.enable_sectors(world, (0, 0))?;
+    }
+
+    let script_log = Rc::new(RefCell::new(VecDeque::<String>::new()));
+    sim.init_scripting(script_log.clone());
+    if let Some(script) = &args.script {
+        if !sim.set_object_script("World Script", &std::fs::read_to_string(script)?) {
+            return Err(format!("no \"World Script\" object to run {script} in").into());
+        }
+    }
+
+    // The in-game log only keeps the last few lines, so drain it after every frame.
+    let mut log = String::new();
+    let mut remaining = args.ticks;
+    while remaining > 0 {
+        let n = remaining.min(TICKS_PER_FRAME);
+        sim.step(n as i32);
+        remaining -= n;
+        for line in script_log.borrow_mut().drain(..) {
+            log.push_str(&line);
+            log.push('\n');
+        }
+    }
+
+    if let Some(mut world) = sim.sectors.take() {
+        world.park_all(&sim)?;
+    }
+
+    let png = map_file::encode_png(&sim.diffuse_rgba, &sim.pal_container)?;
+    std::fs::write(format!("{}.png", args.out), png)?;
+    std::fs::write(format!("{}_temperature.csv", args.out), temperature_csv(&sim))?;
+    std::fs::write(format!("{}_log.txt", args.out), log)?;
+
+    println!(
+        "{} ticks, {:.2}s simulated, seed {} -> {}.png",
+        sim.tick, sim.sim_time_seconds, sim.seed, args.out
+    );
+    Ok(())
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(args) {
+        eprintln!("Error: {err}");
+        std::process::exit(1);
+    }
+}